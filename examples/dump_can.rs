@@ -1,17 +1,26 @@
-use ddose::{CanBus, CanInterface};
-use embedded_hal::can::Frame;
+//! Dumps all frames received on `vcan0` in the candump log format.
+//!
+//! The output can be replayed with `canplayer` from the can-utils.
+
+use std::time::SystemTime;
+
+use ddose::{
+    log::{LogEntry, LogWriter},
+    CanBus, CanInterface, CanTimestamping,
+};
 
 #[tokio::main]
-async fn main() -> std::io::Result<()> {
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let can_if = CanInterface::try_from("vcan0")?;
-    let mut can_bus = CanBus::open(&can_if)?;
+    let mut can_bus = CanBus::open_fd(&can_if)?;
+    can_bus.set_error_mask(libc::CAN_ERR_MASK)?;
+    can_bus.set_timestamping(CanTimestamping::Software)?;
 
+    let mut writer = LogWriter::new(std::io::stdout().lock());
     loop {
-        let frame = can_bus.read().await?;
-        let id = match frame.id() {
-            embedded_hal::can::Id::Standard(id) => id.as_raw() as u32,
-            embedded_hal::can::Id::Extended(id) => id.as_raw(),
-        };
-        println!("[{:03X}] {:02X?}", id, &frame.data()[..frame.dlc()]);
+        let (frame, meta) = can_bus.read_timestamped().await?;
+        let timestamp = meta.software_timestamp.unwrap_or_else(SystemTime::now);
+        writer.write_entry(&LogEntry::new(timestamp, "vcan0", frame))?;
+        writer.flush()?;
     }
 }
@@ -0,0 +1,25 @@
+//! Synchronous versions of the buses and connections.
+//!
+//! The types block the calling thread instead of requiring an async runtime.
+//! Reads and writes fail with `TimedOut` after the timeouts set on the type,
+//! by default they wait forever. Frames, filters, PDUs and NRCs are shared
+//! with the async versions.
+//!
+//! # Example:
+//! ```no_run
+//! # use ddose::{blocking::{IsotpConnection, UdsClient}, CanInterface};
+//! # fn main() -> Result<(), ddose::uds::UdsError> {
+//! let can_if = CanInterface::try_from("can0")?;
+//! let rx_id = embedded_hal::can::StandardId::new(0x7E8).unwrap();
+//! let tx_id = embedded_hal::can::StandardId::new(0x7E0).unwrap();
+//! let isotp_conn = IsotpConnection::open(&can_if, tx_id, rx_id)?;
+//!
+//! let mut uds_client = UdsClient::new(isotp_conn);
+//! uds_client.tester_present()?;
+//! # Ok(())
+//! # }
+//! ```
+
+pub use crate::can::blocking::CanBus;
+pub use crate::isotp::blocking::IsotpConnection;
+pub use crate::uds::blocking::UdsClient;
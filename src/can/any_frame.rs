@@ -0,0 +1,166 @@
+use embedded_hal::can::Frame;
+
+use super::{CanErrorFrame, CanFdFrame, CanFrame, CanXlFrame};
+
+/// Size of the receive buffer, which fits every kind of frame
+pub(super) const MAX_FRAME_SIZE: usize = libc::CANXL_MTU;
+
+/// Holds any kind of frame that can be received from a CAN bus.
+///
+/// When CAN FD frames are enabled on a bus, classic and CAN FD frames can be
+/// mixed on the same bus. This allows handling both of
+/// them in a single receive loop. The same applies to CAN XL frames.
+#[derive(Debug, Clone)]
+pub enum CanAnyFrame {
+    /// Classic CAN 2.0 frame with up to 8 bytes of payload
+    Classic(CanFrame),
+    /// CAN FD frame with up to 64 bytes of payload
+    Fd(CanFdFrame),
+    /// Error frame generated by the CAN controller driver
+    Error(CanErrorFrame),
+    /// CAN XL frame with up to 2048 bytes of payload
+    ///
+    /// The frame is boxed to keep the other variants small.
+    Xl(Box<CanXlFrame>),
+}
+
+impl CanAnyFrame {
+    /// Returns the identifier of the frame
+    ///
+    /// For error frames, the error class is returned as standard identifier.
+    /// CAN XL frames have no identifier but a priority, so `None` is returned
+    /// for them (see [CanXlFrame::prio()]).
+    pub fn id(&self) -> Option<embedded_hal::can::Id> {
+        match self {
+            CanAnyFrame::Classic(frame) => Some(frame.id()),
+            CanAnyFrame::Fd(frame) => Some(frame.id()),
+            CanAnyFrame::Error(frame) => Some(frame.frame().id()),
+            CanAnyFrame::Xl(_) => None,
+        }
+    }
+
+    /// Returns `true` if the frame uses an extended identifier
+    pub fn is_extended(&self) -> bool {
+        match self {
+            CanAnyFrame::Classic(frame) => frame.is_extended(),
+            CanAnyFrame::Fd(frame) => frame.is_extended(),
+            CanAnyFrame::Error(_) | CanAnyFrame::Xl(_) => false,
+        }
+    }
+
+    /// Returns the payload of the frame
+    ///
+    /// In contrast to [CanFrame::data()], only the bytes covered by the DLC are
+    /// returned.
+    pub fn data(&self) -> &[u8] {
+        match self {
+            CanAnyFrame::Classic(frame) => &frame.data()[..frame.dlc().min(libc::CAN_MAX_DLEN)],
+            CanAnyFrame::Fd(frame) => frame.data(),
+            CanAnyFrame::Error(frame) => frame.frame().data(),
+            CanAnyFrame::Xl(frame) => frame.data(),
+        }
+    }
+}
+
+impl From<CanFrame> for CanAnyFrame {
+    fn from(frame: CanFrame) -> Self {
+        CanAnyFrame::Classic(frame)
+    }
+}
+
+impl From<CanFdFrame> for CanAnyFrame {
+    fn from(frame: CanFdFrame) -> Self {
+        CanAnyFrame::Fd(frame)
+    }
+}
+
+impl From<CanErrorFrame> for CanAnyFrame {
+    fn from(frame: CanErrorFrame) -> Self {
+        CanAnyFrame::Error(frame)
+    }
+}
+
+impl From<CanXlFrame> for CanAnyFrame {
+    fn from(frame: CanXlFrame) -> Self {
+        CanAnyFrame::Xl(Box::new(frame))
+    }
+}
+
+/// Returns the frame if it is a classic CAN frame
+pub(super) fn classic_frame(frame: CanAnyFrame) -> Result<CanFrame, std::io::Error> {
+    match frame {
+        CanAnyFrame::Classic(frame) => Ok(frame),
+        CanAnyFrame::Fd(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Received CAN FD frame, use CanBus::read_any() instead",
+        )),
+        CanAnyFrame::Error(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Received CAN error frame, use CanBus::read_any() instead",
+        )),
+        CanAnyFrame::Xl(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Received CAN XL frame, use CanBus::read_any() instead",
+        )),
+    }
+}
+
+/// Returns the bytes of a classic frame as they are written to the socket
+pub(super) fn classic_frame_bytes(frame: &CanFrame) -> &[u8] {
+    // UNSAFE: The frame is a plain C struct
+    unsafe {
+        std::slice::from_raw_parts(
+            frame.inner() as *const libc::can_frame as *const u8,
+            libc::CAN_MTU,
+        )
+    }
+}
+
+/// Returns the bytes of a frame as they are written to the socket
+pub(super) fn frame_bytes(frame: &CanAnyFrame) -> Result<&[u8], std::io::Error> {
+    // UNSAFE: The frames are plain C structs
+    unsafe {
+        match frame {
+            CanAnyFrame::Classic(frame) => Ok(classic_frame_bytes(frame)),
+            CanAnyFrame::Fd(frame) => Ok(std::slice::from_raw_parts(
+                frame.inner() as *const libc::canfd_frame as *const u8,
+                libc::CANFD_MTU,
+            )),
+            CanAnyFrame::Error(_) => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Error frames can't be transmitted",
+            )),
+            CanAnyFrame::Xl(frame) => Ok(frame.as_bytes()),
+        }
+    }
+}
+
+/// Converts the raw bytes received from the socket into a frame
+pub(super) fn parse_frame(buffer: &[u8]) -> Result<CanAnyFrame, std::io::Error> {
+    // CAN XL frames have a variable length, which may match the size of the
+    // other frames. They are recognized by the XLF flag, which is located at
+    // the length of classic and CAN FD frames.
+    if buffer.len() > 4 && buffer[4] & libc::CANXL_XLF as u8 != 0 {
+        return CanXlFrame::from_bytes(buffer)
+            .map(CanAnyFrame::from)
+            .ok_or_else(|| std::io::Error::other("Received incomplete CAN XL frame"));
+    }
+
+    match buffer.len() {
+        libc::CAN_MTU => {
+            // UNSAFE: The buffer holds exactly CAN_MTU initialized bytes
+            let frame = unsafe { std::ptr::read_unaligned(buffer.as_ptr() as *const _) };
+            let frame = CanFrame::from_inner(frame);
+            match CanErrorFrame::from_frame(frame) {
+                Some(error_frame) => Ok(CanAnyFrame::Error(error_frame)),
+                None => Ok(CanAnyFrame::Classic(frame)),
+            }
+        }
+        libc::CANFD_MTU => {
+            // UNSAFE: The buffer holds exactly CANFD_MTU initialized bytes
+            let frame = unsafe { std::ptr::read_unaligned(buffer.as_ptr() as *const _) };
+            Ok(CanAnyFrame::Fd(CanFdFrame::from_inner(frame)))
+        }
+        _ => Err(std::io::Error::other("Received incomplete CAN frame")),
+    }
+}
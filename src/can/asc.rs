@@ -0,0 +1,803 @@
+//! Reading and writing Vector ASCII trace files (`.asc`).
+//!
+//! An ASC file starts with a header describing the number base and the
+//! timestamp mode, followed by one event per line:
+//! ```text
+//! date Mon Oct 16 10:00:00.000 am 2023
+//! base hex  timestamps absolute
+//! internal events logged
+//! Begin Triggerblock Mon Oct 16 10:00:00.000 am 2023
+//!    0.000000 Start of measurement
+//!    0.010000 1  123             Rx   d 4 DE AD BE EF
+//!    0.020000 2  18DAF110x       Tx   r 8
+//!    0.030000 CANFD   1 Rx        321  1 0 9  9 11 22 33 44 55 66 77 88 99 0 0 3000 0 0 0 0 0
+//!    0.040000 1  ErrorFrame
+//! End TriggerBlock
+//! ```
+//! Events which don't describe a frame (e.g., statistics) are skipped by the
+//! reader. Timestamps are returned relative to the start of the measurement,
+//! regardless of whether the file uses absolute or relative timestamps.
+//!
+//! # Example:
+//! ```no_run
+//! # use ddose::{asc::{AscDirection, AscWriter}, CanBus, CanInterface, CanTimestamping};
+//! # #[tokio::main] async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut can_bus = CanBus::open(&CanInterface::try_from("can0")?)?;
+//! can_bus.set_timestamping(CanTimestamping::Software)?;
+//!
+//! let file = std::fs::File::create("trace.asc")?;
+//! let mut writer = AscWriter::new(std::io::BufWriter::new(file), std::time::SystemTime::now())?;
+//! for _ in 0..100 {
+//!     let (frame, meta) = can_bus.read_timestamped().await?;
+//!     let timestamp = meta.software_timestamp.unwrap_or_else(std::time::SystemTime::now);
+//!     writer.write_frame(timestamp, 1, AscDirection::Rx, &frame)?;
+//! }
+//! writer.finish()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io::{BufRead, Write},
+    time::{Duration, SystemTime},
+};
+
+use embedded_hal::can::Frame;
+use thiserror::Error;
+
+use super::{log::parse_seconds, CanAnyFrame, CanErrorFrame, CanFdFrame, CanFrame};
+
+/// Flags of CANFD events (`EDL`, `BRS`, `ESI` and remote frames)
+const FLAG_REMOTE: u32 = 0x0010;
+const FLAG_EDL: u32 = 0x1000;
+const FLAG_BRS: u32 = 0x2000;
+const FLAG_ESI: u32 = 0x4000;
+
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+
+#[derive(Debug, Error)]
+pub enum AscError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid ASC line {line}: {reason}")]
+    InvalidLine { line: usize, reason: String },
+}
+
+/// Direction of a frame in the trace
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AscDirection {
+    /// Frame was received
+    Rx,
+    /// Frame was transmitted
+    Tx,
+}
+
+impl AscDirection {
+    fn parse(direction: &str) -> Option<Self> {
+        match direction {
+            "Rx" => Some(Self::Rx),
+            "Tx" => Some(Self::Tx),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Rx => "Rx",
+            Self::Tx => "Tx",
+        }
+    }
+}
+
+/// Frame event of an ASC trace
+#[derive(Clone)]
+pub struct AscEntry {
+    /// Time since the start of the measurement
+    pub timestamp: Duration,
+    /// Channel number (starting at 1)
+    pub channel: u8,
+    /// Whether the frame was received or transmitted
+    pub direction: AscDirection,
+    /// The frame
+    ///
+    /// ASC error frames carry no details, so they are returned as error
+    /// frame with the `CAN_ERR_BUSERROR` class.
+    pub frame: CanAnyFrame,
+}
+
+/// Reads the frame events of an ASC trace
+pub struct AscReader<R: BufRead> {
+    reader: R,
+    line: usize,
+    base: u32,
+    relative: bool,
+    last_timestamp: Duration,
+    start_time: Option<SystemTime>,
+}
+
+impl<R: BufRead> AscReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: 0,
+            base: 16,
+            relative: false,
+            last_timestamp: Duration::ZERO,
+            start_time: None,
+        }
+    }
+
+    /// Returns the start of the measurement from the `date` header
+    ///
+    /// The date is only available after the header was read and if it is
+    /// written in the english format. ASC files don't store a timezone, so
+    /// the date is interpreted as UTC.
+    pub fn start_time(&self) -> Option<SystemTime> {
+        self.start_time
+    }
+
+    /// Reads the next frame event
+    ///
+    /// Returns `None` at the end of the trace.
+    pub fn read_entry(&mut self) -> Result<Option<AscEntry>, AscError> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+
+            match self.parse_line(&line) {
+                Ok(Some(entry)) => return Ok(Some(entry)),
+                Ok(None) => continue,
+                Err(reason) => {
+                    return Err(AscError::InvalidLine {
+                        line: self.line,
+                        reason,
+                    })
+                }
+            }
+        }
+    }
+
+    fn parse_line(&mut self, line: &str) -> Result<Option<AscEntry>, String> {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let Some(first) = tokens.first() else {
+            return Ok(None);
+        };
+
+        match *first {
+            "date" => {
+                self.start_time = parse_date(&tokens[1..]);
+                return Ok(None);
+            }
+            "base" => {
+                self.base = match tokens.get(1) {
+                    Some(&"dec") => 10,
+                    _ => 16,
+                };
+                self.relative = tokens.get(3) == Some(&"relative");
+                return Ok(None);
+            }
+            _ => {}
+        }
+
+        // Everything else without a leading timestamp (comments, begin and
+        // end of the trigger block, ...) is skipped
+        let Ok(timestamp) = parse_seconds(first) else {
+            return Ok(None);
+        };
+        let timestamp = match self.relative {
+            true => self
+                .last_timestamp
+                .checked_add(timestamp)
+                .ok_or_else(|| format!("Timestamp '{}' is out of range", first))?,
+            false => timestamp,
+        };
+        self.last_timestamp = timestamp;
+
+        let event = match tokens.get(1) {
+            Some(&"CANFD") => parse_fd_event(&tokens[2..], self.base)?,
+            Some(_) => parse_can_event(&tokens[1..], self.base)?,
+            None => None,
+        };
+
+        Ok(event.map(|(channel, direction, frame)| AscEntry {
+            timestamp,
+            channel,
+            direction,
+            frame,
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for AscReader<R> {
+    type Item = Result<AscEntry, AscError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+type Event = (u8, AscDirection, CanAnyFrame);
+
+fn parse_id(id: &str, base: u32) -> Result<libc::canid_t, String> {
+    let invalid = || format!("Invalid identifier '{}'", id);
+
+    let (raw_id, extended) = match id.strip_suffix(['x', 'X']) {
+        Some(id) => (id, true),
+        None => (id, false),
+    };
+    let raw_id = u32::from_str_radix(raw_id, base).map_err(|_| invalid())?;
+
+    match extended {
+        true if raw_id <= libc::CAN_EFF_MASK => Ok(raw_id | libc::CAN_EFF_FLAG),
+        false if raw_id <= libc::CAN_SFF_MASK => Ok(raw_id),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_data(data: &[&str], len: usize, base: u32) -> Result<Vec<u8>, String> {
+    if data.len() < len {
+        return Err(format!("Expected {} data bytes", len));
+    }
+
+    data[..len]
+        .iter()
+        .map(|byte| {
+            u8::from_str_radix(byte, base).map_err(|_| format!("Invalid data byte '{}'", byte))
+        })
+        .collect()
+}
+
+fn error_frame() -> CanAnyFrame {
+    let mut c_can_frame: libc::can_frame = unsafe { std::mem::zeroed() };
+    c_can_frame.can_id = libc::CAN_ERR_FLAG | libc::CAN_ERR_BUSERROR;
+    c_can_frame.can_dlc = libc::CAN_ERR_DLC as u8;
+
+    // UNWRAP: The error flag is set
+    CanAnyFrame::Error(CanErrorFrame::from_frame(CanFrame::from_inner(c_can_frame)).unwrap())
+}
+
+fn classic_frame(can_id: libc::canid_t, dlc: u8, data: &[u8], remote: bool) -> CanAnyFrame {
+    let mut c_can_frame: libc::can_frame = unsafe { std::mem::zeroed() };
+    c_can_frame.can_id = can_id;
+    c_can_frame.can_dlc = dlc.min(libc::CAN_MAX_DLEN as u8);
+    c_can_frame.data[..data.len()].copy_from_slice(data);
+    if remote {
+        c_can_frame.can_id |= libc::CAN_RTR_FLAG;
+    }
+
+    CanAnyFrame::Classic(CanFrame::from_inner(c_can_frame))
+}
+
+/// Parses classic CAN events: `<channel> <id> <dir> d <dlc> <data>`,
+/// `<channel> <id> <dir> r [<dlc>]` or `<channel> ErrorFrame`
+fn parse_can_event(tokens: &[&str], base: u32) -> Result<Option<Event>, String> {
+    let Ok(channel) = tokens[0].parse::<u8>() else {
+        return Ok(None);
+    };
+
+    if tokens.get(1) == Some(&"ErrorFrame") {
+        return Ok(Some((channel, AscDirection::Rx, error_frame())));
+    }
+
+    let (Some(id), Some(direction), Some(kind)) = (tokens.get(1), tokens.get(2), tokens.get(3))
+    else {
+        return Ok(None);
+    };
+    let Some(direction) = AscDirection::parse(direction) else {
+        return Ok(None);
+    };
+    let can_id = parse_id(id, base)?;
+
+    let frame = match *kind {
+        "d" => {
+            let dlc = tokens
+                .get(4)
+                .and_then(|dlc| u8::from_str_radix(dlc, 16).ok())
+                .filter(|dlc| *dlc <= 0x0F)
+                .ok_or("Invalid DLC")?;
+            let len = (dlc as usize).min(libc::CAN_MAX_DLEN);
+            let data = parse_data(&tokens[5..], len, base)?;
+            classic_frame(can_id, dlc, &data, false)
+        }
+        "r" => {
+            let dlc = tokens
+                .get(4)
+                .and_then(|dlc| u8::from_str_radix(dlc, 16).ok())
+                .unwrap_or(0);
+            classic_frame(can_id, dlc, &[], true)
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some((channel, direction, frame)))
+}
+
+/// Parses CANFD events: `<channel> <dir> <id> [<name>] <brs> <esi> <dlc>
+/// <len> <data> <duration> <length> <flags> ...`
+fn parse_fd_event(tokens: &[&str], base: u32) -> Result<Option<Event>, String> {
+    let (Some(channel), Some(direction), Some(id)) = (tokens.first(), tokens.get(1), tokens.get(2))
+    else {
+        return Err("Incomplete CANFD event".to_string());
+    };
+    let channel = channel
+        .parse::<u8>()
+        .map_err(|_| format!("Invalid channel '{}'", channel))?;
+    let direction = AscDirection::parse(direction)
+        .ok_or_else(|| format!("Invalid direction '{}'", direction))?;
+
+    if *id == "ErrorFrame" {
+        return Ok(Some((channel, direction, error_frame())));
+    }
+    let can_id = parse_id(id, base)?;
+
+    // The symbolic name of the message is optional
+    let offset = match tokens.get(3) {
+        Some(&"0") | Some(&"1") => 3,
+        _ => 4,
+    };
+    let fields = &tokens[offset.min(tokens.len())..];
+    if fields.len() < 4 {
+        return Err("Incomplete CANFD event".to_string());
+    }
+
+    let brs = fields[0] == "1";
+    let esi = fields[1] == "1";
+    let dlc = u8::from_str_radix(fields[2], 16).map_err(|_| "Invalid DLC")?;
+    let len: usize = fields[3].parse().map_err(|_| "Invalid data length")?;
+    if len > libc::CANFD_MAX_DLEN {
+        return Err(format!("Invalid data length {}", len));
+    }
+    let data = parse_data(&fields[4..], len, base)?;
+
+    // Classic frames may be logged as CANFD events, which is only visible
+    // in the flags behind the data
+    let flags = fields
+        .get(4 + len + 2)
+        .and_then(|flags| u32::from_str_radix(flags, 16).ok())
+        .unwrap_or(FLAG_EDL);
+
+    let frame = if flags & FLAG_EDL == 0 {
+        if len > libc::CAN_MAX_DLEN {
+            return Err(format!("Invalid data length {}", len));
+        }
+        classic_frame(can_id, dlc, &data, flags & FLAG_REMOTE != 0)
+    } else {
+        let mut c_canfd_frame: libc::canfd_frame = unsafe { std::mem::zeroed() };
+        c_canfd_frame.can_id = can_id;
+        c_canfd_frame.len = len as u8;
+        c_canfd_frame.data[..len].copy_from_slice(&data);
+        if brs {
+            c_canfd_frame.flags |= libc::CANFD_BRS as u8;
+        }
+        if esi {
+            c_canfd_frame.flags |= libc::CANFD_ESI as u8;
+        }
+        CanAnyFrame::Fd(CanFdFrame::from_inner(c_canfd_frame))
+    };
+
+    Ok(Some((channel, direction, frame)))
+}
+
+/// Days since 1970-01-01 of a date in the proleptic gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+
+    era * 146097 + day_of_era - 719468
+}
+
+/// Date in the proleptic gregorian calendar of the days since 1970-01-01
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (year, month, day)
+}
+
+/// Parses dates like `Mon Oct 16 10:00:00.000 am 2023`
+///
+/// The weekday, the milliseconds and the `am`/`pm` marker are optional.
+fn parse_date(tokens: &[&str]) -> Option<SystemTime> {
+    let tokens = match tokens.first() {
+        Some(weekday) if WEEKDAYS.contains(weekday) => &tokens[1..],
+        _ => tokens,
+    };
+
+    let month = MONTHS
+        .iter()
+        .position(|month| Some(month) == tokens.first())? as u32
+        + 1;
+    let day: u32 = tokens
+        .get(1)?
+        .parse()
+        .ok()
+        .filter(|day| (1..=31).contains(day))?;
+
+    let (time, fraction) = tokens.get(2)?.split_once('.').unwrap_or((tokens[2], "0"));
+    let mut time = time.split(':').map(|part| part.parse::<u64>().ok());
+    let (mut hour, minute, second) = (time.next()??, time.next()??, time.next()??);
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    let fraction = parse_seconds(&format!("0.{}", fraction)).ok()?;
+
+    let year = match tokens.get(3)?.to_ascii_lowercase().as_str() {
+        "am" => {
+            hour %= 12;
+            tokens.get(4)?
+        }
+        "pm" => {
+            hour = hour % 12 + 12;
+            tokens.get(4)?
+        }
+        _ => tokens[3],
+    };
+    // Bounding the year keeps the calculation of the days from overflowing
+    let year: i64 = year
+        .parse()
+        .ok()
+        .filter(|year| (1970..=9999).contains(year))?;
+
+    let days = u64::try_from(days_from_civil(year, month, day)).ok()?;
+    let secs = days
+        .checked_mul(86400)?
+        .checked_add(hour * 3600 + minute * 60 + second)?;
+    SystemTime::UNIX_EPOCH
+        .checked_add(Duration::from_secs(secs))?
+        .checked_add(fraction)
+}
+
+/// Formats dates like `Mon Oct 16 10:00:00.000 am 2023` (UTC)
+fn format_date(time: SystemTime) -> String {
+    let time = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .unwrap_or_default();
+    let days = (time.as_secs() / 86400) as i64;
+    let secs = time.as_secs() % 86400;
+    let (year, month, day) = civil_from_days(days);
+
+    let hour = secs / 3600;
+    let (hour_12, marker) = match hour {
+        0 => (12, "am"),
+        1..=11 => (hour, "am"),
+        12 => (12, "pm"),
+        _ => (hour - 12, "pm"),
+    };
+
+    format!(
+        "{} {} {:02} {:02}:{:02}:{:02}.{:03} {} {}",
+        WEEKDAYS[(days + 4).rem_euclid(7) as usize],
+        MONTHS[month as usize - 1],
+        day,
+        hour_12,
+        secs / 60 % 60,
+        secs % 60,
+        time.subsec_millis(),
+        marker,
+        year
+    )
+}
+
+fn format_timestamp(timestamp: Duration) -> String {
+    format!("{}.{:06}", timestamp.as_secs(), timestamp.subsec_micros())
+}
+
+fn format_id(can_id: libc::canid_t) -> String {
+    match can_id & libc::CAN_EFF_FLAG != 0 {
+        true => format!("{:X}x", can_id & libc::CAN_EFF_MASK),
+        false => format!("{:X}", can_id & libc::CAN_SFF_MASK),
+    }
+}
+
+fn format_data(data: &[u8]) -> String {
+    data.iter()
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Writes frames as ASC trace with absolute timestamps
+///
+/// The header is written on creation, [AscWriter::finish()] must be called to
+/// close the trigger block.
+pub struct AscWriter<W: Write> {
+    writer: W,
+    start_time: SystemTime,
+}
+
+impl<W: Write> AscWriter<W> {
+    /// Creates a writer and writes the header of the trace
+    ///
+    /// The start time is the reference for the timestamps of
+    /// [AscWriter::write_frame()].
+    pub fn new(mut writer: W, start_time: SystemTime) -> Result<Self, AscError> {
+        let date = format_date(start_time);
+        writeln!(writer, "date {}", date)?;
+        writeln!(writer, "base hex  timestamps absolute")?;
+        writeln!(writer, "internal events logged")?;
+        writeln!(writer, "Begin Triggerblock {}", date)?;
+        writeln!(
+            writer,
+            "{:>11} Start of measurement",
+            format_timestamp(Duration::ZERO)
+        )?;
+
+        Ok(Self { writer, start_time })
+    }
+
+    /// Writes a single frame event
+    pub fn write_entry(&mut self, entry: &AscEntry) -> Result<(), AscError> {
+        let timestamp = format_timestamp(entry.timestamp);
+        let direction = entry.direction.as_str();
+
+        match &entry.frame {
+            CanAnyFrame::Classic(frame) => {
+                let inner = frame.inner();
+                let id = format_id(inner.can_id);
+                match frame.is_remote_frame() {
+                    true => writeln!(
+                        self.writer,
+                        "{:>11} {:<2} {:<15} {:<4} r {:X}",
+                        timestamp, entry.channel, id, direction, inner.can_dlc
+                    )?,
+                    false => writeln!(
+                        self.writer,
+                        "{:>11} {:<2} {:<15} {:<4} d {:X} {}",
+                        timestamp,
+                        entry.channel,
+                        id,
+                        direction,
+                        inner.can_dlc,
+                        format_data(&frame.data()[..frame.dlc().min(libc::CAN_MAX_DLEN)])
+                    )?,
+                }
+            }
+            CanAnyFrame::Fd(frame) => {
+                let mut flags = FLAG_EDL;
+                if frame.is_brs() {
+                    flags |= FLAG_BRS;
+                }
+                if frame.is_esi() {
+                    flags |= FLAG_ESI;
+                }
+
+                writeln!(
+                    self.writer,
+                    "{:>11} CANFD {:>3} {:<4} {:>8}  {} {} {:X} {:>2} {} {:>8} {:>4} {:>8X} {:>8} {:>8} {:>8} {:>8} {:>8}",
+                    timestamp,
+                    entry.channel,
+                    direction,
+                    format_id(frame.inner().can_id),
+                    frame.is_brs() as u8,
+                    frame.is_esi() as u8,
+                    frame.dlc(),
+                    frame.len(),
+                    format_data(frame.data()),
+                    0,
+                    0,
+                    flags,
+                    0,
+                    0,
+                    0,
+                    0,
+                    0
+                )?;
+            }
+            CanAnyFrame::Error(_) => {
+                writeln!(
+                    self.writer,
+                    "{:>11} {:<2} ErrorFrame",
+                    timestamp, entry.channel
+                )?;
+            }
+            CanAnyFrame::Xl(_) => {
+                return Err(AscError::Io(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "CAN XL frames can't be written to ASC traces",
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a frame received at the given time
+    pub fn write_frame(
+        &mut self,
+        timestamp: SystemTime,
+        channel: u8,
+        direction: AscDirection,
+        frame: &CanAnyFrame,
+    ) -> Result<(), AscError> {
+        let entry = AscEntry {
+            timestamp: timestamp
+                .duration_since(self.start_time)
+                .unwrap_or_default(),
+            channel,
+            direction,
+            frame: frame.clone(),
+        };
+        self.write_entry(&entry)
+    }
+
+    /// Closes the trigger block and returns the underlying writer
+    pub fn finish(mut self) -> Result<W, AscError> {
+        writeln!(self.writer, "End TriggerBlock")?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use embedded_hal::can::Frame;
+
+    use super::{format_date, parse_date, AscDirection, AscReader, AscWriter};
+    use crate::can::CanAnyFrame;
+
+    const TRACE: &str = "date Mon Oct 16 10:00:00.000 am 2023
+base hex  timestamps absolute
+internal events logged
+// version 9.0.0
+Begin Triggerblock Mon Oct 16 10:00:00.000 am 2023
+   0.000000 Start of measurement
+   0.010000 1  123             Rx   d 4 DE AD BE EF  Length = 0 BitCount = 0 ID = 291
+   0.020000 2  18DAF110x       Tx   r 8
+   0.025000 1  Statistic: D 0 R 0 XD 0 XR 0 E 0 O 0 B 0.00%
+   0.030000 CANFD   1 Rx        321                                   1 0 9  9 11 22 33 44 55 66 77 88 99        0    0     3000        0        0        0        0        0
+   0.035000 CANFD   1 Tx        7DF  Request                          0 0 2  2 01 0D        0    0        0        0        0        0        0        0
+   0.040000 1  ErrorFrame
+End TriggerBlock
+";
+
+    #[test]
+    fn reads_trace() {
+        let mut reader = AscReader::new(TRACE.as_bytes());
+        let entries: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(entries.len(), 5);
+        assert_eq!(
+            reader.start_time(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1697450400))
+        );
+
+        assert_eq!(entries[0].timestamp, Duration::from_millis(10));
+        assert_eq!(entries[0].channel, 1);
+        assert_eq!(entries[0].direction, AscDirection::Rx);
+        assert_eq!(entries[0].frame.data(), [0xDE, 0xAD, 0xBE, 0xEF]);
+
+        match entries[1].frame {
+            CanAnyFrame::Classic(frame) => {
+                assert!(frame.is_remote_frame());
+                assert!(frame.is_extended());
+                assert_eq!(frame.dlc(), 8);
+            }
+            _ => panic!("Expected classic frame"),
+        }
+        assert_eq!(entries[1].direction, AscDirection::Tx);
+
+        match entries[2].frame {
+            CanAnyFrame::Fd(frame) => {
+                assert!(frame.is_brs());
+                assert_eq!(frame.len(), 9);
+            }
+            _ => panic!("Expected CAN FD frame"),
+        }
+
+        // Classic frame logged as CANFD event with a symbolic name
+        match entries[3].frame {
+            CanAnyFrame::Classic(frame) => assert_eq!(&frame.data()[..2], [0x01, 0x0D]),
+            _ => panic!("Expected classic frame"),
+        }
+
+        assert!(matches!(entries[4].frame, CanAnyFrame::Error(_)));
+    }
+
+    #[test]
+    fn reads_relative_decimal_trace() {
+        let trace = "base dec  timestamps relative
+   0.100000 1  291             Rx   d 2 10 255
+   0.200000 1  291             Rx   d 1 0
+";
+        let entries: Vec<_> = AscReader::new(trace.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(entries[0].frame.id(), entries[1].frame.id());
+        assert_eq!(entries[0].frame.data(), [10, 255]);
+        assert_eq!(entries[1].timestamp, Duration::from_millis(300));
+    }
+
+    #[test]
+    fn rejects_invalid_frames() {
+        let trace = "   0.100000 1  123             Rx   d 4 00 11\n";
+        assert!(AscReader::new(trace.as_bytes()).next().unwrap().is_err());
+
+        let trace = "   0.100000 1  800             Rx   d 0\n";
+        assert!(AscReader::new(trace.as_bytes()).next().unwrap().is_err());
+    }
+
+    #[test]
+    fn writes_and_reads_trace() {
+        let entries: Vec<_> = AscReader::new(TRACE.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+
+        let start_time = SystemTime::UNIX_EPOCH + Duration::from_secs(1697450400);
+        let mut writer = AscWriter::new(Vec::new(), start_time).unwrap();
+        for entry in &entries {
+            writer.write_entry(entry).unwrap();
+        }
+        let trace = writer.finish().unwrap();
+
+        let mut reader = AscReader::new(trace.as_slice());
+        let written: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+        assert_eq!(reader.start_time(), Some(start_time));
+        assert_eq!(written.len(), entries.len());
+        for (written, entry) in written.iter().zip(&entries) {
+            assert_eq!(written.timestamp, entry.timestamp);
+            assert_eq!(written.channel, entry.channel);
+            assert_eq!(written.direction, entry.direction);
+            assert_eq!(written.frame.id(), entry.frame.id());
+            assert_eq!(written.frame.data(), entry.frame.data());
+        }
+    }
+
+    #[test]
+    fn formats_dates() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1697464800123);
+        assert_eq!(format_date(time), "Mon Oct 16 02:00:00.123 pm 2023");
+
+        let tokens: Vec<_> = "Mon Oct 16 02:00:00.123 pm 2023".split(' ').collect();
+        assert_eq!(parse_date(&tokens), Some(time));
+        let tokens: Vec<_> = "Oct 16 14:00:00 2023".split(' ').collect();
+        assert_eq!(
+            parse_date(&tokens),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1697464800))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_dates() {
+        for date in [
+            "Oct 16 18446744073709551615:00:00 2023",
+            "Oct 16 24:00:00 2023",
+            "Oct 16 10:60:00 2023",
+            "Oct 32 10:00:00 2023",
+            "Oct 16 10:00:00 9223372036854775807",
+        ] {
+            let tokens: Vec<_> = date.split(' ').collect();
+            assert_eq!(parse_date(&tokens), None, "{}", date);
+        }
+    }
+
+    #[test]
+    fn rejects_overflowing_relative_timestamps() {
+        let trace = "base hex timestamps relative\n\
+            18446744073709551615.0 1 123 Rx d 0\n\
+            18446744073709551615.0 1 123 Rx d 0\n";
+        let mut reader = AscReader::new(trace.as_bytes());
+        assert!(reader.read_entry().unwrap().is_some());
+        assert!(reader.read_entry().is_err());
+    }
+}
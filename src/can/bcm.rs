@@ -0,0 +1,542 @@
+use std::{
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use embedded_hal::can::Id as CanId;
+
+use crate::socket::{CanInterface, CanSocket};
+
+use super::{frame::id_from_raw, frame::raw_can_id, CanAnyFrame, CanFdFrame, CanFrame};
+
+const BCM_HEAD_SIZE: usize = std::mem::size_of::<libc::bcm_msg_head>();
+
+/// Notification sent by the broadcast manager
+#[derive(Clone)]
+pub enum CanBcmEvent {
+    /// The content of a monitored frame changed (`RX_CHANGED`)
+    ///
+    /// The first reception of a monitored frame is reported as change as well.
+    Changed(CanAnyFrame),
+    /// A monitored frame wasn't received within the timeout (`RX_TIMEOUT`)
+    Timeout(CanId),
+    /// The counted frames of [CanBcm::tx_setup_count()] were sent (`TX_EXPIRED`)
+    TxExpired(CanId),
+}
+
+/// Provides access to the broadcast manager (`CAN_BCM`) of the kernel.
+///
+/// The broadcast manager transmits frames cyclically with kernel timers, so
+/// the cycle time doesn't depend on the scheduling of the application. It can
+/// also monitor received frames and notify about content changes and timeouts.
+/// The notifications are received with [CanBcm::read_event()] or by using the
+/// [CanBcm] as [Stream](futures_core::Stream).
+///
+/// Each job is identified by the CAN identifier of its frames.
+pub struct CanBcm {
+    socket: CanSocket,
+}
+
+impl CanBcm {
+    /// Opens a broadcast manager socket on the CAN interface
+    pub fn open(can_if: &CanInterface) -> Result<Self, std::io::Error> {
+        let socket = CanSocket::create(libc::SOCK_DGRAM, libc::CAN_BCM)?;
+        socket.connect(can_if)?;
+        socket.set_nonblocking()?;
+
+        Ok(Self { socket })
+    }
+
+    /// Starts the cyclic transmission of frames
+    ///
+    /// All frames must have the same identifier. If multiple frames are given,
+    /// they are transmitted one after another in each cycle (multiplexed
+    /// transmission). An existing job for the identifier is replaced.
+    pub async fn tx_setup(
+        &mut self,
+        frames: &[CanFrame],
+        interval: Duration,
+    ) -> Result<(), std::io::Error> {
+        self.setup(frames, 0, Duration::ZERO, interval).await
+    }
+
+    /// Starts a cyclic transmission which begins with a limited number of
+    /// frames
+    ///
+    /// First, `count` frames are transmitted every `count_interval`. When they
+    /// are sent, [CanBcmEvent::TxExpired] is reported and the transmission
+    /// continues every `interval`. A zero `interval` stops the transmission
+    /// after the counted frames. See [CanBcm::tx_setup()].
+    pub async fn tx_setup_count(
+        &mut self,
+        frames: &[CanFrame],
+        count: u32,
+        count_interval: Duration,
+        interval: Duration,
+    ) -> Result<(), std::io::Error> {
+        self.setup(frames, count, count_interval, interval).await
+    }
+
+    /// Starts the cyclic transmission of CAN FD frames
+    ///
+    /// See [CanBcm::tx_setup()].
+    pub async fn tx_setup_fd(
+        &mut self,
+        frames: &[CanFdFrame],
+        interval: Duration,
+    ) -> Result<(), std::io::Error> {
+        self.setup(frames, 0, Duration::ZERO, interval).await
+    }
+
+    /// Starts a cyclic transmission of CAN FD frames which begins with a
+    /// limited number of frames
+    ///
+    /// See [CanBcm::tx_setup_count()].
+    pub async fn tx_setup_fd_count(
+        &mut self,
+        frames: &[CanFdFrame],
+        count: u32,
+        count_interval: Duration,
+        interval: Duration,
+    ) -> Result<(), std::io::Error> {
+        self.setup(frames, count, count_interval, interval).await
+    }
+
+    /// Replaces the payload of a running cyclic transmission
+    ///
+    /// The frames are replaced atomically, so a cycle never contains a mix of
+    /// old and new frames. The cycle time isn't changed. If `announce` is set,
+    /// the new frames are transmitted immediately once.
+    pub async fn tx_update(
+        &mut self,
+        frames: &[CanFrame],
+        announce: bool,
+    ) -> Result<(), std::io::Error> {
+        self.update(frames, announce).await
+    }
+
+    /// Replaces the payload of a running cyclic transmission of CAN FD frames
+    ///
+    /// See [CanBcm::tx_update()].
+    pub async fn tx_update_fd(
+        &mut self,
+        frames: &[CanFdFrame],
+        announce: bool,
+    ) -> Result<(), std::io::Error> {
+        self.update(frames, announce).await
+    }
+
+    async fn setup<F: BcmFrame>(
+        &mut self,
+        frames: &[F],
+        count: u32,
+        count_interval: Duration,
+        interval: Duration,
+    ) -> Result<(), std::io::Error> {
+        let mut flags = libc::SETTIMER | libc::STARTTIMER | F::FLAGS;
+        if count > 0 {
+            flags |= libc::TX_COUNTEVT;
+        }
+
+        let mut head = Self::msg_head(
+            libc::TX_SETUP,
+            flags,
+            Self::frames_id(frames)?,
+            count_interval,
+            interval,
+        );
+        head.count = count;
+        self.send(head, frames).await
+    }
+
+    async fn update<F: BcmFrame>(
+        &mut self,
+        frames: &[F],
+        announce: bool,
+    ) -> Result<(), std::io::Error> {
+        let flags = match announce {
+            true => libc::TX_ANNOUNCE | F::FLAGS,
+            false => F::FLAGS,
+        };
+        let head = Self::msg_head(
+            libc::TX_SETUP,
+            flags,
+            Self::frames_id(frames)?,
+            Duration::ZERO,
+            Duration::ZERO,
+        );
+        self.send(head, frames).await
+    }
+
+    /// Stops the cyclic transmission for the identifier
+    pub async fn tx_delete(&mut self, id: impl Into<CanId>) -> Result<(), std::io::Error> {
+        self.delete(libc::TX_DELETE, 0, id).await
+    }
+
+    /// Stops the cyclic transmission of CAN FD frames for the identifier
+    ///
+    /// The kernel keeps the jobs of classic and CAN FD frames apart, even if
+    /// they use the same identifier.
+    pub async fn tx_delete_fd(&mut self, id: impl Into<CanId>) -> Result<(), std::io::Error> {
+        self.delete(libc::TX_DELETE, libc::CAN_FD_FRAME, id).await
+    }
+
+    /// Starts monitoring received frames with the identifier
+    ///
+    /// If `mask` is given, only changes of the payload bits set in the mask are
+    /// reported. Otherwise, every received frame is reported as change. A
+    /// `timeout` of [Duration::ZERO] disables the timeout monitoring. The
+    /// `throttle` limits the rate of change notifications.
+    pub async fn rx_setup(
+        &mut self,
+        id: impl Into<CanId>,
+        mask: Option<&[u8]>,
+        timeout: Duration,
+        throttle: Duration,
+    ) -> Result<(), std::io::Error> {
+        let can_id = raw_can_id(id);
+
+        let mut flags = libc::SETTIMER;
+        if timeout.is_zero() {
+            flags |= libc::RX_NO_AUTOTIMER;
+        }
+
+        let frames = match mask {
+            Some(mask) => {
+                let mask_frame =
+                    <CanFrame as embedded_hal::can::Frame>::new(id_from_raw(can_id), mask)
+                        .ok_or_else(|| {
+                            std::io::Error::new(
+                                std::io::ErrorKind::InvalidInput,
+                                "Mask is too long",
+                            )
+                        })?;
+                vec![mask_frame]
+            }
+            None => {
+                flags |= libc::RX_FILTER_ID;
+                Vec::new()
+            }
+        };
+
+        let head = Self::msg_head(libc::RX_SETUP, flags, can_id, timeout, throttle);
+        self.send(head, &frames).await
+    }
+
+    /// Stops monitoring received frames with the identifier
+    pub async fn rx_delete(&mut self, id: impl Into<CanId>) -> Result<(), std::io::Error> {
+        self.delete(libc::RX_DELETE, 0, id).await
+    }
+
+    async fn delete(
+        &mut self,
+        opcode: u32,
+        flags: u32,
+        id: impl Into<CanId>,
+    ) -> Result<(), std::io::Error> {
+        let head = Self::msg_head(
+            opcode,
+            flags,
+            raw_can_id(id),
+            Duration::ZERO,
+            Duration::ZERO,
+        );
+        self.send::<CanFrame>(head, &[]).await
+    }
+
+    /// Polls for the next notification of the broadcast manager
+    pub fn poll_read_event(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<CanBcmEvent, std::io::Error>> {
+        let mut buffer = [0u8; BCM_HEAD_SIZE + libc::CANFD_MTU];
+
+        loop {
+            let (bytes_read, _) = match self.socket.poll_recv(cx, &mut buffer) {
+                Poll::Ready(result) => result?,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            if let Some(event) = Self::parse_event(&buffer[..bytes_read])? {
+                return Poll::Ready(Ok(event));
+            }
+        }
+    }
+
+    /// Reads the next notification of the broadcast manager
+    pub async fn read_event(&mut self) -> Result<CanBcmEvent, std::io::Error> {
+        std::future::poll_fn(|cx| self.poll_read_event(cx)).await
+    }
+
+    /// Converts a received message into an event
+    ///
+    /// Returns `None` for messages which aren't notifications.
+    fn parse_event(buffer: &[u8]) -> Result<Option<CanBcmEvent>, std::io::Error> {
+        if buffer.len() < BCM_HEAD_SIZE {
+            return Err(std::io::Error::other("Received incomplete BCM message"));
+        }
+
+        // UNSAFE: The buffer holds at least BCM_HEAD_SIZE initialized bytes
+        let head: libc::bcm_msg_head =
+            unsafe { std::ptr::read_unaligned(buffer.as_ptr() as *const _) };
+        let frames = &buffer[BCM_HEAD_SIZE..];
+
+        let event = match head.opcode {
+            libc::RX_CHANGED if head.flags & libc::CAN_FD_FRAME != 0 => {
+                if frames.len() < libc::CANFD_MTU {
+                    return Err(std::io::Error::other("Received incomplete CAN FD frame"));
+                }
+                // UNSAFE: The buffer holds at least CANFD_MTU initialized bytes
+                let frame = unsafe { std::ptr::read_unaligned(frames.as_ptr() as *const _) };
+                CanBcmEvent::Changed(CanAnyFrame::Fd(CanFdFrame::from_inner(frame)))
+            }
+            libc::RX_CHANGED => {
+                if frames.len() < libc::CAN_MTU {
+                    return Err(std::io::Error::other("Received incomplete CAN frame"));
+                }
+                // UNSAFE: The buffer holds at least CAN_MTU initialized bytes
+                let frame = unsafe { std::ptr::read_unaligned(frames.as_ptr() as *const _) };
+                CanBcmEvent::Changed(CanAnyFrame::Classic(CanFrame::from_inner(frame)))
+            }
+            libc::RX_TIMEOUT => CanBcmEvent::Timeout(id_from_raw(head.can_id)),
+            libc::TX_EXPIRED => CanBcmEvent::TxExpired(id_from_raw(head.can_id)),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(event))
+    }
+
+    /// Returns the common identifier of the frames
+    fn frames_id<F: BcmFrame>(frames: &[F]) -> Result<libc::canid_t, std::io::Error> {
+        let can_id = match frames.first() {
+            Some(frame) => frame.can_id(),
+            None => {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::InvalidInput,
+                    "At least one frame is required",
+                ))
+            }
+        };
+
+        if frames.iter().any(|frame| frame.can_id() != can_id) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "All frames must have the same identifier",
+            ));
+        }
+
+        Ok(can_id)
+    }
+
+    fn msg_head(
+        opcode: u32,
+        flags: u32,
+        can_id: libc::canid_t,
+        ival1: Duration,
+        ival2: Duration,
+    ) -> libc::bcm_msg_head {
+        // UNSAFE: The C struct layout needs to be zeroed in order for the padding
+        // and reserved values to be valid
+        let mut head: libc::bcm_msg_head = unsafe { std::mem::zeroed() };
+        head.opcode = opcode;
+        head.flags = flags;
+        head.can_id = can_id;
+        head.ival1 = Self::timeval(ival1);
+        head.ival2 = Self::timeval(ival2);
+
+        head
+    }
+
+    fn timeval(duration: Duration) -> libc::bcm_timeval {
+        libc::bcm_timeval {
+            tv_sec: duration.as_secs() as _,
+            tv_usec: duration.subsec_micros() as _,
+        }
+    }
+
+    /// Sends a message consisting of the head and the frames to the kernel
+    async fn send<F: BcmFrame>(
+        &mut self,
+        mut head: libc::bcm_msg_head,
+        frames: &[F],
+    ) -> Result<(), std::io::Error> {
+        head.nframes = frames.len() as u32;
+
+        let mut buffer = Vec::with_capacity(BCM_HEAD_SIZE + frames.len() * F::SIZE);
+        // UNSAFE: The head is a plain C struct
+        let head_bytes = unsafe {
+            std::slice::from_raw_parts(
+                &head as *const libc::bcm_msg_head as *const u8,
+                BCM_HEAD_SIZE,
+            )
+        };
+        buffer.extend_from_slice(head_bytes);
+        for frame in frames {
+            buffer.extend_from_slice(frame.bytes());
+        }
+
+        let bytes_written =
+            std::future::poll_fn(|cx| self.socket.poll_write_bytes(cx, &buffer)).await?;
+        if bytes_written != buffer.len() {
+            return Err(std::io::Error::other("Transmitted incomplete BCM message"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Frames which can be transmitted by the broadcast manager
+trait BcmFrame {
+    /// Flags of the message head for this kind of frame
+    const FLAGS: u32;
+    /// Size of the frame in the message
+    const SIZE: usize;
+
+    fn can_id(&self) -> libc::canid_t;
+    fn bytes(&self) -> &[u8];
+}
+
+impl BcmFrame for CanFrame {
+    const FLAGS: u32 = 0;
+    const SIZE: usize = libc::CAN_MTU;
+
+    fn can_id(&self) -> libc::canid_t {
+        self.inner().can_id
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // UNSAFE: The frame is a plain C struct
+        unsafe {
+            std::slice::from_raw_parts(
+                self.inner() as *const libc::can_frame as *const u8,
+                Self::SIZE,
+            )
+        }
+    }
+}
+
+impl BcmFrame for CanFdFrame {
+    const FLAGS: u32 = libc::CAN_FD_FRAME;
+    const SIZE: usize = libc::CANFD_MTU;
+
+    fn can_id(&self) -> libc::canid_t {
+        self.inner().can_id
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // UNSAFE: The frame is a plain C struct
+        unsafe {
+            std::slice::from_raw_parts(
+                self.inner() as *const libc::canfd_frame as *const u8,
+                Self::SIZE,
+            )
+        }
+    }
+}
+
+impl futures_core::Stream for CanBcm {
+    type Item = Result<CanBcmEvent, std::io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_read_event(cx).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use embedded_hal::can::{Frame, Id, StandardId};
+
+    use super::{BcmFrame, CanBcm, CanBcmEvent, BCM_HEAD_SIZE};
+    use crate::can::{CanAnyFrame, CanFdFrame};
+
+    fn message(head: libc::bcm_msg_head, frame: Option<libc::can_frame>) -> Vec<u8> {
+        let mut buffer = Vec::new();
+        unsafe {
+            let head_ptr = &head as *const libc::bcm_msg_head as *const u8;
+            buffer.extend_from_slice(std::slice::from_raw_parts(head_ptr, BCM_HEAD_SIZE));
+            if let Some(frame) = frame {
+                let frame_ptr = &frame as *const libc::can_frame as *const u8;
+                buffer.extend_from_slice(std::slice::from_raw_parts(frame_ptr, libc::CAN_MTU));
+            }
+        }
+        buffer
+    }
+
+    #[test]
+    fn converts_intervals() {
+        let timeval = CanBcm::timeval(Duration::from_millis(1500));
+        assert_eq!(timeval.tv_sec, 1);
+        assert_eq!(timeval.tv_usec, 500_000);
+    }
+
+    #[test]
+    fn parses_changed_event() {
+        let mut frame: libc::can_frame = unsafe { std::mem::zeroed() };
+        frame.can_id = 0x123;
+        frame.can_dlc = 2;
+        frame.data[..2].copy_from_slice(&[0xAB, 0xCD]);
+
+        let head = CanBcm::msg_head(libc::RX_CHANGED, 0, 0x123, Duration::ZERO, Duration::ZERO);
+        let event = CanBcm::parse_event(&message(head, Some(frame))).unwrap();
+        match event {
+            Some(CanBcmEvent::Changed(CanAnyFrame::Classic(frame))) => {
+                assert_eq!(frame.inner().can_id, 0x123);
+                assert_eq!(&frame.inner().data[..2], [0xAB, 0xCD]);
+            }
+            _ => panic!("Expected changed event"),
+        }
+    }
+
+    #[test]
+    fn parses_timeout_event() {
+        let head = CanBcm::msg_head(libc::RX_TIMEOUT, 0, 0x7FF, Duration::ZERO, Duration::ZERO);
+        let event = CanBcm::parse_event(&message(head, None)).unwrap();
+        match event {
+            Some(CanBcmEvent::Timeout(id)) => {
+                assert_eq!(id, Id::Standard(StandardId::new(0x7FF).unwrap()))
+            }
+            _ => panic!("Expected timeout event"),
+        }
+    }
+
+    #[test]
+    fn parses_tx_expired_event() {
+        let head = CanBcm::msg_head(libc::TX_EXPIRED, 0, 0x100, Duration::ZERO, Duration::ZERO);
+        let event = CanBcm::parse_event(&message(head, None)).unwrap();
+        match event {
+            Some(CanBcmEvent::TxExpired(id)) => {
+                assert_eq!(id, Id::Standard(StandardId::new(0x100).unwrap()))
+            }
+            _ => panic!("Expected TX expired event"),
+        }
+    }
+
+    #[test]
+    fn serializes_fd_frames() {
+        let id = StandardId::new(0x123).unwrap();
+        let frames = [
+            <CanFdFrame as Frame>::new(id, &[1; 12]).unwrap(),
+            <CanFdFrame as Frame>::new(id, &[2; 12]).unwrap(),
+        ];
+        assert_eq!(CanBcm::frames_id(&frames).unwrap(), 0x123);
+        assert_eq!(frames[0].bytes().len(), libc::CANFD_MTU);
+        assert_eq!(frames[1].bytes()[8], 2);
+
+        let other_id = StandardId::new(0x124).unwrap();
+        let frames = [
+            <CanFdFrame as Frame>::new(id, &[]).unwrap(),
+            <CanFdFrame as Frame>::new(other_id, &[]).unwrap(),
+        ];
+        assert!(CanBcm::frames_id(&frames).is_err());
+    }
+
+    #[test]
+    fn ignores_other_messages() {
+        let head = CanBcm::msg_head(libc::TX_STATUS, 0, 0x100, Duration::ZERO, Duration::ZERO);
+        assert!(CanBcm::parse_event(&message(head, None)).unwrap().is_none());
+        assert!(CanBcm::parse_event(&[0; 4]).is_err());
+    }
+}
@@ -0,0 +1,307 @@
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+use crate::{
+    socket::{timed_out, CanInterface, CanRxMeta, RawSocket},
+    sockopt,
+};
+
+use super::{
+    any_frame::{classic_frame, frame_bytes, parse_frame, MAX_FRAME_SIZE},
+    options,
+    tx_queue::TxQueue,
+    CanAnyFrame, CanFdFrame, CanFilter, CanFrame, CanTimestamping, CanXlFrame, CanXlVcidOptions,
+    TxQueuePolicy, TxQueueStats,
+};
+
+/// CAN bus which blocks the calling thread
+///
+/// Reads block the thread until a frame is received or the read timeout
+/// expires. Writes block while the socket buffer is full and retry according
+/// to the [TxQueuePolicy] while the transmit queue of the interface is full.
+///
+/// # Example:
+/// ```no_run
+/// # use ddose::{blocking::CanBus, CanInterface};
+/// # fn main() -> Result<(), std::io::Error> {
+/// let mut can_bus = CanBus::open(&CanInterface::try_from("can0")?)?;
+/// can_bus.set_read_timeout(Some(std::time::Duration::from_millis(100)))?;
+///
+/// let frame = can_bus.read()?;
+/// can_bus.write(&frame)?;
+/// # Ok(())
+/// # }
+/// ```
+pub struct CanBus {
+    socket: RawSocket,
+    fd_frames: bool,
+    xl_frames: bool,
+    tx_queue: TxQueue,
+}
+
+impl CanBus {
+    /// Opens a CAN bus
+    ///
+    /// Creates a new socketcan socket and binds it to the specified interface.
+    pub fn open(can_if: &CanInterface) -> Result<Self, std::io::Error> {
+        Self::open_with(can_if, |_| Ok(()))
+    }
+
+    /// Opens a CAN bus with acceptance filters
+    ///
+    /// In contrast to calling [CanBus::set_filters()] after opening the bus,
+    /// the filters are installed before the socket is bound to the interface.
+    /// Therefore, no frames that don't match the filters can be received.
+    pub fn open_filtered(
+        can_if: &CanInterface,
+        filters: &[CanFilter],
+    ) -> Result<Self, std::io::Error> {
+        Self::open_with(can_if, |can_bus| can_bus.set_filters(filters))
+    }
+
+    /// Opens a CAN bus with CAN FD frames enabled
+    ///
+    /// Same as [CanBus::open()] but the socket also receives and transmits CAN
+    /// FD frames. The interface must have an MTU of `CANFD_MTU` for this.
+    pub fn open_fd(can_if: &CanInterface) -> Result<Self, std::io::Error> {
+        Self::open_with(can_if, |can_bus| can_bus.set_fd_frames(true))
+    }
+
+    /// Opens a CAN bus with CAN XL frames enabled
+    ///
+    /// Same as [CanBus::open()] but the socket also receives and transmits CAN
+    /// XL frames. The interface must have an MTU of `CANXL_MTU` for this,
+    /// which requires kernel 6.2 or newer.
+    pub fn open_xl(can_if: &CanInterface) -> Result<Self, std::io::Error> {
+        Self::open_with(can_if, |can_bus| can_bus.set_xl_frames(true))
+    }
+
+    /// Creates the socket and configures it before binding it to the interface
+    fn open_with(
+        can_if: &CanInterface,
+        configure: impl FnOnce(&mut Self) -> Result<(), std::io::Error>,
+    ) -> Result<Self, std::io::Error> {
+        let socket = RawSocket::create(libc::SOCK_RAW, libc::CAN_RAW)?;
+        let mut can_bus = Self {
+            socket,
+            fd_frames: false,
+            xl_frames: false,
+            tx_queue: TxQueue::new(),
+        };
+        configure(&mut can_bus)?;
+
+        can_bus.socket.bind(can_if)?;
+
+        Ok(can_bus)
+    }
+
+    /// Replaces the acceptance filters of the bus
+    ///
+    /// The filters are evaluated by the kernel, so frames which don't match
+    /// any filter never reach the application. By default, a frame is received
+    /// if it matches any of the filters. An empty list of filters disables the
+    /// reception of frames completely.
+    pub fn set_filters(&mut self, filters: &[CanFilter]) -> Result<(), std::io::Error> {
+        options::set_filters(&self.socket, filters)
+    }
+
+    /// Removes all acceptance filters so every frame is received
+    pub fn accept_all(&mut self) -> Result<(), std::io::Error> {
+        self.set_filters(&[CanFilter::accept_all()])
+    }
+
+    /// Requires a frame to match all filters instead of any filter
+    ///
+    /// This is mostly useful in combination with inverted filters, e.g. to
+    /// receive every frame except for a set of identifiers.
+    pub fn set_join_filters(&mut self, join: bool) -> Result<(), std::io::Error> {
+        options::set_flag(&self.socket, sockopt::CAN_RAW_JOIN_FILTERS, join)
+    }
+
+    /// Sets the classes of error frames that shall be received
+    ///
+    /// The mask is a combination of the `CAN_ERR_*` error classes, e.g.
+    /// `libc::CAN_ERR_BUSOFF | libc::CAN_ERR_CRTL`. Use `libc::CAN_ERR_MASK` to
+    /// receive all error frames or `0` to disable them again (default). Error
+    /// frames are returned as [CanAnyFrame::Error] by [CanBus::read_any()].
+    pub fn set_error_mask(&mut self, mask: libc::can_err_mask_t) -> Result<(), std::io::Error> {
+        self.socket.set_option(sockopt::CAN_RAW_ERR_FILTER, &mask)
+    }
+
+    /// Enables or disables the reception and transmission of CAN FD frames
+    pub fn set_fd_frames(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        options::set_flag(&self.socket, sockopt::CAN_RAW_FD_FRAMES, enable)?;
+        self.fd_frames = enable;
+
+        Ok(())
+    }
+
+    /// Enables or disables the reception and transmission of CAN XL frames
+    pub fn set_xl_frames(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        options::set_flag(&self.socket, sockopt::CAN_RAW_XL_FRAMES, enable)?;
+        self.xl_frames = enable;
+
+        Ok(())
+    }
+
+    /// Sets how the VCID of CAN XL frames is transmitted and filtered
+    ///
+    /// Requires kernel 6.9 or newer.
+    pub fn set_xl_vcid_options(&mut self, options: CanXlVcidOptions) -> Result<(), std::io::Error> {
+        self.socket
+            .set_option(sockopt::CAN_RAW_XL_VCID_OPTS, &options.to_inner())
+    }
+
+    /// Selects which timestamps are recorded for received frames
+    ///
+    /// The timestamps are returned by [CanBus::read_timestamped()].
+    pub fn set_timestamping(
+        &mut self,
+        timestamping: CanTimestamping,
+    ) -> Result<(), std::io::Error> {
+        options::set_timestamping(&self.socket, timestamping)
+    }
+
+    /// Enables or disables reporting the number of dropped frames
+    ///
+    /// When enabled, [CanBus::read_timestamped()] returns the number of frames
+    /// the socket dropped because the receive queue was full.
+    pub fn set_drop_counter(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        options::set_flag(&self.socket, sockopt::SO_RXQ_OVFL, enable)
+    }
+
+    /// Enables or disables the local loopback of transmitted frames
+    ///
+    /// With loopback enabled (default), frames written to the bus are also
+    /// received by the other sockets on the same interface.
+    pub fn set_loopback(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        options::set_flag(&self.socket, sockopt::CAN_RAW_LOOPBACK, enable)
+    }
+
+    /// Enables or disables the reception of the frames written to this bus
+    ///
+    /// Requires the loopback to be enabled (see [CanBus::set_loopback()]).
+    /// Received own frames are marked with [CanRxMeta::is_own].
+    pub fn set_recv_own_msgs(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        options::set_flag(&self.socket, sockopt::CAN_RAW_RECV_OWN_MSGS, enable)
+    }
+
+    /// Returns `true` if CAN FD frames are enabled
+    pub fn fd_frames(&self) -> bool {
+        self.fd_frames
+    }
+
+    /// Returns `true` if CAN XL frames are enabled
+    pub fn xl_frames(&self) -> bool {
+        self.xl_frames
+    }
+
+    /// Sets the time after which reads fail with `TimedOut`, `None` waits
+    /// forever (default)
+    pub fn set_read_timeout(
+        &mut self,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<(), std::io::Error> {
+        self.socket.set_read_timeout(timeout)
+    }
+
+    /// Sets the time after which writes to a full socket buffer fail with
+    /// `TimedOut`, `None` waits forever (default)
+    ///
+    /// A full transmit queue of the interface is handled by the
+    /// [TxQueuePolicy] instead.
+    pub fn set_write_timeout(
+        &mut self,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<(), std::io::Error> {
+        self.socket.set_write_timeout(timeout)
+    }
+
+    /// Sets how writes handle a full transmit queue of the interface
+    ///
+    /// By default, writes are retried for up to a second. See [TxQueuePolicy].
+    pub fn set_tx_queue_policy(&mut self, policy: TxQueuePolicy) {
+        self.tx_queue.set_policy(policy);
+    }
+
+    /// Returns the policy for full transmit queues
+    pub fn tx_queue_policy(&self) -> TxQueuePolicy {
+        self.tx_queue.policy()
+    }
+
+    /// Returns the counters of the writes of the bus
+    pub fn tx_queue_stats(&self) -> TxQueueStats {
+        self.tx_queue.stats()
+    }
+
+    /// Reads a classic CAN frame from the bus
+    ///
+    /// If CAN FD frames or error frames are enabled and such a frame is
+    /// received, an error is returned. Use [CanBus::read_any()] in this case.
+    pub fn read(&mut self) -> Result<CanFrame, std::io::Error> {
+        classic_frame(self.read_any()?)
+    }
+
+    /// Reads any kind of frame from the bus
+    pub fn read_any(&mut self) -> Result<CanAnyFrame, std::io::Error> {
+        let mut buffer = [0; MAX_FRAME_SIZE];
+
+        let bytes_read = self.socket.read(&mut buffer).map_err(timed_out)?;
+        parse_frame(&buffer[..bytes_read])
+    }
+
+    /// Reads a frame together with its receive timestamps
+    ///
+    /// Timestamps must be enabled with [CanBus::set_timestamping()], otherwise
+    /// only the drop counter (if enabled) is returned.
+    pub fn read_timestamped(&mut self) -> Result<(CanAnyFrame, CanRxMeta), std::io::Error> {
+        let mut buffer = [0; MAX_FRAME_SIZE];
+
+        let (bytes_read, meta) = self.socket.recv(&mut buffer).map_err(timed_out)?;
+        let frame = parse_frame(&buffer[..bytes_read])?;
+
+        Ok((frame, meta))
+    }
+
+    /// Writes a classic CAN frame to the bus
+    pub fn write(&mut self, can_frame: &CanFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::Classic(*can_frame))
+    }
+
+    /// Writes a CAN FD frame to the bus
+    pub fn write_fd(&mut self, canfd_frame: &CanFdFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::Fd(*canfd_frame))
+    }
+
+    /// Writes a CAN XL frame to the bus
+    pub fn write_xl(&mut self, canxl_frame: &CanXlFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::from(*canxl_frame))
+    }
+
+    /// Writes any kind of frame to the bus
+    pub fn write_any(&mut self, frame: &CanAnyFrame) -> Result<(), std::io::Error> {
+        let bytes = frame_bytes(frame)?;
+
+        let socket = &self.socket;
+        let bytes_written = self
+            .tx_queue
+            .write_blocking(|| socket.write(bytes))
+            .map_err(timed_out)?;
+        if bytes_written != bytes.len() {
+            return Err(std::io::Error::other("Transmitted incomplete CAN frame"));
+        }
+
+        Ok(())
+    }
+}
+
+impl AsRawFd for CanBus {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl AsFd for CanBus {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
@@ -1,15 +1,72 @@
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    task::{Context, Poll},
+};
 
-use crate::socket::{CanInterface, CanSocket};
+use crate::{
+    socket::{CanInterface, CanRxMeta, CanSocket, RecvBuffers},
+    sockopt,
+};
 
-use super::CanFrame;
+use super::{
+    any_frame::{classic_frame, classic_frame_bytes, frame_bytes, parse_frame, MAX_FRAME_SIZE},
+    options::{self, CanTimestamping},
+    tx_queue::TxQueue,
+    CanAnyFrame, CanFdFrame, CanFilter, CanFrame, CanXlFrame, CanXlVcidOptions, TxQueuePolicy,
+    TxQueueStats,
+};
 
 /// Allows reading and writing frames on a CAN bus.
 ///
 /// [CanBus] provides access to an socketcan interface. using [CanBus::read()]
 /// and [CanBus::write()], frames can be received and transmitted.
+///
+/// CAN FD frames are only available when the bus is opened with
+/// [CanBus::open_fd()] or enabled with [CanBus::set_fd_frames()]. Use
+/// [CanBus::read_any()] to receive classic and CAN FD frames in the same loop.
+/// The same applies to CAN XL frames with [CanBus::open_xl()] and
+/// [CanBus::set_xl_frames()].
+///
+/// To reduce the load on busy buses, acceptance filters can be installed with
+/// [CanBus::open_filtered()] or [CanBus::set_filters()].
+///
+/// To read and write from different tasks at the same time, the bus can be
+/// split into a reader and a writer with [CanBus::split()] or
+/// [CanBus::into_split()].
+///
+/// [CanBus] is also a [Stream](futures_core::Stream) of classic CAN frames and
+/// a [Sink](futures_sink::Sink) for classic and CAN FD frames, so it can be
+/// used with the combinators of the `futures` crate.
+///
+/// # Example:
+/// ```no_run
+/// # use ddose::{CanBus, CanInterface};
+/// # use embedded_hal::can::Frame;
+/// # use futures::{StreamExt, TryStreamExt};
+/// # #[tokio::main] async fn main() -> Result<(), std::io::Error> {
+/// let can0 = CanBus::open(&CanInterface::try_from("can0")?)?;
+/// let can1 = CanBus::open(&CanInterface::try_from("can1")?)?;
+///
+/// // Forward all data frames from can0 to can1
+/// can0.try_filter(|frame| std::future::ready(frame.is_data_frame()))
+///     .forward(can1)
+///     .await?;
+/// # Ok(())
+/// # }
+/// ```
 pub struct CanBus {
-    socket: CanSocket,
+    pub(super) socket: CanSocket,
+    fd_frames: bool,
+    xl_frames: bool,
+    recv_own_msgs: bool,
+    /// Frame passed to the [Sink](futures_sink::Sink) which wasn't sent yet
+    pub(super) pending: Option<CanAnyFrame>,
+    /// Frames received while waiting for a transmit confirmation
+    pub(super) backlog: VecDeque<(CanAnyFrame, CanRxMeta)>,
+    pub(super) tx_queue: TxQueue,
+    /// Buffers of [CanBus::read_many()], kept to avoid allocating per call
+    rx_buffers: RecvBuffers,
 }
 
 impl CanBus {
@@ -17,43 +74,674 @@ impl CanBus {
     ///
     /// Creates a new socketcan socket and binds it to the specified interface.
     pub fn open(can_if: &CanInterface) -> Result<Self, std::io::Error> {
+        Self::open_with(can_if, |_| Ok(()))
+    }
+
+    /// Opens a CAN bus with acceptance filters
+    ///
+    /// In contrast to calling [CanBus::set_filters()] after opening the bus,
+    /// the filters are installed before the socket is bound to the interface.
+    /// Therefore, no frames that don't match the filters can be received.
+    pub fn open_filtered(
+        can_if: &CanInterface,
+        filters: &[CanFilter],
+    ) -> Result<Self, std::io::Error> {
+        Self::open_with(can_if, |can_bus| can_bus.set_filters(filters))
+    }
+
+    /// Opens a CAN bus with CAN FD frames enabled
+    ///
+    /// Same as [CanBus::open()] but the socket also receives and transmits CAN
+    /// FD frames. The interface must have an MTU of `CANFD_MTU` for this.
+    pub fn open_fd(can_if: &CanInterface) -> Result<Self, std::io::Error> {
+        Self::open_with(can_if, |can_bus| can_bus.set_fd_frames(true))
+    }
+
+    /// Opens a CAN bus with CAN XL frames enabled
+    ///
+    /// Same as [CanBus::open()] but the socket also receives and transmits CAN
+    /// XL frames. The interface must have an MTU of `CANXL_MTU` for this,
+    /// which requires kernel 6.2 or newer.
+    pub fn open_xl(can_if: &CanInterface) -> Result<Self, std::io::Error> {
+        Self::open_with(can_if, |can_bus| can_bus.set_xl_frames(true))
+    }
+
+    /// Opens a CAN bus on all CAN interfaces of the host
+    ///
+    /// Frames of all interfaces are received by the same socket. Use
+    /// [CanBus::read_from()] to find out which interface a frame was received
+    /// on and [CanBus::write_to()] to transmit frames on a specific interface.
+    /// Frames written with [CanBus::write()] are rejected by the kernel, as
+    /// there is no interface to send them on.
+    pub fn open_any() -> Result<Self, std::io::Error> {
+        // The kernel binds sockets with the interface index 0 to all interfaces
+        Self::open_with(&CanInterface::from_index_unchecked(0), |_| Ok(()))
+    }
+
+    /// Creates the socket and configures it before binding it to the interface
+    pub(super) fn open_with(
+        can_if: &CanInterface,
+        configure: impl FnOnce(&mut Self) -> Result<(), std::io::Error>,
+    ) -> Result<Self, std::io::Error> {
         let socket = CanSocket::create(libc::SOCK_RAW, libc::CAN_RAW)?;
-        socket.bind(can_if)?;
-        socket.set_nonblocking()?;
+        let mut can_bus = Self {
+            socket,
+            fd_frames: false,
+            xl_frames: false,
+            recv_own_msgs: false,
+            pending: None,
+            backlog: VecDeque::new(),
+            tx_queue: TxQueue::new(),
+            rx_buffers: RecvBuffers::default(),
+        };
+        configure(&mut can_bus)?;
+
+        can_bus.socket.bind(can_if)?;
+        can_bus.socket.set_nonblocking()?;
+
+        Ok(can_bus)
+    }
+
+    /// Replaces the acceptance filters of the bus
+    ///
+    /// The filters are evaluated by the kernel, so frames which don't match
+    /// any filter never reach the application. By default, a frame is received
+    /// if it matches any of the filters. An empty list of filters disables the
+    /// reception of frames completely.
+    pub fn set_filters(&mut self, filters: &[CanFilter]) -> Result<(), std::io::Error> {
+        options::set_filters(self.socket.raw(), filters)
+    }
+
+    /// Removes all acceptance filters so every frame is received
+    pub fn accept_all(&mut self) -> Result<(), std::io::Error> {
+        self.set_filters(&[CanFilter::accept_all()])
+    }
+
+    /// Requires a frame to match all filters instead of any filter
+    ///
+    /// This is mostly useful in combination with inverted filters, e.g. to
+    /// receive every frame except for a set of identifiers.
+    pub fn set_join_filters(&mut self, join: bool) -> Result<(), std::io::Error> {
+        options::set_flag(self.socket.raw(), sockopt::CAN_RAW_JOIN_FILTERS, join)
+    }
+
+    /// Sets the classes of error frames that shall be received
+    ///
+    /// The mask is a combination of the `CAN_ERR_*` error classes, e.g.
+    /// `libc::CAN_ERR_BUSOFF | libc::CAN_ERR_CRTL`. Use `libc::CAN_ERR_MASK` to
+    /// receive all error frames or `0` to disable them again (default). Error
+    /// frames are returned as [CanAnyFrame::Error] by [CanBus::read_any()].
+    pub fn set_error_mask(&mut self, mask: libc::can_err_mask_t) -> Result<(), std::io::Error> {
+        self.socket.set_option(sockopt::CAN_RAW_ERR_FILTER, &mask)
+    }
+
+    /// Enables or disables the reception and transmission of CAN FD frames
+    pub fn set_fd_frames(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        options::set_flag(self.socket.raw(), sockopt::CAN_RAW_FD_FRAMES, enable)?;
+        self.fd_frames = enable;
+
+        Ok(())
+    }
+
+    /// Enables or disables the reception and transmission of CAN XL frames
+    pub fn set_xl_frames(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        options::set_flag(self.socket.raw(), sockopt::CAN_RAW_XL_FRAMES, enable)?;
+        self.xl_frames = enable;
+
+        Ok(())
+    }
+
+    /// Sets how the VCID of CAN XL frames is transmitted and filtered
+    ///
+    /// Requires kernel 6.9 or newer.
+    pub fn set_xl_vcid_options(&mut self, options: CanXlVcidOptions) -> Result<(), std::io::Error> {
+        self.socket
+            .set_option(sockopt::CAN_RAW_XL_VCID_OPTS, &options.to_inner())
+    }
 
-        Ok(Self { socket })
+    /// Selects which timestamps are recorded for received frames
+    ///
+    /// The timestamps are returned by [CanBus::read_timestamped()].
+    pub fn set_timestamping(
+        &mut self,
+        timestamping: CanTimestamping,
+    ) -> Result<(), std::io::Error> {
+        options::set_timestamping(self.socket.raw(), timestamping)
+    }
+
+    /// Enables or disables reporting the number of dropped frames
+    ///
+    /// When enabled, [CanBus::read_timestamped()] returns the number of frames
+    /// the socket dropped because the receive queue was full.
+    pub fn set_drop_counter(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        options::set_flag(self.socket.raw(), sockopt::SO_RXQ_OVFL, enable)
+    }
+
+    /// Enables or disables the local loopback of transmitted frames
+    ///
+    /// With loopback enabled (default), frames written to the bus are also
+    /// received by the other sockets on the same interface.
+    pub fn set_loopback(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        options::set_flag(self.socket.raw(), sockopt::CAN_RAW_LOOPBACK, enable)
+    }
+
+    /// Enables or disables the reception of the frames written to this bus
+    ///
+    /// Requires the loopback to be enabled (see [CanBus::set_loopback()]).
+    /// Received own frames are marked with [CanRxMeta::is_own].
+    pub fn set_recv_own_msgs(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        options::set_flag(self.socket.raw(), sockopt::CAN_RAW_RECV_OWN_MSGS, enable)?;
+        self.recv_own_msgs = enable;
+
+        Ok(())
+    }
+
+    /// Returns `true` if CAN FD frames are enabled on the bus
+    pub fn fd_frames(&self) -> bool {
+        self.fd_frames
+    }
+
+    /// Sets how writes handle a full transmit queue of the interface
+    ///
+    /// By default, writes are retried for up to a second. See [TxQueuePolicy].
+    pub fn set_tx_queue_policy(&mut self, policy: TxQueuePolicy) {
+        self.tx_queue.set_policy(policy);
+    }
+
+    /// Returns how writes handle a full transmit queue of the interface
+    pub fn tx_queue_policy(&self) -> TxQueuePolicy {
+        self.tx_queue.policy()
+    }
+
+    /// Returns the counters of full transmit queues and failed writes
+    pub fn tx_queue_stats(&self) -> TxQueueStats {
+        self.tx_queue.stats()
+    }
+
+    /// Sets the size of the send buffer of the socket (`SO_SNDBUF`)
+    ///
+    /// The kernel doubles the size and enforces a minimum. When the buffer
+    /// is full, writes wait until the socket is writable again instead of
+    /// failing, so a small buffer keeps the transmit queue of the interface
+    /// from overflowing.
+    pub fn set_send_buffer_size(&mut self, size: usize) -> Result<(), std::io::Error> {
+        let size = libc::c_int::try_from(size).unwrap_or(libc::c_int::MAX);
+        self.socket.set_option(sockopt::SO_SNDBUF, &size)
+    }
+
+    /// Returns the size of the send buffer of the socket in bytes
+    pub fn send_buffer_size(&self) -> Result<usize, std::io::Error> {
+        Ok(self.socket.option(sockopt::SO_SNDBUF)? as usize)
+    }
+
+    /// Returns `true` if CAN XL frames are enabled on the bus
+    pub fn xl_frames(&self) -> bool {
+        self.xl_frames
+    }
+
+    /// Returns the underlying socket, e.g. to set further
+    /// [socket options](crate::sockopt)
+    pub fn socket(&self) -> &CanSocket {
+        &self.socket
+    }
+
+    /// Polls for a classic CAN frame from the bus
+    ///
+    /// See [CanBus::read()].
+    pub fn poll_read(&mut self, cx: &mut Context<'_>) -> Poll<Result<CanFrame, std::io::Error>> {
+        self.poll_read_any(cx)
+            .map(|frame| frame.and_then(classic_frame))
     }
 
+    /// Polls for any kind of frame from the bus
+    ///
+    /// See [CanBus::read_any()].
+    pub fn poll_read_any(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<CanAnyFrame, std::io::Error>> {
+        if let Some((frame, _)) = self.backlog.pop_front() {
+            return Poll::Ready(Ok(frame));
+        }
+
+        poll_read_frame(&self.socket, cx)
+    }
+
+    /// Reads a classic CAN frame from the bus
+    ///
+    /// If CAN FD frames or error frames are enabled and such a frame is
+    /// received, an error is returned. Use [CanBus::read_any()] in this case.
     pub async fn read(&mut self) -> Result<CanFrame, std::io::Error> {
-        const FRAME_SIZE: usize = std::mem::size_of::<libc::can_frame>();
-        let mut buffer = [0; FRAME_SIZE];
+        std::future::poll_fn(|cx| self.poll_read(cx)).await
+    }
 
-        let bytes_read = self.socket.read(&mut buffer).await?;
-        if bytes_read != FRAME_SIZE {
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Received incomplete CAN frame",
-            ));
+    /// Reads any kind of frame from the bus
+    ///
+    /// Returns classic, CAN FD, CAN XL and error frames, depending on which of
+    /// them are enabled on the bus.
+    pub async fn read_any(&mut self) -> Result<CanAnyFrame, std::io::Error> {
+        std::future::poll_fn(|cx| self.poll_read_any(cx)).await
+    }
+
+    /// Reads a frame together with its receive timestamps
+    ///
+    /// Timestamps must be enabled with [CanBus::set_timestamping()], otherwise
+    /// only the drop counter (if enabled) is returned.
+    pub async fn read_timestamped(&mut self) -> Result<(CanAnyFrame, CanRxMeta), std::io::Error> {
+        if let Some(entry) = self.backlog.pop_front() {
+            return Ok(entry);
         }
 
-        let frame = unsafe { std::mem::transmute::<[u8; FRAME_SIZE], libc::can_frame>(buffer) };
-        Ok(CanFrame::from_inner(frame))
+        let mut buffer = [0; MAX_FRAME_SIZE];
+
+        let (bytes_read, meta) = self.socket.recv(&mut buffer).await?;
+        let frame = parse_frame(&buffer[..bytes_read])?;
+
+        Ok((frame, meta))
     }
 
-    pub async fn write(&mut self, can_frame: &CanFrame) -> Result<(), std::io::Error> {
-        const FRAME_SIZE: usize = std::mem::size_of::<libc::can_frame>();
-        // TODO: remove unnecessary copy
-        let bytes = unsafe {
-            std::mem::transmute_copy::<libc::can_frame, [u8; FRAME_SIZE]>(can_frame.inner())
+    /// Reads a frame together with the interface it was received on
+    ///
+    /// This is mostly useful for buses opened with [CanBus::open_any()].
+    pub async fn read_from(&mut self) -> Result<(CanAnyFrame, CanInterface), std::io::Error> {
+        let (frame, meta) = self.read_timestamped().await?;
+        let can_if = meta.interface.ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Received frame without interface",
+            )
+        })?;
+
+        Ok((frame, can_if))
+    }
+
+    /// Reads multiple classic CAN frames with a single system call
+    ///
+    /// Waits until at least one frame is available and returns the number of
+    /// frames stored at the beginning of `frames`. Storing stops at the first
+    /// CAN FD or error frame, which is kept together with the rest of the
+    /// batch for the next read. Like [CanBus::read()], an error is returned
+    /// if the first frame isn't a classic frame, so use
+    /// [CanBus::read_many_timestamped()] if such frames are enabled.
+    pub async fn read_many(&mut self, frames: &mut [CanFrame]) -> Result<usize, std::io::Error> {
+        let mut slots = frames.iter_mut();
+        let count = self
+            .recv_many(slots.len(), |frame, meta| match (frame, slots.next()) {
+                (CanAnyFrame::Classic(frame), Some(slot)) => {
+                    *slot = frame;
+                    None
+                }
+                (frame, _) => Some((frame, meta)),
+            })
+            .await?;
+
+        // Only a batch starting with another kind of frame stores nothing
+        if count == 0 && !frames.is_empty() {
+            if let Some((frame, _)) = self.backlog.pop_front() {
+                return classic_frame(frame).map(|_| 0);
+            }
+        }
+
+        Ok(count)
+    }
+
+    /// Reads multiple frames together with their receive timestamps
+    ///
+    /// Waits until at least one frame is available and appends up to `max`
+    /// frames to `frames`. Returns the number of appended frames. The
+    /// metadata of each frame contains its timestamps (see
+    /// [CanBus::read_timestamped()]) and the interface it was received on.
+    pub async fn read_many_timestamped(
+        &mut self,
+        frames: &mut Vec<(CanAnyFrame, CanRxMeta)>,
+        max: usize,
+    ) -> Result<usize, std::io::Error> {
+        self.recv_many(max, |frame, meta| {
+            frames.push((frame, meta));
+            None
+        })
+        .await
+    }
+
+    /// Receives up to `max` frames and passes them to `store`
+    ///
+    /// Frames of the backlog are returned first. If `store` rejects a frame by
+    /// returning it, it is put into the backlog together with the rest of the
+    /// batch. Returns the number of stored frames. A frame which can't be
+    /// parsed ends the batch and is returned as error if no frame was stored
+    /// yet.
+    async fn recv_many(
+        &mut self,
+        max: usize,
+        mut store: impl FnMut(CanAnyFrame, CanRxMeta) -> Option<(CanAnyFrame, CanRxMeta)>,
+    ) -> Result<usize, std::io::Error> {
+        if max == 0 {
+            return Ok(0);
+        }
+
+        let mut count = 0;
+        if !self.backlog.is_empty() {
+            while count < max {
+                let Some((frame, meta)) = self.backlog.pop_front() else {
+                    break;
+                };
+                if let Some(entry) = store(frame, meta) {
+                    self.backlog.push_front(entry);
+                    break;
+                }
+                count += 1;
+            }
+            return Ok(count);
+        }
+
+        // Only allocate buffers for CAN XL frames if they can be received
+        let buffer_size = match self.xl_frames {
+            true => MAX_FRAME_SIZE,
+            false => libc::CANFD_MTU,
         };
+        let Self {
+            socket,
+            rx_buffers,
+            backlog,
+            ..
+        } = self;
+        let mut result = Ok(());
+        let mut rejected = false;
+        std::future::poll_fn(|cx| {
+            socket.poll_recv_many(cx, rx_buffers, max, buffer_size, |buffer, meta| {
+                if rejected || result.is_err() {
+                    if let Ok(frame) = parse_frame(buffer) {
+                        backlog.push_back((frame, meta));
+                    }
+                    return;
+                }
+
+                match parse_frame(buffer) {
+                    Ok(frame) => match store(frame, meta) {
+                        None => count += 1,
+                        Some(entry) => {
+                            backlog.push_back(entry);
+                            rejected = true;
+                        }
+                    },
+                    Err(e) => result = Err(e),
+                }
+            })
+        })
+        .await?;
+
+        match result {
+            Err(e) if count == 0 => Err(e),
+            _ => Ok(count),
+        }
+    }
+
+    /// Writes multiple classic CAN frames with a single system call
+    ///
+    /// Waits until at least one frame can be written and returns the number
+    /// of written frames. If the transmit queue of the interface fills up,
+    /// less frames than given are written and the remaining frames must be
+    /// written again.
+    pub async fn write_many(&mut self, frames: &[CanFrame]) -> Result<usize, std::io::Error> {
+        let messages: Vec<_> = frames.iter().map(classic_frame_bytes).collect();
+        self.send_many(&messages).await
+    }
+
+    /// Writes multiple classic, CAN FD or CAN XL frames with a single system
+    /// call
+    ///
+    /// See [CanBus::write_many()].
+    pub async fn write_many_any(
+        &mut self,
+        frames: &[CanAnyFrame],
+    ) -> Result<usize, std::io::Error> {
+        let messages = frames
+            .iter()
+            .map(frame_bytes)
+            .collect::<Result<Vec<_>, _>>()?;
+        self.send_many(&messages).await
+    }
+
+    /// Sends the encoded frames with a single system call
+    async fn send_many(&mut self, messages: &[&[u8]]) -> Result<usize, std::io::Error> {
+        self.tx_queue.reset();
+        std::future::poll_fn(|cx| {
+            let socket = &self.socket;
+            self.tx_queue
+                .poll_write(cx, |cx| socket.poll_send_many(cx, messages))
+        })
+        .await
+    }
+
+    /// Polls to write a classic, CAN FD or CAN XL frame to the bus
+    ///
+    /// See [CanBus::write_any()].
+    pub fn poll_write_any(
+        &mut self,
+        cx: &mut Context<'_>,
+        frame: &CanAnyFrame,
+    ) -> Poll<Result<(), std::io::Error>> {
+        poll_write_frame(&self.socket, &mut self.tx_queue, cx, frame)
+    }
+
+    pub async fn write(&mut self, can_frame: &CanFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::Classic(*can_frame)).await
+    }
+
+    /// Writes a CAN FD frame to the bus
+    ///
+    /// CAN FD frames must be enabled on the bus, otherwise the kernel rejects
+    /// the frame.
+    pub async fn write_fd(&mut self, canfd_frame: &CanFdFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::Fd(*canfd_frame)).await
+    }
+
+    /// Writes a CAN XL frame to the bus
+    ///
+    /// CAN XL frames must be enabled on the bus, otherwise the kernel rejects
+    /// the frame.
+    pub async fn write_xl(&mut self, canxl_frame: &CanXlFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::from(*canxl_frame)).await
+    }
+
+    /// Writes a classic, CAN FD or CAN XL frame to the bus
+    ///
+    /// Error frames are generated by the driver and can't be written, so an
+    /// [InvalidInput](std::io::ErrorKind::InvalidInput) error is returned
+    /// for them.
+    pub async fn write_any(&mut self, frame: &CanAnyFrame) -> Result<(), std::io::Error> {
+        self.tx_queue.reset();
+        std::future::poll_fn(|cx| self.poll_write_any(cx, frame)).await
+    }
+
+    /// Writes a classic CAN frame and waits until it was transmitted
+    ///
+    /// Returns the metadata of the own frame received back from the kernel,
+    /// whose timestamps tell when the frame was transmitted (see
+    /// [CanBus::set_timestamping()]). Receiving own frames must be enabled
+    /// with [CanBus::set_recv_own_msgs()]. Other frames received in the
+    /// meantime are returned by the next reads.
+    ///
+    /// The kernel returns the frame once the controller transmitted it, so the
+    /// future doesn't resolve if the frame is never acknowledged on the bus.
+    /// Use a timeout (e.g., `tokio::time::timeout()`) in this case.
+    pub async fn write_confirmed(
+        &mut self,
+        can_frame: &CanFrame,
+    ) -> Result<CanRxMeta, std::io::Error> {
+        self.write_any_confirmed(&CanAnyFrame::Classic(*can_frame))
+            .await
+    }
 
-        if self.socket.write(&bytes).await? != FRAME_SIZE {
+    /// Writes a classic, CAN FD or CAN XL frame and waits until it was
+    /// transmitted
+    ///
+    /// See [CanBus::write_confirmed()].
+    pub async fn write_any_confirmed(
+        &mut self,
+        frame: &CanAnyFrame,
+    ) -> Result<CanRxMeta, std::io::Error> {
+        if !self.recv_own_msgs {
             return Err(std::io::Error::new(
-                std::io::ErrorKind::Other,
-                "Received incomplete CAN frame",
+                std::io::ErrorKind::InvalidInput,
+                "Receiving own frames must be enabled for transmit confirmations",
             ));
         }
 
+        self.write_any(frame).await?;
+        let bytes = frame_bytes(frame)?;
+
+        let mut buffer = [0; MAX_FRAME_SIZE];
+        loop {
+            let (bytes_read, meta) = self.socket.recv(&mut buffer).await?;
+            if meta.is_own && &buffer[..bytes_read] == bytes {
+                return Ok(meta);
+            }
+
+            let received = parse_frame(&buffer[..bytes_read])?;
+            self.backlog.push_back((received, meta));
+        }
+    }
+
+    /// Writes a classic CAN frame to a specific interface
+    ///
+    /// The frame is transmitted on the interface even if the bus is bound to
+    /// another interface. This is mostly useful for buses opened with
+    /// [CanBus::open_any()].
+    pub async fn write_to(
+        &mut self,
+        can_if: &CanInterface,
+        can_frame: &CanFrame,
+    ) -> Result<(), std::io::Error> {
+        self.write_any_to(can_if, &CanAnyFrame::Classic(*can_frame))
+            .await
+    }
+
+    /// Writes a classic, CAN FD or CAN XL frame to a specific interface
+    ///
+    /// See [CanBus::write_to()].
+    pub async fn write_any_to(
+        &mut self,
+        can_if: &CanInterface,
+        frame: &CanAnyFrame,
+    ) -> Result<(), std::io::Error> {
+        let bytes = frame_bytes(frame)?;
+
+        let mut address: libc::sockaddr_can = unsafe { std::mem::zeroed() };
+        address.can_family = libc::AF_CAN as _;
+        address.can_ifindex = can_if.if_index() as _;
+
+        self.tx_queue.reset();
+        let bytes_written = std::future::poll_fn(|cx| {
+            let socket = &self.socket;
+            self.tx_queue
+                .poll_write(cx, |cx| socket.poll_send_to(cx, bytes, &address))
+        })
+        .await?;
+        if bytes_written != bytes.len() {
+            return Err(std::io::Error::other("Transmitted incomplete CAN frame"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Polls for a frame on the socket
+pub(super) fn poll_read_frame(
+    socket: &CanSocket,
+    cx: &mut Context<'_>,
+) -> Poll<Result<CanAnyFrame, std::io::Error>> {
+    let mut buffer = [0; MAX_FRAME_SIZE];
+    match socket.poll_read_bytes(cx, &mut buffer) {
+        Poll::Ready(result) => Poll::Ready(result.and_then(|len| parse_frame(&buffer[..len]))),
+        Poll::Pending => Poll::Pending,
+    }
+}
+
+/// Polls to write a frame to the socket
+pub(super) fn poll_write_frame(
+    socket: &CanSocket,
+    tx_queue: &mut TxQueue,
+    cx: &mut Context<'_>,
+    frame: &CanAnyFrame,
+) -> Poll<Result<(), std::io::Error>> {
+    let bytes = match frame_bytes(frame) {
+        Ok(bytes) => bytes,
+        Err(e) => return Poll::Ready(Err(e)),
+    };
+
+    match tx_queue.poll_write(cx, |cx| socket.poll_write_bytes(cx, bytes)) {
+        Poll::Ready(Ok(bytes_written)) if bytes_written != bytes.len() => Poll::Ready(Err(
+            std::io::Error::other("Transmitted incomplete CAN frame"),
+        )),
+        Poll::Ready(result) => Poll::Ready(result.map(|_| ())),
+        Poll::Pending => Poll::Pending,
+    }
+}
+
+/// Writes the frame passed to a [Sink](futures_sink::Sink) if there is one
+pub(super) fn poll_write_pending(
+    socket: &CanSocket,
+    tx_queue: &mut TxQueue,
+    cx: &mut Context<'_>,
+    pending: &mut Option<CanAnyFrame>,
+) -> Poll<Result<(), std::io::Error>> {
+    let Some(frame) = pending else {
+        return Poll::Ready(Ok(()));
+    };
+
+    let result = match poll_write_frame(socket, tx_queue, cx, frame) {
+        Poll::Ready(result) => result,
+        Poll::Pending => return Poll::Pending,
+    };
+    *pending = None;
+
+    Poll::Ready(result)
+}
+
+impl futures_core::Stream for CanBus {
+    type Item = Result<CanFrame, std::io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_read(cx).map(Some)
+    }
+}
+
+impl<F: Into<CanAnyFrame>> futures_sink::Sink<F> for CanBus {
+    type Error = std::io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let can_bus = self.get_mut();
+        poll_write_pending(
+            &can_bus.socket,
+            &mut can_bus.tx_queue,
+            cx,
+            &mut can_bus.pending,
+        )
+    }
+
+    fn start_send(self: Pin<&mut Self>, frame: F) -> Result<(), Self::Error> {
+        self.get_mut().pending = Some(frame.into());
         Ok(())
     }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let can_bus = self.get_mut();
+        poll_write_pending(
+            &can_bus.socket,
+            &mut can_bus.tx_queue,
+            cx,
+            &mut can_bus.pending,
+        )
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let can_bus = self.get_mut();
+        poll_write_pending(
+            &can_bus.socket,
+            &mut can_bus.tx_queue,
+            cx,
+            &mut can_bus.pending,
+        )
+    }
 }
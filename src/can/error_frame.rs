@@ -0,0 +1,381 @@
+use super::CanFrame;
+
+/// Decoded error frame generated by the CAN controller driver.
+///
+/// Error frames are only received if they are enabled with `set_error_mask()`
+/// of the bus. The error classes are stored in the identifier and the details
+/// in the payload of the frame as described in `linux/can/error.h`.
+#[derive(Clone, Copy)]
+pub struct CanErrorFrame(CanFrame);
+
+impl CanErrorFrame {
+    /// Creates an error frame from a received CAN frame
+    ///
+    /// Returns `None` if the frame doesn't have the `CAN_ERR_FLAG` set.
+    pub fn from_frame(frame: CanFrame) -> Option<Self> {
+        match frame.is_error_frame() {
+            true => Some(Self(frame)),
+            false => None,
+        }
+    }
+
+    /// Returns the underlying CAN frame
+    pub fn frame(&self) -> &CanFrame {
+        &self.0
+    }
+
+    /// Returns the raw error classes (`CAN_ERR_TX_TIMEOUT`, `CAN_ERR_BUSOFF`, ...)
+    pub fn error_class(&self) -> u32 {
+        self.0.inner().can_id & libc::CAN_ERR_MASK
+    }
+
+    fn has_class(&self, class: libc::c_uint) -> bool {
+        self.error_class() & class != 0
+    }
+
+    fn data(&self) -> &[u8; libc::CAN_MAX_DLEN] {
+        &self.0.inner().data
+    }
+
+    /// Returns `true` if a transmission timed out
+    pub fn is_tx_timeout(&self) -> bool {
+        self.has_class(libc::CAN_ERR_TX_TIMEOUT)
+    }
+
+    /// Returns the bit in which the arbitration was lost
+    ///
+    /// Returns `None` if the arbitration wasn't lost. A value of `0` means the
+    /// position is unspecified.
+    pub fn lost_arbitration_bit(&self) -> Option<u8> {
+        match self.has_class(libc::CAN_ERR_LOSTARB) {
+            true => Some(self.data()[0]),
+            false => None,
+        }
+    }
+
+    /// Returns the problems reported by the controller
+    pub fn controller_status(&self) -> ControllerStatus {
+        match self.has_class(libc::CAN_ERR_CRTL) {
+            true => ControllerStatus(self.data()[1]),
+            false => ControllerStatus(0),
+        }
+    }
+
+    /// Returns the protocol violation
+    ///
+    /// Returns `None` if the error frame doesn't report a protocol violation.
+    pub fn protocol_violation(&self) -> Option<ProtocolViolation> {
+        match self.has_class(libc::CAN_ERR_PROT) {
+            true => Some(ProtocolViolation {
+                kind: self.data()[2],
+                location: ProtocolViolationLocation::from(self.data()[3]),
+            }),
+            false => None,
+        }
+    }
+
+    /// Returns the raw transceiver status (`CAN_ERR_TRX_*`)
+    ///
+    /// Returns `None` if the error frame doesn't report a transceiver error.
+    pub fn transceiver_status(&self) -> Option<u8> {
+        match self.has_class(libc::CAN_ERR_TRX) {
+            true => Some(self.data()[4]),
+            false => None,
+        }
+    }
+
+    /// Returns `true` if no acknowledge was received on transmission
+    pub fn is_ack_error(&self) -> bool {
+        self.has_class(libc::CAN_ERR_ACK)
+    }
+
+    /// Returns `true` if the controller went into the bus-off state
+    pub fn is_bus_off(&self) -> bool {
+        self.has_class(libc::CAN_ERR_BUSOFF)
+    }
+
+    /// Returns `true` if a bus error occurred
+    pub fn is_bus_error(&self) -> bool {
+        self.has_class(libc::CAN_ERR_BUSERROR)
+    }
+
+    /// Returns `true` if the controller was restarted after bus-off
+    pub fn is_restarted(&self) -> bool {
+        self.has_class(libc::CAN_ERR_RESTARTED)
+    }
+
+    /// Returns `true` if the controller reports being error passive
+    pub fn is_error_passive(&self) -> bool {
+        let status = self.controller_status();
+        status.rx_passive() || status.tx_passive()
+    }
+
+    /// Returns the TX and RX error counters of the controller
+    ///
+    /// Returns `None` if the driver didn't provide the counters.
+    pub fn error_counters(&self) -> Option<CanErrorCounters> {
+        match self.has_class(libc::CAN_ERR_CNT) {
+            true => Some(CanErrorCounters {
+                tx: self.data()[6],
+                rx: self.data()[7],
+            }),
+            false => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for CanErrorFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CanErrorFrame")
+            .field("error_class", &format_args!("{:#05X}", self.error_class()))
+            .field("lost_arbitration_bit", &self.lost_arbitration_bit())
+            .field("controller_status", &self.controller_status())
+            .field("protocol_violation", &self.protocol_violation())
+            .field("transceiver_status", &self.transceiver_status())
+            .field("error_counters", &self.error_counters())
+            .finish()
+    }
+}
+
+/// TX and RX error counters of the CAN controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanErrorCounters {
+    pub tx: u8,
+    pub rx: u8,
+}
+
+/// Problems reported by the CAN controller (`CAN_ERR_CRTL_*`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ControllerStatus(u8);
+
+impl ControllerStatus {
+    /// Returns the raw controller status
+    pub fn bits(&self) -> u8 {
+        self.0
+    }
+
+    fn has_flag(&self, flag: libc::c_int) -> bool {
+        self.0 & flag as u8 != 0
+    }
+
+    /// RX buffer overflow
+    pub fn rx_overflow(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_CRTL_RX_OVERFLOW)
+    }
+
+    /// TX buffer overflow
+    pub fn tx_overflow(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_CRTL_TX_OVERFLOW)
+    }
+
+    /// Reached the warning level for RX errors
+    pub fn rx_warning(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_CRTL_RX_WARNING)
+    }
+
+    /// Reached the warning level for TX errors
+    pub fn tx_warning(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_CRTL_TX_WARNING)
+    }
+
+    /// Reached the error passive status for RX
+    pub fn rx_passive(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_CRTL_RX_PASSIVE)
+    }
+
+    /// Reached the error passive status for TX
+    pub fn tx_passive(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_CRTL_TX_PASSIVE)
+    }
+
+    /// Recovered to the error active state
+    pub fn active(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_CRTL_ACTIVE)
+    }
+}
+
+/// Protocol violation detected by the CAN controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProtocolViolation {
+    /// Raw type of the violation (`CAN_ERR_PROT_*`)
+    pub kind: u8,
+    /// Location of the violation within the frame
+    pub location: ProtocolViolationLocation,
+}
+
+impl ProtocolViolation {
+    fn has_flag(&self, flag: libc::c_int) -> bool {
+        self.kind & flag as u8 != 0
+    }
+
+    /// Single bit error
+    pub fn is_bit_error(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_PROT_BIT)
+    }
+
+    /// Frame format error
+    pub fn is_form_error(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_PROT_FORM)
+    }
+
+    /// Bit stuffing error
+    pub fn is_stuff_error(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_PROT_STUFF)
+    }
+
+    /// Unable to send a dominant bit
+    pub fn is_bit0_error(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_PROT_BIT0)
+    }
+
+    /// Unable to send a recessive bit
+    pub fn is_bit1_error(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_PROT_BIT1)
+    }
+
+    /// Bus overload
+    pub fn is_overload(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_PROT_OVERLOAD)
+    }
+
+    /// Active error announcement
+    pub fn is_active_error(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_PROT_ACTIVE)
+    }
+
+    /// Error occurred on transmission
+    pub fn is_tx(&self) -> bool {
+        self.has_flag(libc::CAN_ERR_PROT_TX)
+    }
+}
+
+/// Location of a protocol violation within the frame (`CAN_ERR_PROT_LOC_*`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtocolViolationLocation {
+    Unspecified,
+    StartOfFrame,
+    Id28To21,
+    Id20To18,
+    SubstituteRtr,
+    IdentifierExtension,
+    Id17To13,
+    Id12To05,
+    Id04To00,
+    Rtr,
+    Reserved1,
+    Reserved0,
+    Dlc,
+    Data,
+    CrcSequence,
+    CrcDelimiter,
+    AckSlot,
+    AckDelimiter,
+    EndOfFrame,
+    Intermission,
+
+    Unknown(u8),
+}
+
+impl From<u8> for ProtocolViolationLocation {
+    fn from(location: u8) -> Self {
+        match location as libc::c_int {
+            libc::CAN_ERR_PROT_LOC_UNSPEC => Self::Unspecified,
+            libc::CAN_ERR_PROT_LOC_SOF => Self::StartOfFrame,
+            libc::CAN_ERR_PROT_LOC_ID28_21 => Self::Id28To21,
+            libc::CAN_ERR_PROT_LOC_ID20_18 => Self::Id20To18,
+            libc::CAN_ERR_PROT_LOC_SRTR => Self::SubstituteRtr,
+            libc::CAN_ERR_PROT_LOC_IDE => Self::IdentifierExtension,
+            libc::CAN_ERR_PROT_LOC_ID17_13 => Self::Id17To13,
+            libc::CAN_ERR_PROT_LOC_ID12_05 => Self::Id12To05,
+            libc::CAN_ERR_PROT_LOC_ID04_00 => Self::Id04To00,
+            libc::CAN_ERR_PROT_LOC_RTR => Self::Rtr,
+            libc::CAN_ERR_PROT_LOC_RES1 => Self::Reserved1,
+            libc::CAN_ERR_PROT_LOC_RES0 => Self::Reserved0,
+            libc::CAN_ERR_PROT_LOC_DLC => Self::Dlc,
+            libc::CAN_ERR_PROT_LOC_DATA => Self::Data,
+            libc::CAN_ERR_PROT_LOC_CRC_SEQ => Self::CrcSequence,
+            libc::CAN_ERR_PROT_LOC_CRC_DEL => Self::CrcDelimiter,
+            libc::CAN_ERR_PROT_LOC_ACK => Self::AckSlot,
+            libc::CAN_ERR_PROT_LOC_ACK_DEL => Self::AckDelimiter,
+            libc::CAN_ERR_PROT_LOC_EOF => Self::EndOfFrame,
+            libc::CAN_ERR_PROT_LOC_INTERM => Self::Intermission,
+            _ => Self::Unknown(location),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::can::CanFrame;
+
+    use super::{CanErrorCounters, CanErrorFrame, ProtocolViolationLocation};
+
+    fn error_frame(class: u32, data: [u8; 8]) -> CanErrorFrame {
+        let mut frame: libc::can_frame = unsafe { std::mem::zeroed() };
+        frame.can_id = libc::CAN_ERR_FLAG | class;
+        frame.can_dlc = libc::CAN_ERR_DLC as u8;
+        frame.data = data;
+
+        CanErrorFrame::from_frame(CanFrame::from_inner(frame)).unwrap()
+    }
+
+    #[test]
+    fn rejects_data_frames() {
+        let frame: libc::can_frame = unsafe { std::mem::zeroed() };
+        assert!(CanErrorFrame::from_frame(CanFrame::from_inner(frame)).is_none());
+    }
+
+    #[test]
+    fn decodes_bus_off() {
+        let frame = error_frame(libc::CAN_ERR_BUSOFF, [0; 8]);
+        assert!(frame.is_bus_off());
+        assert!(!frame.is_restarted());
+        assert!(frame.protocol_violation().is_none());
+        assert!(frame.error_counters().is_none());
+    }
+
+    #[test]
+    fn decodes_controller_status() {
+        let frame = error_frame(
+            libc::CAN_ERR_CRTL | libc::CAN_ERR_CNT,
+            [0, libc::CAN_ERR_CRTL_TX_PASSIVE as u8, 0, 0, 0, 0, 135, 12],
+        );
+        assert!(frame.is_error_passive());
+        assert!(frame.controller_status().tx_passive());
+        assert!(!frame.controller_status().rx_passive());
+        assert_eq!(
+            frame.error_counters(),
+            Some(CanErrorCounters { tx: 135, rx: 12 })
+        );
+    }
+
+    #[test]
+    fn decodes_protocol_violation() {
+        let frame = error_frame(
+            libc::CAN_ERR_PROT | libc::CAN_ERR_BUSERROR,
+            [
+                0,
+                0,
+                (libc::CAN_ERR_PROT_STUFF | libc::CAN_ERR_PROT_TX) as u8,
+                libc::CAN_ERR_PROT_LOC_ACK as u8,
+                0,
+                0,
+                0,
+                0,
+            ],
+        );
+        let violation = frame.protocol_violation().unwrap();
+        assert!(frame.is_bus_error());
+        assert!(violation.is_stuff_error());
+        assert!(violation.is_tx());
+        assert!(!violation.is_form_error());
+        assert_eq!(violation.location, ProtocolViolationLocation::AckSlot);
+    }
+
+    #[test]
+    fn decodes_lost_arbitration() {
+        let frame = error_frame(libc::CAN_ERR_LOSTARB, [5, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(frame.lost_arbitration_bit(), Some(5));
+        assert_eq!(error_frame(0, [5; 8]).lost_arbitration_bit(), None);
+    }
+}
@@ -0,0 +1,188 @@
+use embedded_hal::can::Frame;
+
+use super::frame::{id_from_raw, raw_can_id};
+
+/// Valid payload lengths of a CAN FD frame, indexed by the DLC
+const CANFD_DLC_TO_LEN: [usize; 16] = [0, 1, 2, 3, 4, 5, 6, 7, 8, 12, 16, 20, 24, 32, 48, 64];
+
+/// Converts a CAN FD data length code into the payload length in bytes
+///
+/// DLCs larger than 15 are clamped to the maximum length of 64 bytes.
+pub fn can_fd_dlc_to_len(dlc: u8) -> usize {
+    CANFD_DLC_TO_LEN[(dlc as usize).min(CANFD_DLC_TO_LEN.len() - 1)]
+}
+
+/// Converts a payload length in bytes into the CAN FD data length code
+///
+/// Lengths that can't be represented exactly are rounded up to the next valid
+/// length. Lengths larger than 64 bytes are clamped to the DLC 15.
+pub fn can_fd_len_to_dlc(len: usize) -> u8 {
+    CANFD_DLC_TO_LEN
+        .iter()
+        .position(|&dlc_len| dlc_len >= len)
+        .unwrap_or(CANFD_DLC_TO_LEN.len() - 1) as u8
+}
+
+/// Holds a complete CAN FD frame including the header.
+#[derive(Clone, Copy)]
+pub struct CanFdFrame(libc::canfd_frame);
+
+impl CanFdFrame {
+    /// Creates a new CAN FD frame from an Linux CAN FD frame
+    pub fn from_inner(canfd_frame: libc::canfd_frame) -> Self {
+        Self(canfd_frame)
+    }
+
+    /// Returns the inner representation of the CAN FD frame
+    pub fn inner(&self) -> &libc::canfd_frame {
+        &self.0
+    }
+
+    /// Returns the length of the payload in bytes
+    pub fn len(&self) -> usize {
+        self.0.len as usize
+    }
+
+    /// Returns `true` if the frame doesn't carry any payload
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+
+    /// Returns the raw CAN FD flags (`CANFD_BRS`, `CANFD_ESI`, ...)
+    pub fn flags(&self) -> u8 {
+        self.0.flags
+    }
+
+    /// Returns `true` if the payload is transmitted with the data bitrate
+    pub fn is_brs(&self) -> bool {
+        self.0.flags & libc::CANFD_BRS as u8 != 0
+    }
+
+    /// Enables or disables the bitrate switch for the payload
+    pub fn set_brs(&mut self, brs: bool) {
+        match brs {
+            true => self.0.flags |= libc::CANFD_BRS as u8,
+            false => self.0.flags &= !(libc::CANFD_BRS as u8),
+        }
+    }
+
+    /// Returns `true` if the transmitting node was error passive
+    pub fn is_esi(&self) -> bool {
+        self.0.flags & libc::CANFD_ESI as u8 != 0
+    }
+}
+
+impl embedded_hal::can::Frame for CanFdFrame {
+    /// Creates a new CAN FD frame
+    ///
+    /// Payloads which don't match one of the valid CAN FD lengths are padded
+    /// with zeros up to the next valid length.
+    fn new(id: impl Into<embedded_hal::can::Id>, data: &[u8]) -> Option<Self> {
+        // According to the trait defintion `None` shall be returned when
+        // the data slice is too long
+        if data.len() > libc::CANFD_MAX_DLEN {
+            return None;
+        }
+
+        // UNSAFE: The C struct layout needs to be zeroed in order for the padding
+        // and reserved values to be valid
+        let mut c_canfd_frame: libc::canfd_frame = unsafe { std::mem::zeroed() };
+        c_canfd_frame.can_id = raw_can_id(id);
+
+        // The zeroed data array already contains the padding bytes, so only the
+        // length must be rounded up
+        c_canfd_frame.len = can_fd_dlc_to_len(can_fd_len_to_dlc(data.len())) as u8;
+        c_canfd_frame.data[..data.len()].copy_from_slice(data);
+
+        Some(Self(c_canfd_frame))
+    }
+
+    fn new_remote(_id: impl Into<embedded_hal::can::Id>, _dlc: usize) -> Option<Self> {
+        // CAN FD doesn't support remote frames
+        None
+    }
+
+    fn is_extended(&self) -> bool {
+        self.0.can_id & libc::CAN_EFF_FLAG != 0
+    }
+
+    fn is_remote_frame(&self) -> bool {
+        false
+    }
+
+    fn id(&self) -> embedded_hal::can::Id {
+        id_from_raw(self.0.can_id)
+    }
+
+    fn dlc(&self) -> usize {
+        can_fd_len_to_dlc(self.len()) as usize
+    }
+
+    fn data(&self) -> &[u8] {
+        &self.0.data[..self.len().min(libc::CANFD_MAX_DLEN)]
+    }
+}
+
+impl std::fmt::Debug for CanFdFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CanFdFrame")
+            .field("id", &self.id())
+            .field("flags", &format_args!("{:#04X}", self.flags()))
+            .field("data", &self.data())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal::can::{Frame, StandardId};
+
+    use super::{can_fd_dlc_to_len, can_fd_len_to_dlc, CanFdFrame};
+
+    #[test]
+    fn converts_dlc_to_len() {
+        assert_eq!(can_fd_dlc_to_len(0), 0);
+        assert_eq!(can_fd_dlc_to_len(8), 8);
+        assert_eq!(can_fd_dlc_to_len(9), 12);
+        assert_eq!(can_fd_dlc_to_len(13), 32);
+        assert_eq!(can_fd_dlc_to_len(15), 64);
+        assert_eq!(can_fd_dlc_to_len(0xFF), 64);
+    }
+
+    #[test]
+    fn converts_len_to_dlc() {
+        assert_eq!(can_fd_len_to_dlc(0), 0);
+        assert_eq!(can_fd_len_to_dlc(8), 8);
+        assert_eq!(can_fd_len_to_dlc(9), 9);
+        assert_eq!(can_fd_len_to_dlc(12), 9);
+        assert_eq!(can_fd_len_to_dlc(33), 14);
+        assert_eq!(can_fd_len_to_dlc(64), 15);
+        assert_eq!(can_fd_len_to_dlc(65), 15);
+    }
+
+    #[test]
+    fn pads_payload_to_valid_len() {
+        let id = StandardId::new(0x123).unwrap();
+        let frame = CanFdFrame::new(id, &[0xFF; 9]).unwrap();
+        assert_eq!(frame.len(), 12);
+        assert_eq!(frame.dlc(), 9);
+        assert_eq!(
+            frame.data(),
+            [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0]
+        );
+
+        assert!(CanFdFrame::new(id, &[0xFF; 65]).is_none());
+    }
+
+    #[test]
+    fn sets_brs_flag() {
+        let id = StandardId::new(0x123).unwrap();
+        let mut frame = CanFdFrame::new(id, &[]).unwrap();
+        assert!(!frame.is_brs());
+        frame.set_brs(true);
+        assert!(frame.is_brs());
+        assert!(!frame.is_esi());
+        frame.set_brs(false);
+        assert_eq!(frame.flags(), 0);
+    }
+}
@@ -0,0 +1,143 @@
+use embedded_hal::can;
+
+/// Acceptance filter which is applied to received frames by the kernel.
+///
+/// A frame matches the filter if `received_id & mask == id & mask`. Filters
+/// created with [CanFilter::new()] only match frames with the same identifier
+/// type (standard or extended) as the given identifier. An inverted filter
+/// matches all frames that the non inverted filter would reject.
+///
+/// Filters are installed with `set_filters()` of the bus.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CanFilter {
+    id: u32,
+    mask: u32,
+    inverted: bool,
+}
+
+impl CanFilter {
+    /// Creates a filter matching the identifier under the given mask
+    ///
+    /// The mask is limited to the bits of the identifier type. Additionally,
+    /// the filter only matches frames with the same identifier type.
+    pub fn new(id: impl Into<can::Id>, mask: u32) -> Self {
+        let (id, mask) = match id.into() {
+            can::Id::Extended(extended_id) => (
+                extended_id.as_raw() | libc::CAN_EFF_FLAG,
+                mask & libc::CAN_EFF_MASK,
+            ),
+            can::Id::Standard(standard_id) => {
+                (standard_id.as_raw() as u32, mask & libc::CAN_SFF_MASK)
+            }
+        };
+
+        Self {
+            id,
+            mask: mask | libc::CAN_EFF_FLAG,
+            inverted: false,
+        }
+    }
+
+    /// Creates a filter matching exactly the given identifier
+    pub fn exact(id: impl Into<can::Id>) -> Self {
+        Self::new(id, libc::CAN_EFF_MASK)
+    }
+
+    /// Creates a filter from the raw `can_id` and `can_mask` values
+    ///
+    /// The values are passed to the kernel as is, so the flags in the upper
+    /// bits (e.g., `CAN_EFF_FLAG`, `CAN_RTR_FLAG`) can be matched as well.
+    pub fn from_raw(can_id: u32, can_mask: u32) -> Self {
+        Self {
+            id: can_id & !libc::CAN_INV_FILTER,
+            mask: can_mask,
+            inverted: can_id & libc::CAN_INV_FILTER != 0,
+        }
+    }
+
+    /// Creates a filter which matches all frames
+    pub fn accept_all() -> Self {
+        Self::from_raw(0, 0)
+    }
+
+    /// Inverts the filter so it matches all frames the filter would reject
+    pub fn inverted(mut self) -> Self {
+        self.inverted = !self.inverted;
+        self
+    }
+
+    /// Returns `true` if the filter is inverted
+    pub fn is_inverted(&self) -> bool {
+        self.inverted
+    }
+
+    /// Returns the identifier including the `CAN_EFF_FLAG`
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Returns the mask including the `CAN_EFF_FLAG`
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Returns `true` if a frame with the raw `can_id` passes the filter
+    pub fn matches(&self, can_id: u32) -> bool {
+        (can_id & self.mask == self.id & self.mask) != self.inverted
+    }
+
+    /// Returns the Linux representation of the filter
+    pub fn to_inner(&self) -> libc::can_filter {
+        let inv_flag = match self.inverted {
+            true => libc::CAN_INV_FILTER,
+            false => 0,
+        };
+
+        libc::can_filter {
+            can_id: self.id | inv_flag,
+            can_mask: self.mask,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal::can::{ExtendedId, StandardId};
+
+    use super::CanFilter;
+
+    #[test]
+    fn matches_standard_id() {
+        let filter = CanFilter::new(StandardId::new(0x120).unwrap(), 0x7F0);
+        assert!(filter.matches(0x120));
+        assert!(filter.matches(0x12F));
+        assert!(!filter.matches(0x130));
+        assert!(!filter.matches(0x120 | libc::CAN_EFF_FLAG));
+    }
+
+    #[test]
+    fn matches_extended_id() {
+        let filter = CanFilter::exact(ExtendedId::new(0x18DA00F1).unwrap());
+        assert!(filter.matches(0x18DA00F1 | libc::CAN_EFF_FLAG));
+        assert!(!filter.matches(0x18DA00F2 | libc::CAN_EFF_FLAG));
+        assert!(!filter.matches(0x0F1));
+    }
+
+    #[test]
+    fn matches_inverted() {
+        let filter = CanFilter::exact(StandardId::new(0x7DF).unwrap()).inverted();
+        assert!(!filter.matches(0x7DF));
+        assert!(filter.matches(0x7E0));
+        assert_eq!(filter.to_inner().can_id, 0x7DF | libc::CAN_INV_FILTER);
+
+        let inner = filter.to_inner();
+        assert_eq!(CanFilter::from_raw(inner.can_id, inner.can_mask), filter);
+    }
+
+    #[test]
+    fn accepts_all() {
+        let filter = CanFilter::accept_all();
+        assert!(filter.matches(0x000));
+        assert!(filter.matches(0x1FFFFFFF | libc::CAN_EFF_FLAG));
+    }
+}
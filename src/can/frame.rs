@@ -1,6 +1,25 @@
-use embedded_hal::can;
+use embedded_hal::can::{self, Frame};
+
+/// Converts an identifier into the Linux representation including the EFF flag
+pub(crate) fn raw_can_id(id: impl Into<can::Id>) -> libc::canid_t {
+    match id.into() {
+        can::Id::Extended(extended_id) => extended_id.as_raw() | libc::CAN_EFF_FLAG,
+        can::Id::Standard(standard_id) => standard_id.as_raw() as u32,
+    }
+}
+
+/// Converts the Linux representation of an identifier into an identifier
+pub(crate) fn id_from_raw(can_id: libc::canid_t) -> can::Id {
+    match can_id & libc::CAN_EFF_FLAG != 0 {
+        true => can::Id::Extended(can::ExtendedId::new(can_id & libc::CAN_EFF_MASK).unwrap()),
+        false => {
+            can::Id::Standard(can::StandardId::new((can_id & libc::CAN_SFF_MASK) as u16).unwrap())
+        }
+    }
+}
 
 /// Holds a complete CAN frame including the header.
+#[derive(Clone, Copy)]
 pub struct CanFrame(libc::can_frame);
 
 impl CanFrame {
@@ -12,6 +31,21 @@ impl CanFrame {
     pub fn inner(&self) -> &libc::can_frame {
         &self.0
     }
+
+    /// Returns `true` if the frame is an error frame generated by the driver
+    ///
+    /// Use [CanErrorFrame](super::CanErrorFrame) to decode the error.
+    pub fn is_error_frame(&self) -> bool {
+        self.0.can_id & libc::CAN_ERR_FLAG != 0
+    }
+}
+
+impl Default for CanFrame {
+    /// Creates an empty data frame with the standard identifier `0`
+    fn default() -> Self {
+        // UNSAFE: A zeroed frame is a valid frame
+        Self(unsafe { std::mem::zeroed() })
+    }
 }
 
 impl embedded_hal::can::Frame for CanFrame {
@@ -22,20 +56,13 @@ impl embedded_hal::can::Frame for CanFrame {
             return None;
         }
 
-        // We need to know if we deal with an EFF because we need to set
-        // the bit in the can_id field of the C representation
-        let (raw_id, eff_flag) = match id.into() {
-            can::Id::Extended(extended_id) => (extended_id.as_raw(), libc::CAN_EFF_FLAG),
-            can::Id::Standard(standard_id) => (standard_id.as_raw() as u32, 0),
-        };
-
         // UNSAFE: The C struct layout needs to be zeroed in order for the padding
         // and reserved values to be valid
         let mut c_can_frame: libc::can_frame = unsafe { std::mem::zeroed() };
 
         // The EFF flag is part of the CAN id field, so we need to set
         // it according to the used id type
-        c_can_frame.can_id = raw_id | eff_flag;
+        c_can_frame.can_id = raw_can_id(id);
 
         c_can_frame.can_dlc = data.len() as u8;
 
@@ -53,19 +80,12 @@ impl embedded_hal::can::Frame for CanFrame {
             return None;
         }
 
-        // We need to know if we deal with an EFF because we need to set
-        // the bit in the can_id field of the C representation
-        let (raw_id, eff_flag) = match id.into() {
-            can::Id::Extended(extended_id) => (extended_id.as_raw(), libc::CAN_EFF_FLAG),
-            can::Id::Standard(standard_id) => (standard_id.as_raw() as u32, 0),
-        };
-
         // UNSAFE: The C struct layout needs to be zeroed in order for the padding
         // and reserved values to be valid
         let mut c_can_frame: libc::can_frame = unsafe { std::mem::zeroed() };
 
         // The EFF and RTR flag is part of the CAN id field, so we need to set them
-        c_can_frame.can_id = raw_id | eff_flag | libc::CAN_RTR_FLAG;
+        c_can_frame.can_id = raw_can_id(id) | libc::CAN_RTR_FLAG;
         c_can_frame.can_dlc = dlc as u8;
 
         Some(Self(c_can_frame))
@@ -80,17 +100,7 @@ impl embedded_hal::can::Frame for CanFrame {
     }
 
     fn id(&self) -> embedded_hal::can::Id {
-        match self.is_extended() {
-            true => {
-                let extended_id = can::ExtendedId::new(self.0.can_id & libc::CAN_EFF_MASK).unwrap();
-                can::Id::Extended(extended_id)
-            }
-            false => {
-                let standard_id =
-                    can::StandardId::new((self.0.can_id & libc::CAN_SFF_MASK) as u16).unwrap();
-                can::Id::Standard(standard_id)
-            }
-        }
+        id_from_raw(self.0.can_id)
     }
 
     fn dlc(&self) -> usize {
@@ -101,3 +111,14 @@ impl embedded_hal::can::Frame for CanFrame {
         &self.0.data
     }
 }
+
+impl std::fmt::Debug for CanFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CanFrame")
+            .field("id", &self.id())
+            .field("remote", &self.is_remote_frame())
+            .field("dlc", &self.dlc())
+            .field("data", &self.data())
+            .finish()
+    }
+}
@@ -0,0 +1,540 @@
+//! Reading and writing the log format of `candump -l` from the can-utils.
+//!
+//! Each line holds one frame with its timestamp and the name of the interface
+//! it was received on:
+//! ```text
+//! (1697000000.123456) can0 123#DEADBEEF
+//! (1697000000.124000) can0 12345678#R
+//! (1697000000.125000) can0 321##1112233445566778899
+//! (1697000000.126000) can0 00242#80:03:DEADBEEF#0102030405
+//! ```
+//! Classic frames use a single `#`, CAN FD frames use `##` followed by the
+//! FD flags and remote frames are marked with `R`. Error frames are written
+//! with the `CAN_ERR_FLAG` set in the 8 digit identifier. CAN XL frames use
+//! a 5 digit identifier of the VCID and priority, followed by the flags, the
+//! SDU type and the acceptance field.
+
+use std::{
+    io::{BufRead, Write},
+    time::{Duration, SystemTime},
+};
+
+use embedded_hal::can::Frame;
+use thiserror::Error;
+
+use super::{CanAnyFrame, CanErrorFrame, CanFdFrame, CanFrame, CanXlFrame};
+
+#[derive(Debug, Error)]
+pub enum LogError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid log entry: {0}")]
+    InvalidEntry(String),
+
+    #[error("Invalid log line {line}: {reason}")]
+    InvalidLine { line: usize, reason: String },
+}
+
+/// Single line of a candump log
+#[derive(Clone)]
+pub struct LogEntry {
+    /// Time at which the frame was received
+    pub timestamp: SystemTime,
+    /// Name of the interface (e.g., `can0`)
+    pub interface: String,
+    /// The received frame
+    pub frame: CanAnyFrame,
+}
+
+impl LogEntry {
+    pub fn new(timestamp: SystemTime, interface: impl Into<String>, frame: CanAnyFrame) -> Self {
+        Self {
+            timestamp,
+            interface: interface.into(),
+            frame,
+        }
+    }
+
+    /// Parses a single line of a candump log
+    pub fn parse(line: &str) -> Result<Self, LogError> {
+        Self::parse_line(line).map_err(LogError::InvalidEntry)
+    }
+
+    fn parse_line(line: &str) -> Result<Self, String> {
+        let mut parts = line.split_whitespace();
+
+        let timestamp = parts.next().ok_or("Missing timestamp")?;
+        let timestamp = timestamp
+            .strip_prefix('(')
+            .and_then(|timestamp| timestamp.strip_suffix(')'))
+            .ok_or("Timestamp must be enclosed in parentheses")?;
+        let timestamp = SystemTime::UNIX_EPOCH
+            .checked_add(parse_seconds(timestamp)?)
+            .ok_or_else(|| format!("Timestamp '{}' is out of range", timestamp))?;
+
+        let interface = parts.next().ok_or("Missing interface")?;
+        let frame = parse_frame(parts.next().ok_or("Missing frame")?)?;
+
+        Ok(Self::new(timestamp, interface, frame))
+    }
+}
+
+impl std::str::FromStr for LogEntry {
+    type Err = LogError;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        Self::parse(line)
+    }
+}
+
+impl std::fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let timestamp = self
+            .timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+
+        write!(
+            f,
+            "({}.{:06}) {} ",
+            timestamp.as_secs(),
+            timestamp.subsec_micros(),
+            self.interface
+        )?;
+        write_frame(f, &self.frame)
+    }
+}
+
+/// Parses seconds with an optional fraction (e.g., `12.345678`)
+pub(super) fn parse_seconds(seconds: &str) -> Result<Duration, String> {
+    let invalid = || format!("Invalid timestamp '{}'", seconds);
+
+    let (secs, fraction) = seconds.split_once('.').unwrap_or((seconds, "0"));
+    if !fraction.bytes().all(|c| c.is_ascii_digit()) {
+        return Err(invalid());
+    }
+    let secs: u64 = secs.parse().map_err(|_| invalid())?;
+
+    // The fraction may have any number of digits, so it is scaled to nanoseconds
+    let digits = fraction.len().min(9);
+    let nanos: u32 = fraction[..digits].parse().map_err(|_| invalid())?;
+    let nanos = nanos * 10u32.pow(9 - digits as u32);
+
+    Ok(Duration::new(secs, nanos))
+}
+
+fn parse_hex(data: &str) -> Result<Vec<u8>, String> {
+    // cansend allows dots between the bytes for readability
+    let digits = data
+        .bytes()
+        .filter(|c| *c != b'.')
+        .map(|c| (c as char).to_digit(16).map(|digit| digit as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| format!("Invalid hex data '{}'", data))?;
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("Odd number of hex digits in '{}'", data));
+    }
+
+    Ok(digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair[1])
+        .collect())
+}
+
+fn parse_frame(frame: &str) -> Result<CanAnyFrame, String> {
+    let (id, payload) = frame
+        .split_once('#')
+        .ok_or_else(|| format!("Missing '#' in frame '{}'", frame))?;
+
+    if id.len() == 5 {
+        return parse_xl_frame(frame, id, payload);
+    }
+
+    let raw_id = u32::from_str_radix(id, 16).map_err(|_| format!("Invalid identifier '{}'", id))?;
+    let can_id = match id.len() {
+        3 if raw_id <= libc::CAN_SFF_MASK => raw_id,
+        8 if raw_id & libc::CAN_ERR_FLAG != 0 => raw_id & (libc::CAN_ERR_FLAG | libc::CAN_ERR_MASK),
+        8 if raw_id <= libc::CAN_EFF_MASK => raw_id | libc::CAN_EFF_FLAG,
+        _ => return Err(format!("Invalid identifier '{}'", id)),
+    };
+
+    // CAN FD frames: ID##<flags><data>
+    if let Some(payload) = payload.strip_prefix('#') {
+        let mut chars = payload.chars();
+        let flags = chars
+            .next()
+            .and_then(|flags| flags.to_digit(16))
+            .ok_or_else(|| format!("Missing CAN FD flags in frame '{}'", frame))?;
+        let data = parse_hex(chars.as_str())?;
+        if data.len() > libc::CANFD_MAX_DLEN {
+            return Err(format!("Payload of frame '{}' is too long", frame));
+        }
+
+        let mut c_canfd_frame: libc::canfd_frame = unsafe { std::mem::zeroed() };
+        c_canfd_frame.can_id = can_id;
+        c_canfd_frame.len = data.len() as u8;
+        c_canfd_frame.flags = flags as u8;
+        c_canfd_frame.data[..data.len()].copy_from_slice(&data);
+        return Ok(CanAnyFrame::Fd(CanFdFrame::from_inner(c_canfd_frame)));
+    }
+
+    let mut c_can_frame: libc::can_frame = unsafe { std::mem::zeroed() };
+    c_can_frame.can_id = can_id;
+
+    // Remote frames: ID#R or ID#R<dlc>
+    if let Some(dlc) = payload.strip_prefix('R') {
+        let dlc = match dlc {
+            "" => 0,
+            dlc => dlc
+                .parse::<u8>()
+                .ok()
+                .filter(|dlc| *dlc as usize <= libc::CAN_MAX_DLEN)
+                .ok_or_else(|| format!("Invalid DLC in frame '{}'", frame))?,
+        };
+        c_can_frame.can_id |= libc::CAN_RTR_FLAG;
+        c_can_frame.can_dlc = dlc;
+        return Ok(CanAnyFrame::Classic(CanFrame::from_inner(c_can_frame)));
+    }
+
+    // Classic frames: ID#<data> with an optional _<len8_dlc> suffix, which
+    // is only valid for 8 data bytes and a DLC of 9 to 15
+    let (data, len8_dlc) = match payload.split_once('_') {
+        Some((data, len8_dlc)) => (data, Some(len8_dlc)),
+        None => (payload, None),
+    };
+    let data = parse_hex(data)?;
+    if data.len() > libc::CAN_MAX_DLEN {
+        return Err(format!("Payload of frame '{}' is too long", frame));
+    }
+    if let Some(len8_dlc) = len8_dlc {
+        c_can_frame.len8_dlc = u8::from_str_radix(len8_dlc, 16)
+            .ok()
+            .filter(|dlc| data.len() == libc::CAN_MAX_DLEN && (9..=15).contains(dlc))
+            .ok_or_else(|| format!("Invalid DLC in frame '{}'", frame))?;
+    }
+    c_can_frame.can_dlc = data.len() as u8;
+    c_can_frame.data[..data.len()].copy_from_slice(&data);
+
+    let frame = CanFrame::from_inner(c_can_frame);
+    match CanErrorFrame::from_frame(frame) {
+        Some(error_frame) => Ok(CanAnyFrame::Error(error_frame)),
+        None => Ok(CanAnyFrame::Classic(frame)),
+    }
+}
+
+fn write_id(f: &mut std::fmt::Formatter<'_>, can_id: u32) -> std::fmt::Result {
+    match can_id & libc::CAN_EFF_FLAG != 0 {
+        true => write!(f, "{:08X}", can_id & libc::CAN_EFF_MASK),
+        false => write!(f, "{:03X}", can_id & libc::CAN_SFF_MASK),
+    }
+}
+
+fn write_hex(f: &mut std::fmt::Formatter<'_>, data: &[u8]) -> std::fmt::Result {
+    data.iter().try_for_each(|byte| write!(f, "{:02X}", byte))
+}
+
+/// Parses a CAN XL frame: VVPPP#<flags>:<sdt>:<af>#<data>
+fn parse_xl_frame(frame: &str, id: &str, payload: &str) -> Result<CanAnyFrame, String> {
+    let invalid = || format!("Invalid CAN XL frame '{}'", frame);
+
+    let raw_id = u32::from_str_radix(id, 16).map_err(|_| format!("Invalid identifier '{}'", id))?;
+    let (header, data) = payload.split_once('#').ok_or_else(invalid)?;
+    let mut fields = header.split(':');
+    let mut field = |digits: usize| {
+        fields
+            .next()
+            .filter(|field| field.len() == digits)
+            .and_then(|field| u32::from_str_radix(field, 16).ok())
+            .ok_or_else(invalid)
+    };
+    let (flags, sdt, af) = (field(2)?, field(2)?, field(8)?);
+
+    let data = parse_hex(data)?;
+    let mut canxl_frame = CanXlFrame::new((raw_id & 0xFFF) as u16, sdt as u8, af, &data)
+        .ok_or_else(|| format!("Invalid payload or priority in frame '{}'", frame))?;
+    canxl_frame.set_vcid((raw_id >> 12) as u8);
+
+    let mut c_canxl_frame = *canxl_frame.inner();
+    c_canxl_frame.flags = flags as u8 | libc::CANXL_XLF as u8;
+    Ok(CanXlFrame::from_inner(c_canxl_frame).into())
+}
+
+fn write_frame(f: &mut std::fmt::Formatter<'_>, frame: &CanAnyFrame) -> std::fmt::Result {
+    match frame {
+        CanAnyFrame::Classic(frame) => {
+            let inner = frame.inner();
+            write_id(f, inner.can_id)?;
+            write!(f, "#")?;
+            if frame.is_remote_frame() {
+                write!(f, "R")?;
+                if inner.can_dlc > 0 {
+                    write!(f, "{}", inner.can_dlc)?;
+                }
+                return Ok(());
+            }
+            write_hex(f, &inner.data[..frame.dlc().min(libc::CAN_MAX_DLEN)])?;
+            if inner.can_dlc as usize == libc::CAN_MAX_DLEN
+                && inner.len8_dlc as usize > libc::CAN_MAX_DLEN
+            {
+                write!(f, "_{:X}", inner.len8_dlc)?;
+            }
+            Ok(())
+        }
+        CanAnyFrame::Fd(frame) => {
+            write_id(f, frame.inner().can_id)?;
+            write!(f, "##{:X}", frame.flags() & 0x0F)?;
+            write_hex(f, frame.data())
+        }
+        CanAnyFrame::Xl(frame) => {
+            write!(
+                f,
+                "{:02X}{:03X}#{:02X}:{:02X}:{:08X}#",
+                frame.vcid(),
+                frame.prio(),
+                frame.flags(),
+                frame.sdt(),
+                frame.af()
+            )?;
+            write_hex(f, frame.data())
+        }
+        CanAnyFrame::Error(frame) => {
+            let inner = frame.frame().inner();
+            write!(
+                f,
+                "{:08X}#",
+                inner.can_id & (libc::CAN_ERR_FLAG | libc::CAN_ERR_MASK)
+            )?;
+            write_hex(
+                f,
+                &inner.data[..(inner.can_dlc as usize).min(libc::CAN_MAX_DLEN)],
+            )
+        }
+    }
+}
+
+/// Reads the entries of a candump log line by line
+///
+/// Empty lines are skipped.
+pub struct LogReader<R: BufRead> {
+    reader: R,
+    line: usize,
+}
+
+impl<R: BufRead> LogReader<R> {
+    pub fn new(reader: R) -> Self {
+        Self { reader, line: 0 }
+    }
+
+    /// Reads the next entry
+    ///
+    /// Returns `None` at the end of the log.
+    pub fn read_entry(&mut self) -> Result<Option<LogEntry>, LogError> {
+        let mut line = String::new();
+        loop {
+            line.clear();
+            if self.reader.read_line(&mut line)? == 0 {
+                return Ok(None);
+            }
+            self.line += 1;
+
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            return LogEntry::parse_line(&line)
+                .map(Some)
+                .map_err(|reason| LogError::InvalidLine {
+                    line: self.line,
+                    reason,
+                });
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for LogReader<R> {
+    type Item = Result<LogEntry, LogError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+/// Writes entries in the candump log format
+pub struct LogWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> LogWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer }
+    }
+
+    /// Writes a single entry as line
+    pub fn write_entry(&mut self, entry: &LogEntry) -> Result<(), LogError> {
+        writeln!(self.writer, "{}", entry)?;
+        Ok(())
+    }
+
+    /// Flushes the underlying writer
+    pub fn flush(&mut self) -> Result<(), LogError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use embedded_hal::can::Frame;
+
+    use super::{LogEntry, LogReader, LogWriter};
+    use crate::can::CanAnyFrame;
+
+    fn round_trip(line: &str) -> LogEntry {
+        let entry = LogEntry::parse(line).unwrap();
+        assert_eq!(entry.to_string(), line);
+        entry
+    }
+
+    #[test]
+    fn parses_classic_frame() {
+        let entry = round_trip("(1697000000.123456) can0 123#DEADBEEF");
+        assert_eq!(
+            entry.timestamp,
+            SystemTime::UNIX_EPOCH + Duration::new(1697000000, 123456000)
+        );
+        assert_eq!(entry.interface, "can0");
+        match entry.frame {
+            CanAnyFrame::Classic(frame) => {
+                assert!(!frame.is_extended());
+                assert_eq!(frame.inner().can_id, 0x123);
+                assert_eq!(frame.dlc(), 4);
+                assert_eq!(&frame.data()[..4], [0xDE, 0xAD, 0xBE, 0xEF]);
+            }
+            _ => panic!("Expected classic frame"),
+        }
+    }
+
+    #[test]
+    fn parses_extended_frame() {
+        let entry = round_trip("(0.000001) vcan1 18DAF110#");
+        match entry.frame {
+            CanAnyFrame::Classic(frame) => {
+                assert!(frame.is_extended());
+                assert_eq!(frame.inner().can_id, 0x18DAF110 | libc::CAN_EFF_FLAG);
+                assert_eq!(frame.dlc(), 0);
+            }
+            _ => panic!("Expected classic frame"),
+        }
+    }
+
+    #[test]
+    fn parses_remote_frame() {
+        round_trip("(1.000000) can0 7DF#R");
+        let entry = round_trip("(1.000000) can0 7DF#R3");
+        match entry.frame {
+            CanAnyFrame::Classic(frame) => {
+                assert!(frame.is_remote_frame());
+                assert_eq!(frame.dlc(), 3);
+            }
+            _ => panic!("Expected classic frame"),
+        }
+    }
+
+    #[test]
+    fn parses_fd_frame() {
+        let entry = round_trip("(1.500000) can0 321##1112233445566778899");
+        match entry.frame {
+            CanAnyFrame::Fd(frame) => {
+                assert!(frame.is_brs());
+                assert_eq!(frame.len(), 9);
+                assert_eq!(frame.data()[8], 0x99);
+            }
+            _ => panic!("Expected CAN FD frame"),
+        }
+    }
+
+    #[test]
+    fn parses_xl_frame() {
+        let entry = round_trip("(1.500000) can0 42123#81:03:DEADBEEF#0102030405");
+        assert_eq!(entry.frame.id(), None);
+        match entry.frame {
+            CanAnyFrame::Xl(frame) => {
+                assert_eq!(frame.vcid(), 0x42);
+                assert_eq!(frame.prio(), 0x123);
+                assert!(frame.is_sec());
+                assert_eq!(frame.sdt(), 0x03);
+                assert_eq!(frame.af(), 0xDEADBEEF);
+                assert_eq!(frame.data(), [1, 2, 3, 4, 5]);
+            }
+            _ => panic!("Expected CAN XL frame"),
+        }
+
+        assert!(LogEntry::parse("(1.0) can0 00123#80:03#01").is_err());
+        assert!(LogEntry::parse("(1.0) can0 00123#80:03:00000000#").is_err());
+        assert!(LogEntry::parse("(1.0) can0 00800#80:03:00000000#01").is_err());
+    }
+
+    #[test]
+    fn parses_error_frame() {
+        let entry = round_trip("(1.500000) can0 20000040#0000000000000000");
+        match entry.frame {
+            CanAnyFrame::Error(frame) => assert!(frame.is_bus_off()),
+            _ => panic!("Expected error frame"),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_lines() {
+        assert!(LogEntry::parse("").is_err());
+        assert!(LogEntry::parse("1.0 can0 123#00").is_err());
+        assert!(LogEntry::parse("(1.0) can0 123").is_err());
+        assert!(LogEntry::parse("(1.0) can0 1234#00").is_err());
+        assert!(LogEntry::parse("(1.0) can0 123#0").is_err());
+        assert!(LogEntry::parse("(1.0) can0 123#000000000000000000").is_err());
+        assert!(LogEntry::parse("(1.0) can0 123#aéb").is_err());
+        assert!(LogEntry::parse("(18446744073709551615.0) can0 123#00").is_err());
+    }
+
+    #[test]
+    fn parses_len8_dlc() {
+        let entry = round_trip("(1.000000) can0 123#0011223344556677_C");
+        match entry.frame {
+            CanAnyFrame::Classic(frame) => assert_eq!(frame.inner().len8_dlc, 0x0C),
+            _ => panic!("Expected classic frame"),
+        }
+
+        assert!(LogEntry::parse("(1.0) can0 123#00112233_C").is_err());
+        assert!(LogEntry::parse("(1.0) can0 123#0011223344556677_8").is_err());
+        assert!(LogEntry::parse("(1.0) can0 123#0011223344556677_10").is_err());
+        assert!(LogEntry::parse("(1.0) can0 123#0011223344556677_").is_err());
+        assert!("(1.0) can0 123#0011223344556677_F"
+            .parse::<LogEntry>()
+            .is_ok());
+    }
+
+    #[test]
+    fn reads_and_writes_logs() {
+        let log = "(1.000000) can0 123#00\n\n(2.000000) can1 456##0\n";
+        let entries: Vec<_> = LogReader::new(log.as_bytes())
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(entries.len(), 2);
+
+        let mut writer = LogWriter::new(Vec::new());
+        for entry in &entries {
+            writer.write_entry(entry).unwrap();
+        }
+        assert_eq!(
+            writer.into_inner(),
+            b"(1.000000) can0 123#00\n(2.000000) can1 456##0\n"
+        );
+    }
+}
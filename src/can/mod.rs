@@ -1,5 +1,38 @@
+mod any_frame;
+pub mod asc;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+mod bcm;
+#[cfg(feature = "blocking")]
+pub(crate) mod blocking;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 mod bus;
+mod error_frame;
+mod fd_frame;
+mod filter;
 mod frame;
+pub mod log;
+mod options;
+pub mod pcap;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+mod resilient;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+mod split;
+mod tx_queue;
+mod xl_frame;
 
+pub use any_frame::*;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use bcm::*;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 pub use bus::*;
+pub use error_frame::*;
+pub use fd_frame::*;
+pub use filter::*;
 pub use frame::*;
+pub use options::CanTimestamping;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use resilient::*;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use split::*;
+pub use tx_queue::*;
+pub use xl_frame::*;
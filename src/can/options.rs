@@ -0,0 +1,56 @@
+//! Socket options shared by the async and the blocking [CanBus](super::CanBus)
+
+use crate::{
+    socket::RawSocket,
+    sockopt::{self, SocketOption},
+};
+
+use super::CanFilter;
+
+/// Source of the receive timestamps of a CAN bus
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CanTimestamping {
+    /// No timestamps are recorded (default)
+    Disabled,
+    /// The kernel records the time of reception (`SO_TIMESTAMPNS`)
+    Software,
+    /// The kernel records the time of reception and the timestamp of the
+    /// controller if the hardware supports it (`SO_TIMESTAMPING`)
+    Hardware,
+}
+
+/// Installs the acceptance filters
+pub(super) fn set_filters(socket: &RawSocket, filters: &[CanFilter]) -> Result<(), std::io::Error> {
+    let filters: Vec<libc::can_filter> = filters.iter().map(CanFilter::to_inner).collect();
+    socket.set_option(sockopt::CAN_RAW_FILTER, filters.as_slice())
+}
+
+/// Enables or disables an option with an integer flag as value
+pub(super) fn set_flag(
+    socket: &RawSocket,
+    option: SocketOption<libc::c_int>,
+    enable: bool,
+) -> Result<(), std::io::Error> {
+    socket.set_option(option, &(enable as libc::c_int))
+}
+
+/// Selects the receive timestamps
+pub(super) fn set_timestamping(
+    socket: &RawSocket,
+    timestamping: CanTimestamping,
+) -> Result<(), std::io::Error> {
+    let (timestampns, timestamping): (libc::c_int, libc::c_uint) = match timestamping {
+        CanTimestamping::Disabled => (0, 0),
+        CanTimestamping::Software => (1, 0),
+        CanTimestamping::Hardware => (
+            0,
+            libc::SOF_TIMESTAMPING_RX_SOFTWARE
+                | libc::SOF_TIMESTAMPING_SOFTWARE
+                | libc::SOF_TIMESTAMPING_RX_HARDWARE
+                | libc::SOF_TIMESTAMPING_RAW_HARDWARE,
+        ),
+    };
+
+    socket.set_option(sockopt::SO_TIMESTAMPNS, &timestampns)?;
+    socket.set_option(sockopt::SO_TIMESTAMPING, &timestamping)
+}
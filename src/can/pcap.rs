@@ -0,0 +1,722 @@
+//! Writing pcapng captures and reading pcap/pcapng captures of CAN frames.
+//!
+//! Frames are stored with the link type `LINKTYPE_CAN_SOCKETCAN`, which is
+//! understood by Wireshark including its ISO-TP and UDS dissectors. Each
+//! interface is recorded with its name and nanosecond timestamps.
+//!
+//! # Example:
+//! ```no_run
+//! # use ddose::{pcap::PcapWriter, CanBus, CanInterface, CanTimestamping};
+//! # #[tokio::main] async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut can_bus = CanBus::open_fd(&CanInterface::try_from("can0")?)?;
+//! can_bus.set_timestamping(CanTimestamping::Software)?;
+//!
+//! let file = std::fs::File::create("capture.pcapng")?;
+//! let mut writer = PcapWriter::new(std::io::BufWriter::new(file))?;
+//! for _ in 0..100 {
+//!     let (frame, meta) = can_bus.read_timestamped().await?;
+//!     let timestamp = meta.software_timestamp.unwrap_or_else(std::time::SystemTime::now);
+//!     writer.write_frame(timestamp, "can0", &frame)?;
+//! }
+//! writer.flush()?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::{
+    io::{Read, Write},
+    time::{Duration, SystemTime},
+};
+
+use thiserror::Error;
+
+use super::{CanAnyFrame, CanErrorFrame, CanFdFrame, CanFrame};
+
+/// Link type of SocketCAN frames
+pub const LINKTYPE_CAN_SOCKETCAN: u16 = 227;
+
+const BLOCK_SECTION_HEADER: u32 = 0x0A0D_0D0A;
+const BLOCK_INTERFACE_DESCRIPTION: u32 = 0x0000_0001;
+const BLOCK_ENHANCED_PACKET: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+
+const OPTION_END: u16 = 0;
+const OPTION_IF_NAME: u16 = 2;
+const OPTION_IF_TSRESOL: u16 = 9;
+
+const PCAP_MAGIC_MICROS: u32 = 0xA1B2_C3D4;
+const PCAP_MAGIC_NANOS: u32 = 0xA1B2_3C4D;
+
+/// Blocks larger than this are rejected to not allocate arbitrary memory
+const MAX_BLOCK_LEN: usize = 16 * 1024 * 1024;
+
+/// Length of the header in front of the payload of a frame
+const FRAME_HEADER_LEN: usize = 8;
+
+#[derive(Debug, Error)]
+pub enum PcapError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid capture: {0}")]
+    InvalidFormat(String),
+}
+
+/// Frame of a capture
+#[derive(Clone)]
+pub struct PcapEntry {
+    /// Time at which the frame was captured
+    pub timestamp: SystemTime,
+    /// Name of the interface, empty if the capture doesn't contain it
+    pub interface: String,
+    /// The captured frame
+    pub frame: CanAnyFrame,
+}
+
+/// Encodes a frame as `LINKTYPE_CAN_SOCKETCAN` packet
+///
+/// The identifier is stored in network byte order, the remaining layout
+/// matches `can_frame` and `canfd_frame`. CAN XL frames aren't supported.
+fn encode_frame(frame: &CanAnyFrame) -> Result<Vec<u8>, PcapError> {
+    let (can_id, len, flags, len8_dlc, data): (_, _, _, _, &[u8]) = match frame {
+        CanAnyFrame::Classic(frame) => {
+            let inner = frame.inner();
+            (inner.can_id, inner.can_dlc, 0, inner.len8_dlc, &inner.data)
+        }
+        CanAnyFrame::Error(frame) => {
+            let inner = frame.frame().inner();
+            (inner.can_id, inner.can_dlc, 0, 0, &inner.data)
+        }
+        CanAnyFrame::Fd(frame) => {
+            let inner = frame.inner();
+            let flags = inner.flags | libc::CANFD_FDF as u8;
+            (inner.can_id, inner.len, flags, 0, &inner.data)
+        }
+        CanAnyFrame::Xl(_) => {
+            return Err(PcapError::InvalidFormat(
+                "CAN XL frames can't be captured".to_string(),
+            ))
+        }
+    };
+
+    let mut packet = Vec::with_capacity(FRAME_HEADER_LEN + data.len());
+    packet.extend_from_slice(&can_id.to_be_bytes());
+    packet.extend_from_slice(&[len, flags, 0, len8_dlc]);
+    packet.extend_from_slice(data);
+    Ok(packet)
+}
+
+/// Decodes a `LINKTYPE_CAN_SOCKETCAN` packet
+fn decode_frame(packet: &[u8]) -> Result<CanAnyFrame, PcapError> {
+    if packet.len() < FRAME_HEADER_LEN {
+        return Err(PcapError::InvalidFormat(format!(
+            "Packet of {} bytes is too short",
+            packet.len()
+        )));
+    }
+
+    // UNWRAP: The length was checked above
+    let can_id = u32::from_be_bytes(packet[..4].try_into().unwrap());
+    let len = packet[4] as usize;
+    let flags = packet[5];
+    let data = &packet[FRAME_HEADER_LEN..];
+
+    let is_fd = flags & libc::CANFD_FDF as u8 != 0 || data.len() > libc::CAN_MAX_DLEN;
+    let max_len = match is_fd {
+        true => libc::CANFD_MAX_DLEN,
+        false => libc::CAN_MAX_DLEN,
+    };
+    if len > max_len {
+        return Err(PcapError::InvalidFormat(format!(
+            "Invalid payload length {}",
+            len
+        )));
+    }
+    let data = &data[..data.len().min(max_len)];
+
+    if is_fd {
+        let mut c_canfd_frame: libc::canfd_frame = unsafe { std::mem::zeroed() };
+        c_canfd_frame.can_id = can_id;
+        c_canfd_frame.len = len as u8;
+        c_canfd_frame.flags = flags & !(libc::CANFD_FDF as u8);
+        c_canfd_frame.data[..data.len()].copy_from_slice(data);
+        return Ok(CanAnyFrame::Fd(CanFdFrame::from_inner(c_canfd_frame)));
+    }
+
+    let mut c_can_frame: libc::can_frame = unsafe { std::mem::zeroed() };
+    c_can_frame.can_id = can_id;
+    c_can_frame.can_dlc = len as u8;
+    c_can_frame.len8_dlc = packet[7];
+    c_can_frame.data[..data.len()].copy_from_slice(data);
+
+    let frame = CanFrame::from_inner(c_can_frame);
+    match CanErrorFrame::from_frame(frame) {
+        Some(error_frame) => Ok(CanAnyFrame::Error(error_frame)),
+        None => Ok(CanAnyFrame::Classic(frame)),
+    }
+}
+
+/// Writes frames as pcapng capture with the `LINKTYPE_CAN_SOCKETCAN` link type
+///
+/// The section header is written on creation. An interface description is
+/// added for every new interface name.
+pub struct PcapWriter<W: Write> {
+    writer: W,
+    interfaces: Vec<String>,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Creates a writer and writes the section header
+    pub fn new(writer: W) -> Result<Self, PcapError> {
+        let mut pcap_writer = Self {
+            writer,
+            interfaces: Vec::new(),
+        };
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        body.extend_from_slice(&1u16.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        // The length of the section is not known
+        body.extend_from_slice(&(-1i64).to_le_bytes());
+        pcap_writer.write_block(BLOCK_SECTION_HEADER, &body)?;
+
+        Ok(pcap_writer)
+    }
+
+    fn write_block(&mut self, block_type: u32, body: &[u8]) -> Result<(), PcapError> {
+        let padding = (4 - body.len() % 4) % 4;
+        let total_len = (12 + body.len() + padding) as u32;
+
+        self.writer.write_all(&block_type.to_le_bytes())?;
+        self.writer.write_all(&total_len.to_le_bytes())?;
+        self.writer.write_all(body)?;
+        self.writer.write_all(&[0; 3][..padding])?;
+        self.writer.write_all(&total_len.to_le_bytes())?;
+        Ok(())
+    }
+
+    /// Returns the id of the interface, describing it first if it is new
+    fn interface_id(&mut self, interface: &str) -> Result<u32, PcapError> {
+        if let Some(id) = self.interfaces.iter().position(|name| name == interface) {
+            return Ok(id as u32);
+        }
+
+        let mut body = Vec::new();
+        body.extend_from_slice(&LINKTYPE_CAN_SOCKETCAN.to_le_bytes());
+        body.extend_from_slice(&0u16.to_le_bytes());
+        // A snap length of 0 means that packets are not truncated
+        body.extend_from_slice(&0u32.to_le_bytes());
+        push_option(&mut body, OPTION_IF_NAME, interface.as_bytes());
+        push_option(&mut body, OPTION_IF_TSRESOL, &[9]);
+        push_option(&mut body, OPTION_END, &[]);
+        self.write_block(BLOCK_INTERFACE_DESCRIPTION, &body)?;
+
+        self.interfaces.push(interface.to_string());
+        Ok(self.interfaces.len() as u32 - 1)
+    }
+
+    /// Writes a single captured frame
+    pub fn write_entry(&mut self, entry: &PcapEntry) -> Result<(), PcapError> {
+        self.write_frame(entry.timestamp, &entry.interface, &entry.frame)
+    }
+
+    /// Writes a frame received on the interface at the given time
+    pub fn write_frame(
+        &mut self,
+        timestamp: SystemTime,
+        interface: &str,
+        frame: &CanAnyFrame,
+    ) -> Result<(), PcapError> {
+        let interface_id = self.interface_id(interface)?;
+        let timestamp = timestamp
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos() as u64;
+        let packet = encode_frame(frame)?;
+
+        let mut body = Vec::with_capacity(20 + packet.len());
+        body.extend_from_slice(&interface_id.to_le_bytes());
+        body.extend_from_slice(&((timestamp >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(timestamp as u32).to_le_bytes());
+        body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(packet.len() as u32).to_le_bytes());
+        body.extend_from_slice(&packet);
+        self.write_block(BLOCK_ENHANCED_PACKET, &body)
+    }
+
+    /// Flushes the underlying writer
+    pub fn flush(&mut self) -> Result<(), PcapError> {
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /// Returns the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+fn push_option(body: &mut Vec<u8>, code: u16, value: &[u8]) {
+    body.extend_from_slice(&code.to_le_bytes());
+    body.extend_from_slice(&(value.len() as u16).to_le_bytes());
+    body.extend_from_slice(value);
+    body.resize(body.len() + (4 - value.len() % 4) % 4, 0);
+}
+
+/// Interface of a pcapng section
+struct Interface {
+    link_type: u16,
+    /// Resolution of the timestamps as encoded in `if_tsresol`
+    tsresol: u8,
+    name: String,
+}
+
+enum Format {
+    Pcap { link_type: u16, nanos: bool },
+    PcapNg { interfaces: Vec<Interface> },
+}
+
+/// Reads frames from pcap and pcapng captures
+///
+/// Only packets with the `LINKTYPE_CAN_SOCKETCAN` link type are returned,
+/// packets of other interfaces in the capture are skipped.
+pub struct PcapReader<R: Read> {
+    reader: R,
+    format: Format,
+    big_endian: bool,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Creates a reader and reads the header of the capture
+    ///
+    /// The format of the capture is detected from the header.
+    pub fn new(mut reader: R) -> Result<Self, PcapError> {
+        let mut magic = [0; 4];
+        reader.read_exact(&mut magic)?;
+
+        let (format, big_endian) = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+            (BLOCK_SECTION_HEADER, _) => (
+                Format::PcapNg {
+                    interfaces: Vec::new(),
+                },
+                false,
+            ),
+            (PCAP_MAGIC_MICROS, _) | (PCAP_MAGIC_NANOS, _) => (Self::pcap_format(magic), false),
+            (_, PCAP_MAGIC_MICROS) | (_, PCAP_MAGIC_NANOS) => (Self::pcap_format(magic), true),
+            _ => return Err(PcapError::InvalidFormat("Unknown file format".to_string())),
+        };
+
+        let mut pcap_reader = Self {
+            reader,
+            format,
+            big_endian,
+        };
+
+        match pcap_reader.format {
+            Format::PcapNg { .. } => pcap_reader.read_section_header()?,
+            Format::Pcap { .. } => {
+                let mut header = [0; 20];
+                pcap_reader.reader.read_exact(&mut header)?;
+                let link_type = pcap_reader.u32(&header, 16)? as u16;
+                if let Format::Pcap {
+                    link_type: format_link_type,
+                    ..
+                } = &mut pcap_reader.format
+                {
+                    *format_link_type = link_type;
+                }
+            }
+        }
+
+        Ok(pcap_reader)
+    }
+
+    fn pcap_format(magic: [u8; 4]) -> Format {
+        let nanos = u32::from_le_bytes(magic) == PCAP_MAGIC_NANOS
+            || u32::from_be_bytes(magic) == PCAP_MAGIC_NANOS;
+        Format::Pcap {
+            link_type: 0,
+            nanos,
+        }
+    }
+
+    fn u16(&self, data: &[u8], offset: usize) -> Result<u16, PcapError> {
+        let bytes = data
+            .get(offset..offset + 2)
+            .ok_or_else(|| PcapError::InvalidFormat("Truncated block".to_string()))?;
+        // UNWRAP: The slice has a length of 2
+        let bytes = bytes.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u16::from_be_bytes(bytes),
+            false => u16::from_le_bytes(bytes),
+        })
+    }
+
+    fn u32(&self, data: &[u8], offset: usize) -> Result<u32, PcapError> {
+        let bytes = data
+            .get(offset..offset + 4)
+            .ok_or_else(|| PcapError::InvalidFormat("Truncated block".to_string()))?;
+        // UNWRAP: The slice has a length of 4
+        let bytes = bytes.try_into().unwrap();
+        Ok(match self.big_endian {
+            true => u32::from_be_bytes(bytes),
+            false => u32::from_le_bytes(bytes),
+        })
+    }
+
+    /// Reads exactly `buf.len()` bytes, returns `false` at the end of the file
+    fn read_or_eof(&mut self, buf: &mut [u8]) -> Result<bool, PcapError> {
+        let mut read = 0;
+        while read < buf.len() {
+            match self.reader.read(&mut buf[read..]) {
+                Ok(0) if read == 0 => return Ok(false),
+                Ok(0) => return Err(std::io::Error::from(std::io::ErrorKind::UnexpectedEof).into()),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Ok(true)
+    }
+
+    /// Reads the remainder of a section header after its block type
+    ///
+    /// The byte order magic determines the byte order of the whole section.
+    fn read_section_header(&mut self) -> Result<(), PcapError> {
+        let mut header = [0; 8];
+        self.reader.read_exact(&mut header)?;
+        // UNWRAP: The slice has a length of 4
+        let magic: [u8; 4] = header[4..].try_into().unwrap();
+        self.big_endian = match (u32::from_le_bytes(magic), u32::from_be_bytes(magic)) {
+            (BYTE_ORDER_MAGIC, _) => false,
+            (_, BYTE_ORDER_MAGIC) => true,
+            _ => {
+                return Err(PcapError::InvalidFormat(
+                    "Invalid byte order magic".to_string(),
+                ))
+            }
+        };
+
+        // The block type and length and the magic were already read
+        let total_len = self.u32(&header, 0)? as usize;
+        self.read_block_body(total_len, 12)?;
+
+        self.format = Format::PcapNg {
+            interfaces: Vec::new(),
+        };
+        Ok(())
+    }
+
+    /// Reads the body and trailing length of a block with the given length
+    fn read_block_body(&mut self, total_len: usize, consumed: usize) -> Result<Vec<u8>, PcapError> {
+        if total_len < consumed + 4 || !total_len.is_multiple_of(4) || total_len > MAX_BLOCK_LEN {
+            return Err(PcapError::InvalidFormat(format!(
+                "Invalid block length {}",
+                total_len
+            )));
+        }
+
+        let mut body = vec![0; total_len - consumed];
+        self.reader.read_exact(&mut body)?;
+        body.truncate(body.len() - 4);
+        Ok(body)
+    }
+
+    /// Reads the next frame
+    ///
+    /// Returns `None` at the end of the capture.
+    pub fn read_entry(&mut self) -> Result<Option<PcapEntry>, PcapError> {
+        loop {
+            let entry = match self.format {
+                Format::Pcap { .. } => self.read_pcap_record()?,
+                Format::PcapNg { .. } => self.read_pcapng_block()?,
+            };
+
+            match entry {
+                Some(Some(entry)) => return Ok(Some(entry)),
+                Some(None) => continue,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    fn read_pcap_record(&mut self) -> Result<Option<Option<PcapEntry>>, PcapError> {
+        let mut header = [0; 16];
+        if !self.read_or_eof(&mut header)? {
+            return Ok(None);
+        }
+
+        let secs = self.u32(&header, 0)? as u64;
+        let fraction = self.u32(&header, 4)?;
+        let captured_len = self.u32(&header, 8)? as usize;
+        if captured_len > MAX_BLOCK_LEN {
+            return Err(PcapError::InvalidFormat(format!(
+                "Invalid packet length {}",
+                captured_len
+            )));
+        }
+
+        let mut packet = vec![0; captured_len];
+        self.reader.read_exact(&mut packet)?;
+
+        let Format::Pcap { link_type, nanos } = self.format else {
+            unreachable!()
+        };
+        if link_type != LINKTYPE_CAN_SOCKETCAN {
+            return Ok(Some(None));
+        }
+
+        let nanos = match nanos {
+            true => fraction,
+            false => fraction.saturating_mul(1000),
+        };
+        Ok(Some(Some(PcapEntry {
+            timestamp: SystemTime::UNIX_EPOCH + Duration::new(secs, nanos),
+            interface: String::new(),
+            frame: decode_frame(&packet)?,
+        })))
+    }
+
+    fn read_pcapng_block(&mut self) -> Result<Option<Option<PcapEntry>>, PcapError> {
+        let mut header = [0; 4];
+        if !self.read_or_eof(&mut header)? {
+            return Ok(None);
+        }
+
+        // The section header has the same block type in both byte orders
+        if u32::from_le_bytes(header) == BLOCK_SECTION_HEADER {
+            self.read_section_header()?;
+            return Ok(Some(None));
+        }
+
+        let block_type = self.u32(&header, 0)?;
+        self.reader.read_exact(&mut header)?;
+        let total_len = self.u32(&header, 0)? as usize;
+        let body = self.read_block_body(total_len, 8)?;
+
+        match block_type {
+            BLOCK_INTERFACE_DESCRIPTION => {
+                let interface = self.parse_interface(&body)?;
+                if let Format::PcapNg { interfaces } = &mut self.format {
+                    interfaces.push(interface);
+                }
+                Ok(Some(None))
+            }
+            BLOCK_ENHANCED_PACKET => self.parse_enhanced_packet(&body).map(Some),
+            _ => Ok(Some(None)),
+        }
+    }
+
+    fn parse_interface(&self, body: &[u8]) -> Result<Interface, PcapError> {
+        let mut interface = Interface {
+            link_type: self.u16(body, 0)?,
+            tsresol: 6,
+            name: String::new(),
+        };
+
+        let mut offset = 8;
+        while offset + 4 <= body.len() {
+            let code = self.u16(body, offset)?;
+            let len = self.u16(body, offset + 2)? as usize;
+            let value = body
+                .get(offset + 4..offset + 4 + len)
+                .ok_or_else(|| PcapError::InvalidFormat("Truncated option".to_string()))?;
+
+            match code {
+                OPTION_END => break,
+                OPTION_IF_NAME => {
+                    interface.name = String::from_utf8_lossy(value)
+                        .trim_end_matches('\0')
+                        .to_string()
+                }
+                OPTION_IF_TSRESOL if len == 1 => interface.tsresol = value[0],
+                _ => {}
+            }
+
+            offset += 4 + len + (4 - len % 4) % 4;
+        }
+
+        Ok(interface)
+    }
+
+    fn parse_enhanced_packet(&self, body: &[u8]) -> Result<Option<PcapEntry>, PcapError> {
+        let Format::PcapNg { interfaces } = &self.format else {
+            unreachable!()
+        };
+
+        let interface_id = self.u32(body, 0)? as usize;
+        let interface = interfaces.get(interface_id).ok_or_else(|| {
+            PcapError::InvalidFormat(format!("Unknown interface {}", interface_id))
+        })?;
+        if interface.link_type != LINKTYPE_CAN_SOCKETCAN {
+            return Ok(None);
+        }
+
+        let timestamp = (self.u32(body, 4)? as u64) << 32 | self.u32(body, 8)? as u64;
+        let captured_len = self.u32(body, 12)? as usize;
+        let packet = body
+            .get(20..20 + captured_len)
+            .ok_or_else(|| PcapError::InvalidFormat("Truncated packet".to_string()))?;
+
+        Ok(Some(PcapEntry {
+            timestamp: tsresol_to_time(timestamp, interface.tsresol)?,
+            interface: interface.name.clone(),
+            frame: decode_frame(packet)?,
+        }))
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<PcapEntry, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_entry().transpose()
+    }
+}
+
+/// Converts a timestamp of an enhanced packet block into the time of capture
+fn tsresol_to_time(timestamp: u64, tsresol: u8) -> Result<SystemTime, PcapError> {
+    SystemTime::UNIX_EPOCH
+        .checked_add(tsresol_to_duration(timestamp, tsresol))
+        .ok_or_else(|| PcapError::InvalidFormat(format!("Timestamp {} is out of range", timestamp)))
+}
+
+/// Converts a timestamp in units of the `if_tsresol` resolution
+///
+/// If the most significant bit is set, the resolution is a negative power of
+/// 2, otherwise of 10.
+fn tsresol_to_duration(timestamp: u64, tsresol: u8) -> Duration {
+    let exponent = (tsresol & 0x7F) as u32;
+    let units_per_sec: u128 = match tsresol & 0x80 != 0 {
+        true => 1u128.checked_shl(exponent).unwrap_or(u128::MAX),
+        false => 10u128.checked_pow(exponent).unwrap_or(u128::MAX),
+    };
+
+    let secs = timestamp as u128 / units_per_sec;
+    let nanos = (timestamp as u128 % units_per_sec) * 1_000_000_000 / units_per_sec;
+    Duration::new(secs as u64, nanos as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, SystemTime};
+
+    use embedded_hal::can::Frame;
+
+    use super::{tsresol_to_duration, tsresol_to_time, PcapEntry, PcapReader, PcapWriter};
+    use crate::{can::CanAnyFrame, log::LogEntry};
+
+    fn entries() -> Vec<PcapEntry> {
+        [
+            "(1697000000.123456) can0 123#DEADBEEF",
+            "(1697000000.200000) can1 18DAF110#R",
+            "(1697000001.000000) can0 321##3112233445566778899",
+            "(1697000002.000000) can1 20000040#0000000000000000",
+        ]
+        .iter()
+        .map(|line| {
+            let entry = LogEntry::parse(line).unwrap();
+            PcapEntry {
+                timestamp: entry.timestamp,
+                interface: entry.interface,
+                frame: entry.frame,
+            }
+        })
+        .collect()
+    }
+
+    #[test]
+    fn writes_and_reads_pcapng() {
+        let entries = entries();
+        let mut writer = PcapWriter::new(Vec::new()).unwrap();
+        for entry in &entries {
+            writer.write_entry(entry).unwrap();
+        }
+        let capture = writer.into_inner();
+
+        let read: Vec<_> = PcapReader::new(capture.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read.len(), entries.len());
+        for (read, entry) in read.iter().zip(&entries) {
+            assert_eq!(read.timestamp, entry.timestamp);
+            assert_eq!(read.interface, entry.interface);
+            assert_eq!(read.frame.id(), entry.frame.id());
+            assert_eq!(read.frame.data(), entry.frame.data());
+        }
+
+        match read[1].frame {
+            CanAnyFrame::Classic(frame) => assert!(frame.is_remote_frame()),
+            _ => panic!("Expected classic frame"),
+        }
+        match read[2].frame {
+            CanAnyFrame::Fd(frame) => assert!(frame.is_brs() && frame.is_esi()),
+            _ => panic!("Expected CAN FD frame"),
+        }
+        assert!(matches!(read[3].frame, CanAnyFrame::Error(_)));
+    }
+
+    #[test]
+    fn encodes_socketcan_packets() {
+        let capture = {
+            let mut writer = PcapWriter::new(Vec::new()).unwrap();
+            writer.write_entry(&entries()[0]).unwrap();
+            writer.into_inner()
+        };
+
+        // The packet is the last part of the enhanced packet block
+        let packet = &capture[capture.len() - 4 - 16..capture.len() - 4];
+        assert_eq!(
+            packet,
+            [0x00, 0x00, 0x01, 0x23, 4, 0, 0, 0, 0xDE, 0xAD, 0xBE, 0xEF, 0, 0, 0, 0]
+        );
+    }
+
+    #[test]
+    fn reads_big_endian_pcap() {
+        let mut capture = Vec::new();
+        capture.extend_from_slice(&0xA1B2_C3D4u32.to_be_bytes());
+        capture.extend_from_slice(&[0, 2, 0, 4]);
+        capture.extend_from_slice(&[0; 8]);
+        capture.extend_from_slice(&65535u32.to_be_bytes());
+        capture.extend_from_slice(&227u32.to_be_bytes());
+
+        capture.extend_from_slice(&10u32.to_be_bytes());
+        capture.extend_from_slice(&500u32.to_be_bytes());
+        capture.extend_from_slice(&16u32.to_be_bytes());
+        capture.extend_from_slice(&16u32.to_be_bytes());
+        capture.extend_from_slice(&[0x80, 0x00, 0x01, 0x00, 2, 0, 0, 0, 0x11, 0x22]);
+        capture.extend_from_slice(&[0; 6]);
+
+        let read: Vec<_> = PcapReader::new(capture.as_slice())
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(
+            read[0].timestamp,
+            SystemTime::UNIX_EPOCH + Duration::new(10, 500_000)
+        );
+        assert!(read[0].frame.is_extended());
+        assert_eq!(read[0].frame.data(), [0x11, 0x22]);
+    }
+
+    #[test]
+    fn rejects_unknown_formats() {
+        assert!(PcapReader::new(&b"not a capture"[..]).is_err());
+    }
+
+    #[test]
+    fn converts_timestamp_resolutions() {
+        assert_eq!(
+            tsresol_to_duration(1_500_000, 6),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(tsresol_to_duration(3, 0x81), Duration::from_millis(1500));
+
+        assert!(tsresol_to_time(u64::MAX, 6).is_ok());
+        assert!(tsresol_to_time(u64::MAX, 0).is_err());
+    }
+}
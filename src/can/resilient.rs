@@ -0,0 +1,394 @@
+use std::time::Duration;
+
+use crate::socket::CanInterface;
+
+use super::{
+    any_frame::classic_frame, CanAnyFrame, CanBus, CanFdFrame, CanFilter, CanFrame,
+    CanTimestamping, TxQueuePolicy,
+};
+
+/// Default delay between the attempts to reopen a lost interface
+const DEFAULT_RETRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Event of a [ResilientCanBus]
+#[derive(Debug)]
+pub enum ResilientCanEvent {
+    /// A frame was received
+    Frame(CanAnyFrame),
+    /// The interface was lost because of the error (e.g., `ENODEV` when the
+    /// adapter was unplugged or `ENETDOWN` when the interface went down)
+    Disconnected(std::io::Error),
+    /// The interface is available again and the bus was reopened
+    Reconnected(CanInterface),
+}
+
+/// Settings of the bus which are applied again after reopening it
+#[derive(Debug, Clone, Default)]
+struct CanBusConfig {
+    filters: Option<Vec<CanFilter>>,
+    join_filters: bool,
+    error_mask: libc::can_err_mask_t,
+    fd_frames: bool,
+    xl_frames: bool,
+    timestamping: Option<CanTimestamping>,
+    drop_counter: bool,
+    loopback: Option<bool>,
+    recv_own_msgs: bool,
+    send_buffer_size: Option<usize>,
+    tx_queue_policy: TxQueuePolicy,
+}
+
+impl CanBusConfig {
+    fn apply(&self, can_bus: &mut CanBus) -> Result<(), std::io::Error> {
+        if let Some(filters) = &self.filters {
+            can_bus.set_filters(filters)?;
+        }
+        if self.join_filters {
+            can_bus.set_join_filters(true)?;
+        }
+        if self.error_mask != 0 {
+            can_bus.set_error_mask(self.error_mask)?;
+        }
+        if self.fd_frames {
+            can_bus.set_fd_frames(true)?;
+        }
+        if self.xl_frames {
+            can_bus.set_xl_frames(true)?;
+        }
+        if let Some(timestamping) = self.timestamping {
+            can_bus.set_timestamping(timestamping)?;
+        }
+        if self.drop_counter {
+            can_bus.set_drop_counter(true)?;
+        }
+        if let Some(loopback) = self.loopback {
+            can_bus.set_loopback(loopback)?;
+        }
+        if self.recv_own_msgs {
+            can_bus.set_recv_own_msgs(true)?;
+        }
+        if let Some(size) = self.send_buffer_size {
+            can_bus.set_send_buffer_size(size)?;
+        }
+        can_bus.set_tx_queue_policy(self.tx_queue_policy);
+
+        Ok(())
+    }
+}
+
+/// Returns `true` if the error is caused by a missing or down interface
+fn is_link_lost(error: &std::io::Error) -> bool {
+    matches!(
+        error.raw_os_error(),
+        Some(libc::ENODEV | libc::ENETDOWN | libc::ENXIO)
+    )
+}
+
+/// [CanBus] which reopens itself when the interface is lost.
+///
+/// The interface is addressed by its name, because it gets a new index when
+/// it is created again (e.g., when an USB adapter is re-enumerated). When the
+/// interface is removed or goes down, [ResilientCanBus::read_event()] reports
+/// the disconnect instead of returning an error. The next call waits until the
+/// interface is up again, reopens the bus and reports the reconnect.
+/// Filters and socket options set on the [ResilientCanBus] are applied again
+/// to the new socket.
+///
+/// [ResilientCanBus::read()] and [ResilientCanBus::read_any()] skip these
+/// events and only return frames. Writes fail while the interface is lost.
+///
+/// # Example:
+/// ```no_run
+/// # use ddose::{ResilientCanBus, ResilientCanEvent};
+/// # #[tokio::main] async fn main() -> Result<(), std::io::Error> {
+/// let mut can_bus = ResilientCanBus::open("can0")?;
+///
+/// loop {
+///     match can_bus.read_event().await? {
+///         ResilientCanEvent::Frame(frame) => println!("{:?}", frame.data()),
+///         ResilientCanEvent::Disconnected(e) => println!("Lost can0: {}", e),
+///         ResilientCanEvent::Reconnected(_) => println!("can0 is back"),
+///     }
+/// }
+/// # }
+/// ```
+pub struct ResilientCanBus {
+    if_name: String,
+    can_bus: Option<(CanInterface, CanBus)>,
+    config: CanBusConfig,
+    retry_interval: Duration,
+}
+
+impl ResilientCanBus {
+    /// Opens a CAN bus on the interface with the name
+    ///
+    /// If the interface doesn't exist or is down, the bus is opened once it
+    /// is available.
+    pub fn open(if_name: &str) -> Result<Self, std::io::Error> {
+        Self::open_with(if_name, CanBusConfig::default())
+    }
+
+    /// Opens a CAN bus with acceptance filters
+    ///
+    /// See [CanBus::open_filtered()].
+    pub fn open_filtered(if_name: &str, filters: &[CanFilter]) -> Result<Self, std::io::Error> {
+        let config = CanBusConfig {
+            filters: Some(filters.to_vec()),
+            ..Default::default()
+        };
+        Self::open_with(if_name, config)
+    }
+
+    /// Opens a CAN bus with CAN FD frames enabled
+    ///
+    /// See [CanBus::open_fd()].
+    pub fn open_fd(if_name: &str) -> Result<Self, std::io::Error> {
+        let config = CanBusConfig {
+            fd_frames: true,
+            ..Default::default()
+        };
+        Self::open_with(if_name, config)
+    }
+
+    fn open_with(if_name: &str, config: CanBusConfig) -> Result<Self, std::io::Error> {
+        let mut resilient = Self {
+            if_name: if_name.to_string(),
+            can_bus: None,
+            config,
+            retry_interval: DEFAULT_RETRY_INTERVAL,
+        };
+
+        match resilient.reopen() {
+            Err(e) if !is_link_lost(&e) => Err(e),
+            _ => Ok(resilient),
+        }
+    }
+
+    /// Opens the bus again if the interface exists and is up
+    fn reopen(&mut self) -> Result<CanInterface, std::io::Error> {
+        let can_if = CanInterface::try_from(self.if_name.as_str())?;
+        if !can_if.is_up()? {
+            return Err(std::io::Error::from_raw_os_error(libc::ENETDOWN));
+        }
+
+        let can_bus = CanBus::open_with(&can_if, |can_bus| self.config.apply(can_bus))?;
+        self.can_bus = Some((can_if, can_bus));
+
+        Ok(can_if)
+    }
+
+    /// Waits until the bus could be opened again
+    async fn reconnect(&mut self) -> Result<CanInterface, std::io::Error> {
+        loop {
+            match self.reopen() {
+                Err(e) if is_link_lost(&e) => crate::runtime::sleep(self.retry_interval).await,
+                result => return result,
+            }
+        }
+    }
+
+    /// Returns the open bus or tries to open it once
+    fn connected(&mut self) -> Result<&mut CanBus, std::io::Error> {
+        if self.can_bus.is_none() {
+            self.reopen()?;
+        }
+
+        self.can_bus
+            .as_mut()
+            .map(|(_, can_bus)| can_bus)
+            .ok_or_else(|| std::io::Error::from_raw_os_error(libc::ENODEV))
+    }
+
+    /// Changes the open bus
+    ///
+    /// Callers store the change in the config only if it succeeded, so a
+    /// rejected value doesn't break reopening the bus. While the bus is
+    /// closed, the change is only stored.
+    fn configure(
+        &mut self,
+        f: impl FnOnce(&mut CanBus) -> Result<(), std::io::Error>,
+    ) -> Result<(), std::io::Error> {
+        match &mut self.can_bus {
+            Some((_, can_bus)) => f(can_bus),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns the name of the interface
+    pub fn if_name(&self) -> &str {
+        &self.if_name
+    }
+
+    /// Returns the interface while the bus is open
+    pub fn interface(&self) -> Option<CanInterface> {
+        self.can_bus.as_ref().map(|(can_if, _)| *can_if)
+    }
+
+    /// Returns `true` if the bus is open
+    pub fn is_connected(&self) -> bool {
+        self.can_bus.is_some()
+    }
+
+    /// Sets the delay between the attempts to reopen a lost interface
+    pub fn set_retry_interval(&mut self, interval: Duration) {
+        self.retry_interval = interval;
+    }
+
+    /// See [CanBus::set_filters()]
+    pub fn set_filters(&mut self, filters: &[CanFilter]) -> Result<(), std::io::Error> {
+        self.configure(|can_bus| can_bus.set_filters(filters))?;
+        self.config.filters = Some(filters.to_vec());
+        Ok(())
+    }
+
+    /// See [CanBus::accept_all()]
+    pub fn accept_all(&mut self) -> Result<(), std::io::Error> {
+        self.set_filters(&[CanFilter::accept_all()])
+    }
+
+    /// See [CanBus::set_join_filters()]
+    pub fn set_join_filters(&mut self, join: bool) -> Result<(), std::io::Error> {
+        self.configure(|can_bus| can_bus.set_join_filters(join))?;
+        self.config.join_filters = join;
+        Ok(())
+    }
+
+    /// See [CanBus::set_error_mask()]
+    pub fn set_error_mask(&mut self, mask: libc::can_err_mask_t) -> Result<(), std::io::Error> {
+        self.configure(|can_bus| can_bus.set_error_mask(mask))?;
+        self.config.error_mask = mask;
+        Ok(())
+    }
+
+    /// See [CanBus::set_fd_frames()]
+    pub fn set_fd_frames(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        self.configure(|can_bus| can_bus.set_fd_frames(enable))?;
+        self.config.fd_frames = enable;
+        Ok(())
+    }
+
+    /// Returns `true` if CAN FD frames are enabled on the bus
+    pub fn fd_frames(&self) -> bool {
+        self.config.fd_frames
+    }
+
+    /// See [CanBus::set_xl_frames()]
+    pub fn set_xl_frames(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        self.configure(|can_bus| can_bus.set_xl_frames(enable))?;
+        self.config.xl_frames = enable;
+        Ok(())
+    }
+
+    /// Returns `true` if CAN XL frames are enabled on the bus
+    pub fn xl_frames(&self) -> bool {
+        self.config.xl_frames
+    }
+
+    /// See [CanBus::set_timestamping()]
+    pub fn set_timestamping(
+        &mut self,
+        timestamping: CanTimestamping,
+    ) -> Result<(), std::io::Error> {
+        self.configure(|can_bus| can_bus.set_timestamping(timestamping))?;
+        self.config.timestamping = Some(timestamping);
+        Ok(())
+    }
+
+    /// See [CanBus::set_drop_counter()]
+    pub fn set_drop_counter(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        self.configure(|can_bus| can_bus.set_drop_counter(enable))?;
+        self.config.drop_counter = enable;
+        Ok(())
+    }
+
+    /// See [CanBus::set_loopback()]
+    pub fn set_loopback(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        self.configure(|can_bus| can_bus.set_loopback(enable))?;
+        self.config.loopback = Some(enable);
+        Ok(())
+    }
+
+    /// See [CanBus::set_recv_own_msgs()]
+    pub fn set_recv_own_msgs(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        self.configure(|can_bus| can_bus.set_recv_own_msgs(enable))?;
+        self.config.recv_own_msgs = enable;
+        Ok(())
+    }
+
+    /// See [CanBus::set_send_buffer_size()]
+    pub fn set_send_buffer_size(&mut self, size: usize) -> Result<(), std::io::Error> {
+        self.configure(|can_bus| can_bus.set_send_buffer_size(size))?;
+        self.config.send_buffer_size = Some(size);
+        Ok(())
+    }
+
+    /// See [CanBus::set_tx_queue_policy()]
+    pub fn set_tx_queue_policy(&mut self, policy: TxQueuePolicy) {
+        self.config.tx_queue_policy = policy;
+        if let Some((_, can_bus)) = &mut self.can_bus {
+            can_bus.set_tx_queue_policy(policy);
+        }
+    }
+
+    /// Reads the next frame or change of the connection
+    ///
+    /// Errors which aren't caused by a lost interface are returned as error.
+    pub async fn read_event(&mut self) -> Result<ResilientCanEvent, std::io::Error> {
+        let Some((_, can_bus)) = &mut self.can_bus else {
+            let can_if = self.reconnect().await?;
+            return Ok(ResilientCanEvent::Reconnected(can_if));
+        };
+
+        match can_bus.read_any().await {
+            Ok(frame) => Ok(ResilientCanEvent::Frame(frame)),
+            Err(e) if is_link_lost(&e) => {
+                self.can_bus = None;
+                Ok(ResilientCanEvent::Disconnected(e))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Reads a classic CAN frame, waiting for the interface if it is lost
+    ///
+    /// See [CanBus::read()].
+    pub async fn read(&mut self) -> Result<CanFrame, std::io::Error> {
+        classic_frame(self.read_any().await?)
+    }
+
+    /// Reads any kind of frame, waiting for the interface if it is lost
+    pub async fn read_any(&mut self) -> Result<CanAnyFrame, std::io::Error> {
+        loop {
+            if let ResilientCanEvent::Frame(frame) = self.read_event().await? {
+                return Ok(frame);
+            }
+        }
+    }
+
+    /// Writes a classic CAN frame to the bus
+    ///
+    /// If the interface is lost, the error is returned and the bus is
+    /// reopened by the next read or write.
+    pub async fn write(&mut self, can_frame: &CanFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::Classic(*can_frame)).await
+    }
+
+    /// Writes a CAN FD frame to the bus
+    ///
+    /// See [ResilientCanBus::write()].
+    pub async fn write_fd(&mut self, canfd_frame: &CanFdFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::Fd(*canfd_frame)).await
+    }
+
+    /// Writes a classic, CAN FD or CAN XL frame to the bus
+    ///
+    /// See [ResilientCanBus::write()].
+    pub async fn write_any(&mut self, frame: &CanAnyFrame) -> Result<(), std::io::Error> {
+        let result = self.connected()?.write_any(frame).await;
+        if result.as_ref().is_err_and(is_link_lost) {
+            self.can_bus = None;
+        }
+
+        result
+    }
+}
@@ -0,0 +1,248 @@
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+
+use crate::socket::{CanRxMeta, CanSocket};
+
+use super::{
+    any_frame::classic_frame,
+    bus::{poll_read_frame, poll_write_frame, poll_write_pending},
+    tx_queue::TxQueue,
+    CanAnyFrame, CanBus, CanFdFrame, CanFrame, CanXlFrame, TxQueuePolicy, TxQueueStats,
+};
+
+impl CanBus {
+    /// Splits the bus into a reader and a writer borrowing the bus
+    ///
+    /// The halves can be used concurrently, e.g. in a `select!` loop. Both
+    /// halves use the same socket, so frames written by the writer are
+    /// received by the reader in the order of transmission if the socket
+    /// receives its own frames.
+    pub fn split(&mut self) -> (CanReadHalf<'_>, CanWriteHalf<'_>) {
+        let reader = CanReadHalf {
+            socket: &self.socket,
+            backlog: &mut self.backlog,
+        };
+
+        let writer = CanWriteHalf {
+            socket: &self.socket,
+            tx_queue: &mut self.tx_queue,
+        };
+
+        (reader, writer)
+    }
+
+    /// Splits the bus into a reader and a writer owning the bus
+    ///
+    /// The halves can be moved to different tasks. The socket is closed
+    /// when both halves are dropped. See [CanBus::split()].
+    pub fn into_split(self) -> (CanReader, CanWriter) {
+        let socket = Arc::new(self.socket);
+        let reader = CanReader {
+            socket: socket.clone(),
+            backlog: self.backlog,
+        };
+        let writer = CanWriter {
+            socket,
+            pending: self.pending,
+            tx_queue: self.tx_queue,
+        };
+
+        (reader, writer)
+    }
+}
+
+/// Reading half of a [CanBus] created by [CanBus::split()]
+pub struct CanReadHalf<'a> {
+    socket: &'a CanSocket,
+    backlog: &'a mut VecDeque<(CanAnyFrame, CanRxMeta)>,
+}
+
+impl CanReadHalf<'_> {
+    /// See [CanBus::read()]
+    pub async fn read(&mut self) -> Result<CanFrame, std::io::Error> {
+        classic_frame(self.read_any().await?)
+    }
+
+    /// See [CanBus::read_any()]
+    pub async fn read_any(&mut self) -> Result<CanAnyFrame, std::io::Error> {
+        if let Some((frame, _)) = self.backlog.pop_front() {
+            return Ok(frame);
+        }
+
+        std::future::poll_fn(|cx| poll_read_frame(self.socket, cx)).await
+    }
+}
+
+/// Writing half of a [CanBus] created by [CanBus::split()]
+///
+/// Writes use the [TxQueuePolicy] of the bus and count to its
+/// [TxQueueStats].
+pub struct CanWriteHalf<'a> {
+    socket: &'a CanSocket,
+    tx_queue: &'a mut TxQueue,
+}
+
+impl CanWriteHalf<'_> {
+    /// See [CanBus::write()]
+    pub async fn write(&mut self, can_frame: &CanFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::Classic(*can_frame)).await
+    }
+
+    /// See [CanBus::write_fd()]
+    pub async fn write_fd(&mut self, canfd_frame: &CanFdFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::Fd(*canfd_frame)).await
+    }
+
+    /// See [CanBus::write_xl()]
+    pub async fn write_xl(&mut self, canxl_frame: &CanXlFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::from(*canxl_frame)).await
+    }
+
+    /// See [CanBus::write_any()]
+    pub async fn write_any(&mut self, frame: &CanAnyFrame) -> Result<(), std::io::Error> {
+        self.tx_queue.reset();
+        std::future::poll_fn(|cx| poll_write_frame(self.socket, self.tx_queue, cx, frame)).await
+    }
+}
+
+/// Reading half of a [CanBus] created by [CanBus::into_split()]
+///
+/// Like the [CanBus], the reader is a [Stream](futures_core::Stream) of
+/// classic CAN frames.
+pub struct CanReader {
+    socket: Arc<CanSocket>,
+    backlog: VecDeque<(CanAnyFrame, CanRxMeta)>,
+}
+
+impl CanReader {
+    /// See [CanBus::poll_read()]
+    pub fn poll_read(&mut self, cx: &mut Context<'_>) -> Poll<Result<CanFrame, std::io::Error>> {
+        self.poll_read_any(cx)
+            .map(|frame| frame.and_then(classic_frame))
+    }
+
+    /// See [CanBus::poll_read_any()]
+    pub fn poll_read_any(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<CanAnyFrame, std::io::Error>> {
+        if let Some((frame, _)) = self.backlog.pop_front() {
+            return Poll::Ready(Ok(frame));
+        }
+
+        poll_read_frame(&self.socket, cx)
+    }
+
+    /// See [CanBus::read()]
+    pub async fn read(&mut self) -> Result<CanFrame, std::io::Error> {
+        std::future::poll_fn(|cx| self.poll_read(cx)).await
+    }
+
+    /// See [CanBus::read_any()]
+    pub async fn read_any(&mut self) -> Result<CanAnyFrame, std::io::Error> {
+        std::future::poll_fn(|cx| self.poll_read_any(cx)).await
+    }
+}
+
+impl futures_core::Stream for CanReader {
+    type Item = Result<CanFrame, std::io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_read(cx).map(Some)
+    }
+}
+
+/// Writing half of a [CanBus] created by [CanBus::into_split()]
+///
+/// Like the [CanBus], the writer is a [Sink](futures_sink::Sink) for classic
+/// and CAN FD frames.
+pub struct CanWriter {
+    socket: Arc<CanSocket>,
+    pending: Option<CanAnyFrame>,
+    tx_queue: TxQueue,
+}
+
+impl CanWriter {
+    /// See [CanBus::set_tx_queue_policy()]
+    pub fn set_tx_queue_policy(&mut self, policy: TxQueuePolicy) {
+        self.tx_queue.set_policy(policy);
+    }
+
+    /// See [CanBus::tx_queue_stats()]
+    pub fn tx_queue_stats(&self) -> TxQueueStats {
+        self.tx_queue.stats()
+    }
+
+    /// See [CanBus::poll_write_any()]
+    pub fn poll_write_any(
+        &mut self,
+        cx: &mut Context<'_>,
+        frame: &CanAnyFrame,
+    ) -> Poll<Result<(), std::io::Error>> {
+        poll_write_frame(&self.socket, &mut self.tx_queue, cx, frame)
+    }
+
+    /// See [CanBus::write()]
+    pub async fn write(&mut self, can_frame: &CanFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::Classic(*can_frame)).await
+    }
+
+    /// See [CanBus::write_fd()]
+    pub async fn write_fd(&mut self, canfd_frame: &CanFdFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::Fd(*canfd_frame)).await
+    }
+
+    /// See [CanBus::write_xl()]
+    pub async fn write_xl(&mut self, canxl_frame: &CanXlFrame) -> Result<(), std::io::Error> {
+        self.write_any(&CanAnyFrame::from(*canxl_frame)).await
+    }
+
+    /// See [CanBus::write_any()]
+    pub async fn write_any(&mut self, frame: &CanAnyFrame) -> Result<(), std::io::Error> {
+        self.tx_queue.reset();
+        std::future::poll_fn(|cx| self.poll_write_any(cx, frame)).await
+    }
+}
+
+impl<F: Into<CanAnyFrame>> futures_sink::Sink<F> for CanWriter {
+    type Error = std::io::Error;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let writer = self.get_mut();
+        poll_write_pending(
+            &writer.socket,
+            &mut writer.tx_queue,
+            cx,
+            &mut writer.pending,
+        )
+    }
+
+    fn start_send(self: Pin<&mut Self>, frame: F) -> Result<(), Self::Error> {
+        self.get_mut().pending = Some(frame.into());
+        Ok(())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let writer = self.get_mut();
+        poll_write_pending(
+            &writer.socket,
+            &mut writer.tx_queue,
+            cx,
+            &mut writer.pending,
+        )
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let writer = self.get_mut();
+        poll_write_pending(
+            &writer.socket,
+            &mut writer.tx_queue,
+            cx,
+            &mut writer.pending,
+        )
+    }
+}
@@ -0,0 +1,387 @@
+use std::time::Duration;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use crate::runtime::{Instant, Sleep};
+
+/// Behaviour of writes when the transmit queue of the interface is full
+///
+/// The kernel rejects frames with `ENOBUFS` when the queue of the interface
+/// is full, e.g. when frames are written faster than the bus can transmit
+/// them. In contrast to a full socket buffer, the socket doesn't become
+/// writable again when the queue drains, so the write is retried after a
+/// delay.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TxQueuePolicy {
+    /// Writes fail with `ENOBUFS` immediately
+    Fail,
+    /// Writes are retried with a delay which doubles after every attempt
+    Retry {
+        /// Delay before the first retry
+        initial_delay: Duration,
+        /// Upper limit of the delay
+        max_delay: Duration,
+        /// Time after which the write fails with `ENOBUFS`, `None` retries
+        /// until the frame is queued
+        timeout: Option<Duration>,
+    },
+}
+
+impl TxQueuePolicy {
+    /// Returns the delay before the next attempt or `None` if the write must
+    /// fail
+    ///
+    /// `previous` is the delay before the last attempt and `elapsed` the time
+    /// since the queue was found full.
+    fn next_delay(&self, previous: Option<Duration>, elapsed: Duration) -> Option<Duration> {
+        let TxQueuePolicy::Retry {
+            initial_delay,
+            max_delay,
+            timeout,
+        } = *self
+        else {
+            return None;
+        };
+
+        let mut delay = match previous {
+            None => initial_delay,
+            Some(delay) => (delay * 2).min(max_delay),
+        };
+        if let Some(timeout) = timeout {
+            let remaining = timeout.saturating_sub(elapsed);
+            if remaining.is_zero() {
+                return None;
+            }
+            delay = delay.min(remaining);
+        }
+
+        Some(delay)
+    }
+}
+
+impl Default for TxQueuePolicy {
+    /// Retries starting with 1 ms up to 100 ms for at most 1 s
+    fn default() -> Self {
+        Self::Retry {
+            initial_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(100),
+            timeout: Some(Duration::from_secs(1)),
+        }
+    }
+}
+
+/// Counters of the writes of a bus
+///
+/// A growing number of full queues with few failed writes indicates a
+/// saturated bus, while other errors indicate real failures.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TxQueueStats {
+    /// Writes which found the transmit queue full
+    pub queue_full: u64,
+    /// Attempts to write again after waiting for the queue
+    pub retries: u64,
+    /// Writes which failed because the queue stayed full
+    pub failed: u64,
+    /// Writes which failed for other reasons
+    pub errors: u64,
+    /// Total time writes waited for the queue
+    pub wait_time: Duration,
+}
+
+/// Retry of a write which found the transmit queue full
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+struct Backoff {
+    started: Instant,
+    delay: Duration,
+    sleep: Sleep,
+    waiting: bool,
+}
+
+/// Handles full transmit queues for the writes of a bus
+pub(super) struct TxQueue {
+    policy: TxQueuePolicy,
+    stats: TxQueueStats,
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    backoff: Option<Backoff>,
+}
+
+impl TxQueue {
+    pub(super) fn new() -> Self {
+        Self {
+            policy: TxQueuePolicy::default(),
+            stats: TxQueueStats::default(),
+            #[cfg(any(feature = "tokio", feature = "async-io"))]
+            backoff: None,
+        }
+    }
+
+    pub(super) fn policy(&self) -> TxQueuePolicy {
+        self.policy
+    }
+
+    pub(super) fn set_policy(&mut self, policy: TxQueuePolicy) {
+        self.policy = policy;
+    }
+
+    pub(super) fn stats(&self) -> TxQueueStats {
+        self.stats
+    }
+
+    /// Abandons the retries of a previous write which wasn't polled to the end
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub(super) fn reset(&mut self) {
+        self.finish();
+    }
+
+    /// Polls the write and retries it according to the policy while the
+    /// transmit queue is full
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub(super) fn poll_write<T>(
+        &mut self,
+        cx: &mut Context<'_>,
+        mut write: impl FnMut(&mut Context<'_>) -> Poll<Result<T, std::io::Error>>,
+    ) -> Poll<Result<T, std::io::Error>> {
+        loop {
+            if let Some(backoff) = &mut self.backoff {
+                if backoff.waiting {
+                    if Pin::new(&mut backoff.sleep).poll(cx).is_pending() {
+                        return Poll::Pending;
+                    }
+                    backoff.waiting = false;
+                    self.stats.retries += 1;
+                }
+            }
+
+            let result = match write(cx) {
+                Poll::Ready(result) => result,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            match result {
+                Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                    if !self.wait() {
+                        self.stats.failed += 1;
+                        self.finish();
+                        return Poll::Ready(Err(e));
+                    }
+                }
+                Err(e) => {
+                    self.stats.errors += 1;
+                    self.finish();
+                    return Poll::Ready(Err(e));
+                }
+                Ok(value) => {
+                    self.finish();
+                    return Poll::Ready(Ok(value));
+                }
+            }
+        }
+    }
+
+    /// Schedules the next attempt, returns `false` if the write must fail
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    fn wait(&mut self) -> bool {
+        let now = Instant::now();
+        if self.backoff.is_none() {
+            self.stats.queue_full += 1;
+        }
+
+        let (previous, elapsed) = match &self.backoff {
+            Some(backoff) => (Some(backoff.delay), now - backoff.started),
+            None => (None, Duration::ZERO),
+        };
+        let Some(delay) = self.policy.next_delay(previous, elapsed) else {
+            return false;
+        };
+
+        let backoff = self.backoff.get_or_insert_with(|| Backoff {
+            started: now,
+            delay,
+            sleep: Sleep::until(now),
+            waiting: false,
+        });
+        backoff.delay = delay;
+        backoff.sleep.reset(now + delay);
+        backoff.waiting = true;
+
+        true
+    }
+
+    /// Writes and retries according to the policy while the transmit queue
+    /// is full, blocking the thread between the attempts
+    #[cfg(feature = "blocking")]
+    pub(super) fn write_blocking<T>(
+        &mut self,
+        mut write: impl FnMut() -> Result<T, std::io::Error>,
+    ) -> Result<T, std::io::Error> {
+        let started = std::time::Instant::now();
+        let mut delay = None;
+
+        let result = loop {
+            match write() {
+                Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                    if delay.is_none() {
+                        self.stats.queue_full += 1;
+                    }
+                    match self.policy.next_delay(delay, started.elapsed()) {
+                        Some(next_delay) => {
+                            std::thread::sleep(next_delay);
+                            delay = Some(next_delay);
+                            self.stats.retries += 1;
+                        }
+                        None => {
+                            self.stats.failed += 1;
+                            break Err(e);
+                        }
+                    }
+                }
+                Err(e) => {
+                    self.stats.errors += 1;
+                    break Err(e);
+                }
+                Ok(value) => break Ok(value),
+            }
+        };
+
+        if delay.is_some() {
+            self.stats.wait_time += started.elapsed();
+        }
+
+        result
+    }
+
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    fn finish(&mut self) {
+        if let Some(backoff) = self.backoff.take() {
+            self.stats.wait_time += backoff.started.elapsed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn queue_full() -> std::io::Error {
+        std::io::Error::from_raw_os_error(libc::ENOBUFS)
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn retries_full_queue() {
+        let mut tx_queue = TxQueue::new();
+        let mut attempts = 0;
+
+        let result = std::future::poll_fn(|cx| {
+            tx_queue.poll_write(cx, |_| {
+                attempts += 1;
+                match attempts {
+                    1..=3 => std::task::Poll::Ready(Err(queue_full())),
+                    _ => std::task::Poll::Ready(Ok(attempts)),
+                }
+            })
+        })
+        .await;
+
+        assert_eq!(result.unwrap(), 4);
+        let stats = tx_queue.stats();
+        assert_eq!(stats.queue_full, 1);
+        assert_eq!(stats.retries, 3);
+        assert_eq!(stats.failed, 0);
+        // 1 ms + 2 ms + 4 ms
+        assert_eq!(stats.wait_time, Duration::from_millis(7));
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn fails_after_timeout() {
+        let mut tx_queue = TxQueue::new();
+        tx_queue.set_policy(TxQueuePolicy::Retry {
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(10),
+            timeout: Some(Duration::from_millis(25)),
+        });
+
+        let result: Result<(), _> =
+            std::future::poll_fn(|cx| tx_queue.poll_write(cx, |_| Err(queue_full()).into())).await;
+
+        assert_eq!(result.unwrap_err().raw_os_error(), Some(libc::ENOBUFS));
+        let stats = tx_queue.stats();
+        assert_eq!(stats.retries, 3);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.wait_time, Duration::from_millis(25));
+    }
+
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    #[tokio::test]
+    async fn fails_immediately() {
+        let mut tx_queue = TxQueue::new();
+        tx_queue.set_policy(TxQueuePolicy::Fail);
+
+        let result: Result<(), _> = std::future::poll_fn(|cx| {
+            tx_queue.poll_write(cx, |_| Err(std::io::Error::other("Failure")).into())
+        })
+        .await;
+        assert!(result.is_err());
+
+        let result: Result<(), _> =
+            std::future::poll_fn(|cx| tx_queue.poll_write(cx, |_| Err(queue_full()).into())).await;
+        assert!(result.is_err());
+
+        let stats = tx_queue.stats();
+        assert_eq!(stats.queue_full, 1);
+        assert_eq!(stats.retries, 0);
+        assert_eq!(stats.failed, 1);
+        assert_eq!(stats.errors, 1);
+    }
+
+    #[cfg(all(feature = "async-io", not(feature = "tokio")))]
+    #[test]
+    fn retries_full_queue_async_io() {
+        let mut tx_queue = TxQueue::new();
+        let mut attempts = 0;
+
+        let result = async_io::block_on(std::future::poll_fn(|cx| {
+            tx_queue.poll_write(cx, |_| {
+                attempts += 1;
+                match attempts {
+                    1..=3 => std::task::Poll::Ready(Err(queue_full())),
+                    _ => std::task::Poll::Ready(Ok(attempts)),
+                }
+            })
+        }));
+
+        assert_eq!(result.unwrap(), 4);
+        let stats = tx_queue.stats();
+        assert_eq!(stats.retries, 3);
+        assert!(stats.wait_time >= Duration::from_millis(7));
+    }
+
+    #[cfg(feature = "blocking")]
+    #[test]
+    fn retries_full_queue_blocking() {
+        let mut tx_queue = TxQueue::new();
+        let mut attempts = 0;
+
+        let result = tx_queue.write_blocking(|| {
+            attempts += 1;
+            match attempts {
+                1..=3 => Err(queue_full()),
+                _ => Ok(attempts),
+            }
+        });
+
+        assert_eq!(result.unwrap(), 4);
+        let stats = tx_queue.stats();
+        assert_eq!(stats.queue_full, 1);
+        assert_eq!(stats.retries, 3);
+        assert!(stats.wait_time >= Duration::from_millis(7));
+    }
+}
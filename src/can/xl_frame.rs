@@ -0,0 +1,265 @@
+/// Offset of the virtual CAN network identifier in the priority field
+const CANXL_VCID_OFFSET: u32 = 16;
+/// Mask of the virtual CAN network identifier in the priority field
+const CANXL_VCID_MASK: u32 = 0xFF << CANXL_VCID_OFFSET;
+
+/// Holds a complete CAN XL frame including the header.
+///
+/// CAN XL frames carry 1 to 2048 bytes of payload. Instead of an identifier,
+/// they have an 11 bit priority, a virtual CAN network identifier (VCID), the
+/// type of the payload (SDU type) and a 32 bit acceptance field.
+#[derive(Clone, Copy)]
+pub struct CanXlFrame(libc::canxl_frame);
+
+impl CanXlFrame {
+    /// Creates a new CAN XL frame
+    ///
+    /// Returns `None` if the priority exceeds 11 bits or the payload is empty
+    /// or longer than 2048 bytes.
+    pub fn new(prio: u16, sdt: u8, af: u32, data: &[u8]) -> Option<Self> {
+        if prio as u32 > libc::CANXL_PRIO_MASK
+            || !(libc::CANXL_MIN_DLEN..=libc::CANXL_MAX_DLEN).contains(&data.len())
+        {
+            return None;
+        }
+
+        // UNSAFE: The C struct layout needs to be zeroed in order for the
+        // unused payload to be valid
+        let mut c_canxl_frame: libc::canxl_frame = unsafe { std::mem::zeroed() };
+        c_canxl_frame.prio = prio as u32;
+        c_canxl_frame.flags = libc::CANXL_XLF as u8;
+        c_canxl_frame.sdt = sdt;
+        c_canxl_frame.len = data.len() as u16;
+        c_canxl_frame.af = af;
+        c_canxl_frame.data[..data.len()].copy_from_slice(data);
+
+        Some(Self(c_canxl_frame))
+    }
+
+    /// Creates a new CAN XL frame from an Linux CAN XL frame
+    pub fn from_inner(canxl_frame: libc::canxl_frame) -> Self {
+        Self(canxl_frame)
+    }
+
+    /// Returns the inner representation of the CAN XL frame
+    pub fn inner(&self) -> &libc::canxl_frame {
+        &self.0
+    }
+
+    /// Returns the 11 bit priority of the frame
+    pub fn prio(&self) -> u16 {
+        (self.0.prio & libc::CANXL_PRIO_MASK) as u16
+    }
+
+    /// Returns the virtual CAN network identifier
+    pub fn vcid(&self) -> u8 {
+        ((self.0.prio & CANXL_VCID_MASK) >> CANXL_VCID_OFFSET) as u8
+    }
+
+    /// Sets the virtual CAN network identifier
+    ///
+    /// The kernel only transmits the VCID if it is passed through by the
+    /// socket, see [CanXlVcidOptions].
+    pub fn set_vcid(&mut self, vcid: u8) {
+        self.0.prio = (self.0.prio & !CANXL_VCID_MASK) | ((vcid as u32) << CANXL_VCID_OFFSET);
+    }
+
+    /// Returns the SDU type describing the content of the payload
+    pub fn sdt(&self) -> u8 {
+        self.0.sdt
+    }
+
+    /// Returns the acceptance field
+    pub fn af(&self) -> u32 {
+        self.0.af
+    }
+
+    /// Returns the raw CAN XL flags (`CANXL_XLF`, `CANXL_SEC`, ...)
+    pub fn flags(&self) -> u8 {
+        self.0.flags
+    }
+
+    /// Returns `true` if the simple extended content flag is set
+    pub fn is_sec(&self) -> bool {
+        self.0.flags & libc::CANXL_SEC as u8 != 0
+    }
+
+    /// Sets or clears the simple extended content flag
+    pub fn set_sec(&mut self, sec: bool) {
+        match sec {
+            true => self.0.flags |= libc::CANXL_SEC as u8,
+            false => self.0.flags &= !(libc::CANXL_SEC as u8),
+        }
+    }
+
+    /// Returns the length of the payload in bytes
+    pub fn len(&self) -> usize {
+        self.0.len as usize
+    }
+
+    /// Returns `true` if the frame doesn't carry any payload
+    pub fn is_empty(&self) -> bool {
+        self.0.len == 0
+    }
+
+    /// Returns the payload of the frame
+    pub fn data(&self) -> &[u8] {
+        &self.0.data[..self.len().min(libc::CANXL_MAX_DLEN)]
+    }
+
+    /// Returns the header and payload as they are written to the socket
+    pub(super) fn as_bytes(&self) -> &[u8] {
+        let len = libc::CANXL_HDR_SIZE + self.len().min(libc::CANXL_MAX_DLEN);
+        // UNSAFE: The frame is a plain C struct of at least `len` bytes
+        unsafe { std::slice::from_raw_parts(&self.0 as *const libc::canxl_frame as *const u8, len) }
+    }
+
+    /// Reads a frame received from the socket
+    ///
+    /// Returns `None` if the buffer doesn't hold a complete CAN XL frame.
+    pub(super) fn from_bytes(buffer: &[u8]) -> Option<Self> {
+        if buffer.len() < libc::CANXL_HDR_SIZE + libc::CANXL_MIN_DLEN
+            || buffer.len() > libc::CANXL_MTU
+        {
+            return None;
+        }
+
+        // UNSAFE: The struct can be zeroed and the buffer fits into it
+        let mut c_canxl_frame: libc::canxl_frame = unsafe { std::mem::zeroed() };
+        unsafe {
+            std::ptr::copy_nonoverlapping(
+                buffer.as_ptr(),
+                &mut c_canxl_frame as *mut libc::canxl_frame as *mut u8,
+                buffer.len(),
+            );
+        }
+
+        let frame = Self(c_canxl_frame);
+        match frame.flags() & libc::CANXL_XLF as u8 != 0
+            && libc::CANXL_HDR_SIZE + frame.len() == buffer.len()
+        {
+            true => Some(frame),
+            false => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for CanXlFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CanXlFrame")
+            .field("prio", &self.prio())
+            .field("vcid", &self.vcid())
+            .field("sdt", &format_args!("{:#04X}", self.sdt()))
+            .field("af", &format_args!("{:#010X}", self.af()))
+            .field("flags", &format_args!("{:#04X}", self.flags()))
+            .field("data", &self.data())
+            .finish()
+    }
+}
+
+/// Handling of the VCID of CAN XL frames by a CAN bus
+///
+/// By default, the kernel clears the VCID of written frames and only receives
+/// frames without VCID.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CanXlVcidOptions {
+    /// VCID set on every written frame
+    pub tx_vcid: Option<u8>,
+    /// Transmits the VCID of the written frames instead of clearing it
+    pub tx_pass: bool,
+    /// Receives frames with `vcid & mask == rx_vcid & mask` as `(rx_vcid, mask)`
+    pub rx_filter: Option<(u8, u8)>,
+}
+
+impl CanXlVcidOptions {
+    pub(super) fn to_inner(self) -> CanRawVcidOptions {
+        let mut options = CanRawVcidOptions::default();
+        if let Some(tx_vcid) = self.tx_vcid {
+            options.flags |= CAN_RAW_XL_VCID_TX_SET;
+            options.tx_vcid = tx_vcid;
+        }
+        if self.tx_pass {
+            options.flags |= CAN_RAW_XL_VCID_TX_PASS;
+        }
+        if let Some((rx_vcid, rx_vcid_mask)) = self.rx_filter {
+            options.flags |= CAN_RAW_XL_VCID_RX_FILTER;
+            options.rx_vcid = rx_vcid;
+            options.rx_vcid_mask = rx_vcid_mask;
+        }
+        options
+    }
+}
+
+const CAN_RAW_XL_VCID_TX_SET: u8 = 0x01;
+const CAN_RAW_XL_VCID_TX_PASS: u8 = 0x02;
+const CAN_RAW_XL_VCID_RX_FILTER: u8 = 0x04;
+
+/// C representation of `struct can_raw_vcid_options`
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct CanRawVcidOptions {
+    flags: u8,
+    tx_vcid: u8,
+    rx_vcid: u8,
+    rx_vcid_mask: u8,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CanXlFrame, CanXlVcidOptions};
+
+    #[test]
+    fn creates_frame() {
+        let frame = CanXlFrame::new(0x123, 0x03, 0xDEADBEEF, &[0xAA; 100]).unwrap();
+        assert_eq!(frame.prio(), 0x123);
+        assert_eq!(frame.sdt(), 0x03);
+        assert_eq!(frame.af(), 0xDEADBEEF);
+        assert_eq!(frame.len(), 100);
+        assert_eq!(frame.data(), [0xAA; 100]);
+        assert_eq!(frame.vcid(), 0);
+        assert!(!frame.is_sec());
+
+        assert!(CanXlFrame::new(0x800, 0, 0, &[0]).is_none());
+        assert!(CanXlFrame::new(0x123, 0, 0, &[]).is_none());
+        assert!(CanXlFrame::new(0x123, 0, 0, &[0; 2049]).is_none());
+        assert!(CanXlFrame::new(0x123, 0, 0, &[0; 2048]).is_some());
+    }
+
+    #[test]
+    fn sets_vcid() {
+        let mut frame = CanXlFrame::new(0x7FF, 0, 0, &[0]).unwrap();
+        frame.set_vcid(0x42);
+        assert_eq!(frame.vcid(), 0x42);
+        assert_eq!(frame.prio(), 0x7FF);
+        frame.set_vcid(0);
+        assert_eq!(frame.inner().prio, 0x7FF);
+    }
+
+    #[test]
+    fn converts_bytes() {
+        let frame = CanXlFrame::new(0x100, 0x01, 0x12345678, &[1, 2, 3]).unwrap();
+        let bytes = frame.as_bytes();
+        assert_eq!(bytes.len(), libc::CANXL_HDR_SIZE + 3);
+
+        let parsed = CanXlFrame::from_bytes(bytes).unwrap();
+        assert_eq!(parsed.prio(), 0x100);
+        assert_eq!(parsed.af(), 0x12345678);
+        assert_eq!(parsed.data(), [1, 2, 3]);
+
+        assert!(CanXlFrame::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+    }
+
+    #[test]
+    fn converts_vcid_options() {
+        let options = CanXlVcidOptions {
+            tx_vcid: Some(0x11),
+            tx_pass: false,
+            rx_filter: Some((0x20, 0xF0)),
+        }
+        .to_inner();
+        assert_eq!(options.flags, 0x05);
+        assert_eq!(options.tx_vcid, 0x11);
+        assert_eq!(options.rx_vcid, 0x20);
+        assert_eq!(options.rx_vcid_mask, 0xF0);
+    }
+}
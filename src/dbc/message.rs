@@ -0,0 +1,150 @@
+use embedded_hal::can::{self, Frame};
+
+use crate::can::CanFrame;
+
+use super::{DbcError, Multiplexing, Signal};
+
+/// Decoded value of a signal
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DecodedSignal<'a> {
+    /// Name of the signal
+    pub name: &'a str,
+    /// Physical value of the signal
+    pub value: f64,
+    /// Unit of the physical value
+    pub unit: &'a str,
+    /// Description of the raw value from the value table
+    pub description: Option<&'a str>,
+}
+
+/// Message of a DBC database
+#[derive(Debug, Clone, PartialEq)]
+pub struct Message {
+    pub(super) id: can::Id,
+    pub(super) name: String,
+    pub(super) size: usize,
+    pub(super) transmitter: String,
+    pub(super) signals: Vec<Signal>,
+}
+
+impl Message {
+    pub fn id(&self) -> can::Id {
+        self.id
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Length of the payload in bytes
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    /// Name of the node transmitting the message
+    pub fn transmitter(&self) -> &str {
+        &self.transmitter
+    }
+
+    pub fn signals(&self) -> &[Signal] {
+        &self.signals
+    }
+
+    /// Returns the signal with the name
+    pub fn signal(&self, name: &str) -> Option<&Signal> {
+        self.signals.iter().find(|signal| signal.name == name)
+    }
+
+    /// Returns the multiplexor signal of a multiplexed message
+    pub fn multiplexor(&self) -> Option<&Signal> {
+        self.signals
+            .iter()
+            .find(|signal| signal.multiplexing == Multiplexing::Multiplexor)
+    }
+
+    /// Decodes the signals in the payload
+    ///
+    /// Multiplexed signals are only decoded if the multiplexor selects them.
+    pub fn decode<'a>(&'a self, data: &[u8]) -> Result<Vec<DecodedSignal<'a>>, DbcError> {
+        if data.len() < self.size {
+            return Err(DbcError::InvalidPayload(format!(
+                "Payload of {} bytes is too short for message '{}' with {} bytes",
+                data.len(),
+                self.name,
+                self.size
+            )));
+        }
+
+        // The size was checked, so all signals fit into the payload
+        let multiplexor = self
+            .multiplexor()
+            .and_then(|multiplexor| multiplexor.decode_raw(data));
+
+        let decoded = self
+            .signals
+            .iter()
+            .filter(|signal| match signal.multiplexing {
+                Multiplexing::Multiplexed(value) => multiplexor == Some(value),
+                _ => true,
+            })
+            .filter_map(|signal| {
+                let raw = signal.decode_raw(data)?;
+                Some(DecodedSignal {
+                    name: &signal.name,
+                    value: signal.raw_to_physical(raw),
+                    unit: &signal.unit,
+                    description: signal.value_description(raw),
+                })
+            })
+            .collect();
+
+        Ok(decoded)
+    }
+
+    /// Encodes the physical values of the signals into a payload
+    ///
+    /// Signals without a value are encoded with a raw value of `0`.
+    /// Multiplexed signals can only be encoded together with the multiplexor
+    /// value selecting them.
+    pub fn encode_data(&self, values: &[(&str, f64)]) -> Result<Vec<u8>, DbcError> {
+        let multiplexor = match self.multiplexor() {
+            Some(multiplexor) => values
+                .iter()
+                .find(|(name, _)| *name == multiplexor.name)
+                .map(|(_, value)| multiplexor.physical_to_raw(*value))
+                .transpose()?,
+            None => None,
+        };
+
+        let mut data = vec![0; self.size];
+        for (name, value) in values {
+            let signal = self
+                .signal(name)
+                .ok_or_else(|| DbcError::UnknownSignal(name.to_string()))?;
+
+            if let Multiplexing::Multiplexed(selector) = signal.multiplexing {
+                if multiplexor != Some(selector) {
+                    return Err(DbcError::InactiveSignal(name.to_string()));
+                }
+            }
+
+            signal.encode(&mut data, *value)?;
+        }
+
+        Ok(data)
+    }
+
+    /// Encodes the physical values of the signals into a frame
+    ///
+    /// See [Message::encode_data()]. Messages with more than 8 bytes don't fit
+    /// into a classic frame and return an error.
+    pub fn encode(&self, values: &[(&str, f64)]) -> Result<CanFrame, DbcError> {
+        let data = self.encode_data(values)?;
+        CanFrame::new(self.id, &data).ok_or_else(|| {
+            DbcError::InvalidPayload(format!(
+                "Message '{}' with {} bytes doesn't fit into a classic frame",
+                self.name, self.size
+            ))
+        })
+    }
+}
@@ -0,0 +1,120 @@
+//! Decoding and encoding of signals described by DBC databases.
+//!
+//! A DBC file describes the messages on a CAN bus and the signals they carry.
+//! [Dbc] parses the messages, signals, value tables and signal value types
+//! of a database. Other parts of the file (e.g., attributes and comments)
+//! are skipped.
+//!
+//! # Example:
+//! ```no_run
+//! # use ddose::{dbc::Dbc, CanBus, CanInterface};
+//! # #[tokio::main] async fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let dbc = Dbc::from_file("vehicle.dbc")?;
+//! let mut can_bus = CanBus::open(&CanInterface::try_from("can0")?)?;
+//!
+//! // Decode a received frame into physical values
+//! let frame = can_bus.read().await?;
+//! for signal in dbc.decode(&frame)? {
+//!     println!("{} = {} {}", signal.name, signal.value, signal.unit);
+//! }
+//!
+//! // Encode physical values into a frame
+//! let frame = dbc.encode("EngineData", &[("EngineSpeed", 1000.0)])?;
+//! can_bus.write(&frame).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::path::Path;
+
+use embedded_hal::can::{self, Frame};
+use thiserror::Error;
+
+use crate::can::CanFrame;
+
+mod message;
+mod parser;
+mod signal;
+
+pub use message::*;
+pub use signal::*;
+
+#[derive(Debug, Error)]
+pub enum DbcError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Invalid DBC line {line}: {reason}")]
+    Parse { line: usize, reason: String },
+
+    #[error("Unknown message: {0}")]
+    UnknownMessage(String),
+
+    #[error("Unknown signal: {0}")]
+    UnknownSignal(String),
+
+    #[error("Signal '{0}' is not selected by the multiplexor value")]
+    InactiveSignal(String),
+
+    #[error("Value {value} is out of range for signal '{signal}'")]
+    ValueOutOfRange { signal: String, value: f64 },
+
+    #[error("Invalid payload: {0}")]
+    InvalidPayload(String),
+}
+
+/// Database of messages and their signals
+#[derive(Debug, Clone, PartialEq)]
+pub struct Dbc {
+    messages: Vec<Message>,
+}
+
+impl Dbc {
+    /// Parses the content of a DBC file
+    pub fn parse(input: &str) -> Result<Self, DbcError> {
+        parser::parse(input)
+    }
+
+    /// Reads and parses a DBC file
+    ///
+    /// DBC files are often not encoded in UTF-8, so invalid characters are
+    /// replaced.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, DbcError> {
+        let content = std::fs::read(path)?;
+        Self::parse(&String::from_utf8_lossy(&content))
+    }
+
+    pub fn messages(&self) -> &[Message] {
+        &self.messages
+    }
+
+    /// Returns the message with the name
+    pub fn message(&self, name: &str) -> Option<&Message> {
+        self.messages.iter().find(|message| message.name == name)
+    }
+
+    /// Returns the message with the identifier
+    pub fn message_by_id(&self, id: impl Into<can::Id>) -> Option<&Message> {
+        let id = id.into();
+        self.messages.iter().find(|message| message.id == id)
+    }
+
+    /// Decodes the signals of a received frame
+    ///
+    /// See [Message::decode()].
+    pub fn decode<'a>(&'a self, frame: &CanFrame) -> Result<Vec<DecodedSignal<'a>>, DbcError> {
+        let message = self
+            .message_by_id(frame.id())
+            .ok_or_else(|| DbcError::UnknownMessage(format!("{:?}", frame.id())))?;
+        message.decode(&frame.data()[..frame.dlc().min(libc::CAN_MAX_DLEN)])
+    }
+
+    /// Encodes the physical values of the signals into a frame of the message
+    ///
+    /// See [Message::encode()].
+    pub fn encode(&self, message: &str, values: &[(&str, f64)]) -> Result<CanFrame, DbcError> {
+        self.message(message)
+            .ok_or_else(|| DbcError::UnknownMessage(message.to_string()))?
+            .encode(values)
+    }
+}
@@ -0,0 +1,535 @@
+use embedded_hal::can::{self, ExtendedId, StandardId};
+
+use super::{ByteOrder, Dbc, DbcError, Message, Multiplexing, Signal, ValueType};
+
+/// Keywords of statements which are terminated by a semicolon and may span
+/// multiple lines (e.g., comments)
+const TERMINATED_KEYWORDS: [&str; 16] = [
+    "CM_",
+    "VAL_",
+    "VAL_TABLE_",
+    "BA_DEF_",
+    "BA_DEF_DEF_",
+    "BA_",
+    "BA_DEF_REL_",
+    "BA_DEF_DEF_REL_",
+    "BA_REL_",
+    "SIG_VALTYPE_",
+    "SIG_GROUP_",
+    "SIG_TYPE_REF_",
+    "SG_MUL_VAL_",
+    "BO_TX_BU_",
+    "EV_",
+    "ENVVAR_DATA_",
+];
+
+/// Name of the pseudo message holding signals which aren't assigned to a
+/// message
+const INDEPENDENT_SIGNALS_MESSAGE: &str = "VECTOR__INDEPENDENT_SIG_MSG";
+
+/// Flag of the message id marking extended identifiers
+const EXTENDED_ID_FLAG: u32 = 0x8000_0000;
+
+pub(super) fn parse(input: &str) -> Result<Dbc, DbcError> {
+    let mut messages: Vec<Message> = Vec::new();
+    let mut skip_signals = false;
+    let mut in_new_symbols = false;
+
+    let mut lines = input.lines().enumerate();
+    while let Some((index, line)) = lines.next() {
+        let error = |reason: String| DbcError::Parse {
+            line: index + 1,
+            reason,
+        };
+
+        let indented = line.starts_with(char::is_whitespace);
+        let keyword = line.split_whitespace().next().unwrap_or_default();
+
+        // The list of new symbols contains keywords, which must not be
+        // mistaken for statements
+        if in_new_symbols && (indented || keyword.is_empty()) {
+            continue;
+        }
+        in_new_symbols = keyword == "NS_";
+
+        let mut statement = line.to_string();
+        if !indented && TERMINATED_KEYWORDS.contains(&keyword) {
+            while !is_terminated(&statement) {
+                let (_, line) = lines
+                    .next()
+                    .ok_or_else(|| error("Unterminated statement".to_string()))?;
+                statement.push('\n');
+                statement.push_str(line);
+            }
+        }
+
+        match keyword {
+            "BO_" => {
+                let message = parse_message(&statement).map_err(error)?;
+                skip_signals = message.name == INDEPENDENT_SIGNALS_MESSAGE;
+                if !skip_signals {
+                    messages.push(message);
+                }
+            }
+            "SG_" if !skip_signals => {
+                let signal = parse_signal(&statement).map_err(error)?;
+                let message = messages
+                    .last_mut()
+                    .ok_or_else(|| error("Signal outside of a message".to_string()))?;
+
+                let end = signal.bit_positions().into_iter().max().unwrap_or_default();
+                if end >= message.size * 8 {
+                    return Err(error(format!(
+                        "Signal '{}' exceeds the {} bytes of message '{}'",
+                        signal.name, message.size, message.name
+                    )));
+                }
+                message.signals.push(signal);
+            }
+            "VAL_" => parse_value_descriptions(&statement, &mut messages).map_err(error)?,
+            "SIG_VALTYPE_" => parse_value_type(&statement, &mut messages).map_err(error)?,
+            _ => {}
+        }
+    }
+
+    Ok(Dbc { messages })
+}
+
+/// Returns `true` if the statement contains a semicolon outside of strings
+fn is_terminated(statement: &str) -> bool {
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in statement.chars() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_string => escaped = true,
+            '"' => in_string = !in_string,
+            ';' if !in_string => return true,
+            _ => {}
+        }
+    }
+
+    false
+}
+
+/// Splits a statement into words, strings and colons up to the semicolon
+///
+/// Strings are returned without the quotes.
+fn tokenize(statement: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut chars = statement.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            ';' => break,
+            ':' => tokens.push(":".to_string()),
+            '"' => {
+                let mut string = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => string.extend(chars.next()),
+                        '"' => break,
+                        c => string.push(c),
+                    }
+                }
+                tokens.push(string);
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !";:\"".contains(*c)) {
+                    word.push(c);
+                }
+                tokens.push(word);
+            }
+        }
+    }
+
+    tokens
+}
+
+fn parse_number<T: std::str::FromStr>(number: &str, what: &str) -> Result<T, String> {
+    number
+        .trim()
+        .parse()
+        .map_err(|_| format!("Invalid {} '{}'", what, number.trim()))
+}
+
+fn parse_id(id: &str) -> Result<can::Id, String> {
+    let raw_id: u32 = parse_number(id, "message id")?;
+
+    let id = match raw_id & EXTENDED_ID_FLAG != 0 {
+        true => ExtendedId::new(raw_id & !EXTENDED_ID_FLAG & libc::CAN_EFF_MASK).map(can::Id::from),
+        false => u16::try_from(raw_id)
+            .ok()
+            .and_then(StandardId::new)
+            .map(can::Id::from),
+    };
+    id.ok_or_else(|| format!("Invalid message id '{}'", raw_id))
+}
+
+/// Parses `BO_ <id> <name>: <size> <transmitter>`
+fn parse_message(statement: &str) -> Result<Message, String> {
+    let (head, tail) = statement
+        .trim()
+        .trim_start_matches("BO_")
+        .split_once(':')
+        .ok_or("Missing ':' in message")?;
+
+    let mut head = head.split_whitespace();
+    let id = parse_id(head.next().ok_or("Missing message id")?)?;
+    let name = head.next().ok_or("Missing message name")?;
+
+    let mut tail = tail.split_whitespace();
+    let size = parse_number(tail.next().ok_or("Missing message size")?, "message size")?;
+    if size > libc::CANFD_MAX_DLEN {
+        return Err(format!("Invalid message size {}", size));
+    }
+    let transmitter = tail.next().unwrap_or_default();
+
+    Ok(Message {
+        id,
+        name: name.to_string(),
+        size,
+        transmitter: transmitter.to_string(),
+        signals: Vec::new(),
+    })
+}
+
+/// Returns the part of the text between the delimiters and the remainder
+fn between(text: &str, start: char, end: char) -> Result<(&str, &str), String> {
+    let (_, text) = text
+        .split_once(start)
+        .ok_or_else(|| format!("Missing '{}'", start))?;
+    text.split_once(end)
+        .ok_or_else(|| format!("Missing '{}'", end))
+}
+
+/// Parses `SG_ <name> [<multiplexing>] : <start>|<size>@<order><sign>
+/// (<factor>,<offset>) [<min>|<max>] "<unit>" <receivers>`
+fn parse_signal(statement: &str) -> Result<Signal, String> {
+    let (head, tail) = statement
+        .trim()
+        .trim_start_matches("SG_")
+        .split_once(':')
+        .ok_or("Missing ':' in signal")?;
+
+    let mut head = head.split_whitespace();
+    let name = head.next().ok_or("Missing signal name")?;
+    let multiplexing = match head.next() {
+        None => Multiplexing::None,
+        Some("M") => Multiplexing::Multiplexor,
+        Some(multiplexing) => {
+            // Extended multiplexing (`m1M`) is decoded like simple multiplexing
+            let value = multiplexing
+                .strip_prefix('m')
+                .map(|value| value.trim_end_matches('M'))
+                .ok_or_else(|| format!("Invalid multiplexing '{}'", multiplexing))?;
+            Multiplexing::Multiplexed(parse_number(value, "multiplexor value")?)
+        }
+    };
+
+    let (layout, _) = tail.split_once('(').ok_or("Missing '('")?;
+    let (start_bit, layout) = layout.split_once('|').ok_or("Missing '|' in signal")?;
+    let (size, layout) = layout.split_once('@').ok_or("Missing '@' in signal")?;
+    let start_bit: usize = parse_number(start_bit, "start bit")?;
+    let size: usize = parse_number(size, "signal size")?;
+    if !(1..=64).contains(&size) {
+        return Err(format!("Invalid signal size {}", size));
+    }
+    if start_bit >= libc::CANFD_MAX_DLEN * 8 {
+        return Err(format!("Invalid start bit {}", start_bit));
+    }
+
+    let (byte_order, value_type) = match layout.trim() {
+        "1+" => (ByteOrder::LittleEndian, ValueType::Unsigned),
+        "1-" => (ByteOrder::LittleEndian, ValueType::Signed),
+        "0+" => (ByteOrder::BigEndian, ValueType::Unsigned),
+        "0-" => (ByteOrder::BigEndian, ValueType::Signed),
+        layout => return Err(format!("Invalid byte order and sign '{}'", layout)),
+    };
+
+    let (scaling, tail) = between(tail, '(', ')')?;
+    let (factor, offset) = scaling.split_once(',').ok_or("Missing ',' in scaling")?;
+    let (range, tail) = between(tail, '[', ']')?;
+    let (min, max) = range.split_once('|').ok_or("Missing '|' in range")?;
+    let (unit, receivers) = between(tail, '"', '"')?;
+
+    Ok(Signal {
+        name: name.to_string(),
+        start_bit,
+        size,
+        byte_order,
+        value_type,
+        factor: parse_number(factor, "factor")?,
+        offset: parse_number(offset, "offset")?,
+        min: parse_number(min, "minimum")?,
+        max: parse_number(max, "maximum")?,
+        unit: unit.to_string(),
+        receivers: receivers
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|receiver| !receiver.is_empty())
+            .map(str::to_string)
+            .collect(),
+        multiplexing,
+        value_descriptions: Vec::new(),
+    })
+}
+
+/// Returns the signal of the message, `None` if the message or signal is not
+/// defined
+fn find_signal<'a>(
+    messages: &'a mut [Message],
+    id: &str,
+    name: &str,
+) -> Result<Option<&'a mut Signal>, String> {
+    let id = parse_id(id)?;
+    Ok(messages
+        .iter_mut()
+        .find(|message| message.id == id)
+        .and_then(|message| {
+            message
+                .signals
+                .iter_mut()
+                .find(|signal| signal.name == name)
+        }))
+}
+
+/// Parses `VAL_ <id> <signal> <value> "<description>" ... ;`
+fn parse_value_descriptions(statement: &str, messages: &mut [Message]) -> Result<(), String> {
+    let tokens = tokenize(statement);
+
+    // Value descriptions of environment variables don't have a message id
+    let (Some(id), Some(name)) = (tokens.get(1), tokens.get(2)) else {
+        return Err("Incomplete value descriptions".to_string());
+    };
+    if !id.starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok(());
+    }
+
+    let descriptions = &tokens[3..];
+    if !descriptions.len().is_multiple_of(2) {
+        return Err("Value without description".to_string());
+    }
+    let descriptions = descriptions
+        .chunks(2)
+        .map(|pair| Ok((parse_number(&pair[0], "value")?, pair[1].clone())))
+        .collect::<Result<_, String>>()?;
+
+    if let Some(signal) = find_signal(messages, id, name)? {
+        signal.value_descriptions = descriptions;
+    }
+
+    Ok(())
+}
+
+/// Parses `SIG_VALTYPE_ <id> <signal> : <type>;`
+fn parse_value_type(statement: &str, messages: &mut [Message]) -> Result<(), String> {
+    let tokens: Vec<_> = tokenize(statement)
+        .into_iter()
+        .filter(|token| token != ":")
+        .collect();
+    let [_, id, name, value_type] = tokens.as_slice() else {
+        return Err("Invalid signal value type".to_string());
+    };
+
+    let Some(signal) = find_signal(messages, id, name)? else {
+        return Ok(());
+    };
+
+    signal.value_type = match (value_type.as_str(), signal.size) {
+        ("0", _) => signal.value_type,
+        ("1", 32) => ValueType::Float,
+        ("2", 64) => ValueType::Double,
+        (value_type, size) => {
+            return Err(format!(
+                "Invalid value type {} for signal '{}' with {} bits",
+                value_type, name, size
+            ))
+        }
+    };
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal::can::{ExtendedId, Frame, Id, StandardId};
+
+    use super::tokenize;
+    use crate::dbc::{ByteOrder, Dbc, DbcError, Multiplexing, ValueType};
+
+    const DBC: &str = r#"VERSION ""
+
+NS_ :
+    CM_
+    BA_DEF_
+    VAL_
+
+BS_:
+
+BU_: Engine Gateway
+
+BO_ 100 EngineData: 8 Engine
+ SG_ EngineSpeed : 0|16@1+ (0.125,0) [0|8031.875] "rpm" Gateway
+ SG_ CoolantTemp : 23|8@0- (1,0) [-40|127] "degC" Gateway,Dashboard
+ SG_ Gear : 31|4@0+ (1,0) [0|0] "" Gateway
+
+BO_ 2566844926 Diagnostics: 8 Gateway
+ SG_ Mode M : 0|8@1+ (1,0) [0|255] "" Engine
+ SG_ Voltage m1 : 8|16@1+ (0.001,0) [0|65.535] "V" Engine
+ SG_ Current m2 : 8|32@1- (1,0) [0|0] "mA" Engine
+ SG_ Pressure m2 : 40|16@1+ (0.1,0) [0|0] "bar" Engine
+
+BO_ 3221225472 VECTOR__INDEPENDENT_SIG_MSG: 0 Vector__XXX
+ SG_ Unused : 0|8@1+ (1,0) [0|0] "" Vector__XXX
+
+BO_ 200 Ratio: 4 Gateway
+ SG_ Ratio : 0|32@1- (1,0) [0|0] "" Engine
+
+CM_ SG_ 100 EngineSpeed "Speed of the engine;
+measured at the crankshaft";
+BA_DEF_ BO_  "GenMsgCycleTime" INT 0 10000;
+VAL_ 100 Gear 0 "Park" 1 "Reverse" 2 "Neutral" 3 "Drive" ;
+SIG_VALTYPE_ 200 Ratio : 1;
+"#;
+
+    #[test]
+    fn tokenizes_statements() {
+        assert_eq!(
+            tokenize(r#"VAL_ 100 Gear 0 "P;a\"rk" 1 "R";"#),
+            ["VAL_", "100", "Gear", "0", "P;a\"rk", "1", "R"]
+        );
+        assert_eq!(
+            tokenize("SIG_VALTYPE_ 1 S: 1;"),
+            ["SIG_VALTYPE_", "1", "S", ":", "1"]
+        );
+    }
+
+    #[test]
+    fn parses_database() {
+        let dbc = Dbc::parse(DBC).unwrap();
+        assert_eq!(dbc.messages().len(), 3);
+
+        let message = dbc.message("EngineData").unwrap();
+        assert_eq!(message.id(), Id::Standard(StandardId::new(100).unwrap()));
+        assert_eq!(message.size(), 8);
+        assert_eq!(message.transmitter(), "Engine");
+        assert_eq!(message.signals().len(), 3);
+
+        let signal = message.signal("CoolantTemp").unwrap();
+        assert_eq!(signal.start_bit(), 23);
+        assert_eq!(signal.byte_order(), ByteOrder::BigEndian);
+        assert_eq!(signal.value_type(), ValueType::Signed);
+        assert_eq!(signal.min(), -40.0);
+        assert_eq!(signal.unit(), "degC");
+        assert_eq!(signal.receivers(), ["Gateway", "Dashboard"]);
+        assert_eq!(
+            message.signal("Gear").unwrap().value_description(3),
+            Some("Drive")
+        );
+
+        let message = dbc
+            .message_by_id(ExtendedId::new(0x18FEF1FE).unwrap())
+            .unwrap();
+        assert_eq!(message.name(), "Diagnostics");
+        assert_eq!(
+            message.signal("Voltage").unwrap().multiplexing(),
+            Multiplexing::Multiplexed(1)
+        );
+        assert_eq!(message.multiplexor().unwrap().name(), "Mode");
+
+        let signal = dbc.message("Ratio").unwrap().signal("Ratio").unwrap();
+        assert_eq!(signal.value_type(), ValueType::Float);
+    }
+
+    #[test]
+    fn decodes_frame() {
+        let dbc = Dbc::parse(DBC).unwrap();
+        let id = StandardId::new(100).unwrap();
+        let frame = crate::CanFrame::new(id, &[0x40, 0x1F, 0xFB, 0x30, 0, 0, 0, 0]).unwrap();
+
+        let signals = dbc.decode(&frame).unwrap();
+        assert_eq!(signals.len(), 3);
+        assert_eq!(signals[0].name, "EngineSpeed");
+        assert_eq!(signals[0].value, 1000.0);
+        assert_eq!(signals[0].unit, "rpm");
+        assert_eq!(signals[1].value, -5.0);
+        assert_eq!(signals[2].value, 3.0);
+        assert_eq!(signals[2].description, Some("Drive"));
+    }
+
+    #[test]
+    fn decodes_multiplexed_frame() {
+        let dbc = Dbc::parse(DBC).unwrap();
+        let message = dbc.message("Diagnostics").unwrap();
+
+        let signals = message
+            .decode(&[0x02, 0xFE, 0xFF, 0xFF, 0xFF, 0x64, 0x00, 0x00])
+            .unwrap();
+        let values: Vec<_> = signals
+            .iter()
+            .map(|signal| (signal.name, signal.value))
+            .collect();
+        assert_eq!(
+            values,
+            [("Mode", 2.0), ("Current", -2.0), ("Pressure", 10.0)]
+        );
+        assert!(message.decode(&[0x02]).is_err());
+    }
+
+    #[test]
+    fn encodes_frame() {
+        let dbc = Dbc::parse(DBC).unwrap();
+
+        let frame = dbc
+            .encode(
+                "EngineData",
+                &[
+                    ("EngineSpeed", 1000.0),
+                    ("CoolantTemp", -5.0),
+                    ("Gear", 3.0),
+                ],
+            )
+            .unwrap();
+        assert_eq!(frame.data(), [0x40, 0x1F, 0xFB, 0x30, 0, 0, 0, 0]);
+
+        let frame = dbc
+            .encode("Diagnostics", &[("Mode", 1.0), ("Voltage", 12.5)])
+            .unwrap();
+        assert!(frame.is_extended());
+        assert_eq!(&frame.data()[..3], [0x01, 0xD4, 0x30]);
+
+        assert!(matches!(
+            dbc.encode("Diagnostics", &[("Mode", 1.0), ("Current", 1.0)]),
+            Err(DbcError::InactiveSignal(_))
+        ));
+        assert!(matches!(
+            dbc.encode("EngineData", &[("Unknown", 1.0)]),
+            Err(DbcError::UnknownSignal(_))
+        ));
+        assert!(matches!(
+            dbc.encode("EngineData", &[("CoolantTemp", 300.0)]),
+            Err(DbcError::ValueOutOfRange { .. })
+        ));
+        assert!(matches!(
+            dbc.encode("Unknown", &[]),
+            Err(DbcError::UnknownMessage(_))
+        ));
+    }
+
+    #[test]
+    fn reports_invalid_lines() {
+        let dbc = "BO_ 100 Message: 1 Node\n SG_ Signal : 4|8@1+ (1,0) [0|0] \"\" Node\n";
+        match Dbc::parse(dbc) {
+            Err(DbcError::Parse { line, .. }) => assert_eq!(line, 2),
+            _ => panic!("Expected parse error"),
+        }
+
+        assert!(Dbc::parse("BO_ 2048 Message: 8 Node").is_err());
+        assert!(Dbc::parse("CM_ \"Unterminated comment").is_err());
+
+        let dbc = "BO_ 100 Message: 8 Node\n SG_ Signal : 18446744073709551615|8@1+ (1,0) [0|0] \"\" Node\n";
+        assert!(Dbc::parse(dbc).is_err());
+    }
+}
@@ -0,0 +1,364 @@
+use super::DbcError;
+
+/// Byte order of a signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteOrder {
+    /// Intel byte order (`@1`), the start bit is the least significant bit
+    LittleEndian,
+    /// Motorola byte order (`@0`), the start bit is the most significant bit
+    BigEndian,
+}
+
+/// Type of the raw value of a signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ValueType {
+    Unsigned,
+    /// Two's complement signed integer
+    Signed,
+    /// IEEE 754 single precision float (`SIG_VALTYPE_ 1`)
+    Float,
+    /// IEEE 754 double precision float (`SIG_VALTYPE_ 2`)
+    Double,
+}
+
+/// Role of a signal in a multiplexed message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexing {
+    /// The signal is always present
+    None,
+    /// The signal selects which multiplexed signals are present (`M`)
+    Multiplexor,
+    /// The signal is only present if the multiplexor has the value (`m<value>`)
+    Multiplexed(u64),
+}
+
+/// Signal of a message in a DBC database
+///
+/// The raw value of the signal is converted to the physical value using
+/// `physical = raw * factor + offset`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Signal {
+    pub(super) name: String,
+    pub(super) start_bit: usize,
+    pub(super) size: usize,
+    pub(super) byte_order: ByteOrder,
+    pub(super) value_type: ValueType,
+    pub(super) factor: f64,
+    pub(super) offset: f64,
+    pub(super) min: f64,
+    pub(super) max: f64,
+    pub(super) unit: String,
+    pub(super) receivers: Vec<String>,
+    pub(super) multiplexing: Multiplexing,
+    pub(super) value_descriptions: Vec<(i64, String)>,
+}
+
+impl Signal {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Start bit as written in the DBC file
+    pub fn start_bit(&self) -> usize {
+        self.start_bit
+    }
+
+    /// Length of the signal in bits
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn byte_order(&self) -> ByteOrder {
+        self.byte_order
+    }
+
+    pub fn value_type(&self) -> ValueType {
+        self.value_type
+    }
+
+    pub fn factor(&self) -> f64 {
+        self.factor
+    }
+
+    pub fn offset(&self) -> f64 {
+        self.offset
+    }
+
+    /// Minimum physical value
+    pub fn min(&self) -> f64 {
+        self.min
+    }
+
+    /// Maximum physical value
+    pub fn max(&self) -> f64 {
+        self.max
+    }
+
+    pub fn unit(&self) -> &str {
+        &self.unit
+    }
+
+    /// Names of the nodes receiving the signal
+    pub fn receivers(&self) -> &[String] {
+        &self.receivers
+    }
+
+    pub fn multiplexing(&self) -> Multiplexing {
+        self.multiplexing
+    }
+
+    /// Descriptions of raw values from the value table (`VAL_`)
+    pub fn value_descriptions(&self) -> &[(i64, String)] {
+        &self.value_descriptions
+    }
+
+    /// Returns the description of a raw value from the value table
+    pub fn value_description(&self, raw: u64) -> Option<&str> {
+        let raw = match self.value_type {
+            ValueType::Signed => sign_extend(raw, self.size),
+            _ => raw as i64,
+        };
+
+        self.value_descriptions
+            .iter()
+            .find(|(value, _)| *value == raw)
+            .map(|(_, description)| description.as_str())
+    }
+
+    /// Positions of the bits in the payload, starting with the least
+    /// significant bit of the value
+    ///
+    /// Bit `n` is bit `n % 8` of byte `n / 8`.
+    pub(super) fn bit_positions(&self) -> Vec<usize> {
+        match self.byte_order {
+            ByteOrder::LittleEndian => (self.start_bit..self.start_bit + self.size).collect(),
+            ByteOrder::BigEndian => {
+                // Motorola signals continue in the most significant bit of
+                // the next byte once the least significant bit is reached
+                let mut positions = Vec::with_capacity(self.size);
+                let mut position = self.start_bit;
+                for _ in 0..self.size {
+                    positions.push(position);
+                    position = match position % 8 {
+                        0 => position + 15,
+                        _ => position - 1,
+                    };
+                }
+                positions.reverse();
+                positions
+            }
+        }
+    }
+
+    /// Extracts the raw value from the payload
+    ///
+    /// Returns `None` if the payload is too short for the signal.
+    pub fn decode_raw(&self, data: &[u8]) -> Option<u64> {
+        self.bit_positions()
+            .iter()
+            .enumerate()
+            .try_fold(0u64, |raw, (i, position)| {
+                let byte = data.get(position / 8)?;
+                Some(raw | (((*byte >> (position % 8)) & 1) as u64) << i)
+            })
+    }
+
+    /// Extracts the physical value from the payload
+    ///
+    /// Returns `None` if the payload is too short for the signal.
+    pub fn decode(&self, data: &[u8]) -> Option<f64> {
+        self.decode_raw(data).map(|raw| self.raw_to_physical(raw))
+    }
+
+    /// Converts a raw value into the physical value
+    pub fn raw_to_physical(&self, raw: u64) -> f64 {
+        let value = match self.value_type {
+            ValueType::Unsigned => raw as f64,
+            ValueType::Signed => sign_extend(raw, self.size) as f64,
+            ValueType::Float => f32::from_bits(raw as u32) as f64,
+            ValueType::Double => f64::from_bits(raw),
+        };
+
+        value * self.factor + self.offset
+    }
+
+    /// Converts a physical value into the raw value
+    ///
+    /// Fails if the value is outside of the minimum and maximum of the signal
+    /// or doesn't fit in the signal.
+    pub fn physical_to_raw(&self, value: f64) -> Result<u64, DbcError> {
+        let out_of_range = || DbcError::ValueOutOfRange {
+            signal: self.name.clone(),
+            value,
+        };
+
+        // A minimum equal to the maximum means that the range is not defined
+        if self.min < self.max && (value < self.min || value > self.max) {
+            return Err(out_of_range());
+        }
+
+        let scaled = (value - self.offset) / self.factor;
+        let mask = match self.size {
+            64 => u64::MAX,
+            size => (1 << size) - 1,
+        };
+
+        match self.value_type {
+            ValueType::Unsigned => {
+                let raw = scaled.round();
+                if !(0.0..=mask as f64).contains(&raw) {
+                    return Err(out_of_range());
+                }
+                Ok(raw as u64)
+            }
+            ValueType::Signed => {
+                let raw = scaled.round();
+                let limit = (1u64 << (self.size - 1)) as f64;
+                if !(-limit..limit).contains(&raw) {
+                    return Err(out_of_range());
+                }
+                Ok(raw as i64 as u64 & mask)
+            }
+            ValueType::Float => Ok((scaled as f32).to_bits() as u64),
+            ValueType::Double => Ok(scaled.to_bits()),
+        }
+    }
+
+    /// Writes the raw value into the payload
+    ///
+    /// Bits of the payload which don't belong to the signal are preserved.
+    pub fn encode_raw(&self, data: &mut [u8], raw: u64) -> Result<(), DbcError> {
+        let positions = self.bit_positions();
+        if positions.iter().any(|position| position / 8 >= data.len()) {
+            return Err(DbcError::InvalidPayload(format!(
+                "Payload of {} bytes is too short for signal '{}'",
+                data.len(),
+                self.name
+            )));
+        }
+
+        for (i, position) in positions.iter().enumerate() {
+            let byte = &mut data[position / 8];
+            let bit = 1 << (position % 8);
+            match (raw >> i) & 1 {
+                0 => *byte &= !bit,
+                _ => *byte |= bit,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes the physical value into the payload
+    pub fn encode(&self, data: &mut [u8], value: f64) -> Result<(), DbcError> {
+        let raw = self.physical_to_raw(value)?;
+        self.encode_raw(data, raw)
+    }
+}
+
+fn sign_extend(raw: u64, size: usize) -> i64 {
+    let shift = 64 - size;
+    ((raw << shift) as i64) >> shift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ByteOrder, Multiplexing, Signal, ValueType};
+
+    fn signal(start_bit: usize, size: usize, byte_order: ByteOrder) -> Signal {
+        Signal {
+            name: "Signal".to_string(),
+            start_bit,
+            size,
+            byte_order,
+            value_type: ValueType::Unsigned,
+            factor: 1.0,
+            offset: 0.0,
+            min: 0.0,
+            max: 0.0,
+            unit: String::new(),
+            receivers: Vec::new(),
+            multiplexing: Multiplexing::None,
+            value_descriptions: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn decodes_intel_signal() {
+        let signal = signal(4, 12, ByteOrder::LittleEndian);
+        assert_eq!(signal.decode_raw(&[0xA0, 0xBC]), Some(0xBCA));
+        assert_eq!(signal.decode_raw(&[0xA0]), None);
+    }
+
+    #[test]
+    fn decodes_motorola_signal() {
+        // MSB in bit 7 of byte 0, LSB in bit 4 of byte 1
+        let signal = signal(7, 12, ByteOrder::BigEndian);
+        assert_eq!(signal.decode_raw(&[0xAB, 0xC0]), Some(0xABC));
+
+        let signal = super::Signal {
+            start_bit: 3,
+            size: 8,
+            ..signal
+        };
+        assert_eq!(signal.decode_raw(&[0x0A, 0xB0]), Some(0xAB));
+    }
+
+    #[test]
+    fn encodes_signal() {
+        let signal = signal(7, 12, ByteOrder::BigEndian);
+        let mut data = [0x00, 0x0F];
+        signal.encode_raw(&mut data, 0xABC).unwrap();
+        assert_eq!(data, [0xAB, 0xCF]);
+
+        let signal = super::Signal {
+            byte_order: ByteOrder::LittleEndian,
+            start_bit: 4,
+            ..signal
+        };
+        let mut data = [0x0F, 0x00];
+        signal.encode_raw(&mut data, 0xABC).unwrap();
+        assert_eq!(data, [0xCF, 0xAB]);
+        assert!(signal.encode_raw(&mut [0], 0).is_err());
+    }
+
+    #[test]
+    fn converts_signed_values() {
+        let signal = Signal {
+            value_type: ValueType::Signed,
+            factor: 0.5,
+            offset: 10.0,
+            ..signal(0, 8, ByteOrder::LittleEndian)
+        };
+
+        assert_eq!(signal.raw_to_physical(0xFE), 9.0);
+        assert_eq!(signal.physical_to_raw(9.0).unwrap(), 0xFE);
+        assert_eq!(signal.physical_to_raw(73.5).unwrap(), 0x7F);
+        assert!(signal.physical_to_raw(74.0).is_err());
+        assert!(signal.physical_to_raw(-54.5).is_err());
+    }
+
+    #[test]
+    fn converts_float_values() {
+        let signal = Signal {
+            value_type: ValueType::Float,
+            ..signal(0, 32, ByteOrder::LittleEndian)
+        };
+
+        let mut data = [0; 4];
+        signal.encode(&mut data, 1.5).unwrap();
+        assert_eq!(data, 1.5f32.to_le_bytes());
+        assert_eq!(signal.decode(&data), Some(1.5));
+    }
+
+    #[test]
+    fn checks_value_range() {
+        let signal = Signal {
+            min: 0.0,
+            max: 100.0,
+            ..signal(0, 8, ByteOrder::LittleEndian)
+        };
+        assert!(signal.physical_to_raw(100.0).is_ok());
+        assert!(signal.physical_to_raw(101.0).is_err());
+    }
+}
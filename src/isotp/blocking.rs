@@ -0,0 +1,63 @@
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+use embedded_hal::can::Id as CanId;
+
+use crate::socket::{timed_out, CanInterface, RawSocket};
+
+/// ISO-TP connection which blocks the calling thread
+///
+/// Reads and writes block the thread until the payload is transferred or the
+/// timeout of the connection expires.
+pub struct IsotpConnection {
+    socket: RawSocket,
+}
+
+impl IsotpConnection {
+    pub fn open(
+        can_if: &CanInterface,
+        tx_id: impl Into<CanId>,
+        rx_id: impl Into<CanId>,
+    ) -> Result<Self, std::io::Error> {
+        let socket = super::connection::open_socket(can_if, tx_id, rx_id)?;
+
+        Ok(Self { socket })
+    }
+
+    /// Sets the time after which [IsotpConnection::read()] fails with
+    /// `TimedOut`, `None` waits forever (default)
+    pub fn set_read_timeout(
+        &mut self,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<(), std::io::Error> {
+        self.socket.set_read_timeout(timeout)
+    }
+
+    /// Sets the time after which [IsotpConnection::write()] fails with
+    /// `TimedOut`, `None` waits forever (default)
+    pub fn set_write_timeout(
+        &mut self,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<(), std::io::Error> {
+        self.socket.set_write_timeout(timeout)
+    }
+
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<usize, std::io::Error> {
+        self.socket.read(buffer).map_err(timed_out)
+    }
+
+    pub fn write(&mut self, buffer: &[u8]) -> Result<usize, std::io::Error> {
+        self.socket.write(buffer).map_err(timed_out)
+    }
+}
+
+impl AsRawFd for IsotpConnection {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl AsFd for IsotpConnection {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.socket.as_fd()
+    }
+}
@@ -1,75 +1,85 @@
-use std::os::unix::prelude::AsRawFd;
-
 use embedded_hal::can::Id as CanId;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
 
-use crate::socket::{CanInterface, CanSocket};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use crate::socket::CanSocket;
+use crate::{
+    socket::{CanInterface, RawSocket},
+    sockopt::{SocketOption, SOL_CAN_ISOTP},
+};
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 pub struct IsotpConnection {
     socket: CanSocket,
 }
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 impl IsotpConnection {
     pub fn open(
         can_if: &CanInterface,
         tx_id: impl Into<CanId>,
         rx_id: impl Into<CanId>,
     ) -> Result<Self, std::io::Error> {
-        let socket = CanSocket::create(libc::SOCK_DGRAM, libc::CAN_ISOTP)?;
+        let socket = CanSocket::from_raw(open_socket(can_if, tx_id, rx_id)?)?;
         socket.set_nonblocking()?;
 
-        let fd = socket.as_raw_fd();
-        let mut isotp_options = IsoTpOptions::new();
-        isotp_options.set_flag(IsotpOptionsFlag::WaitTxDone);
-
-        let isotp_options_ptr: *const libc::c_void =
-            &isotp_options as *const _ as *const libc::c_void;
-
-        let _ = unsafe {
-            libc::setsockopt(
-                fd,
-                libc::SOL_CAN_BASE + libc::CAN_ISOTP,
-                1, // Constant...
-                isotp_options_ptr,
-                ISOTP_OPTIONS_SIZE.try_into().unwrap(),
-            )
-        };
-
-        // The socket must be bound to the specific ISOTP TX and RX IDs
-        let can_addr = Self::can_address(rx_id, tx_id);
-        socket.bind_address(can_if, can_addr)?;
-
         Ok(Self { socket })
     }
 
+    /// Returns the underlying socket, e.g. to set the
+    /// [separation time](crate::sockopt::CAN_ISOTP_TX_STMIN)
+    pub fn socket(&self) -> &CanSocket {
+        &self.socket
+    }
+
     pub async fn read(&mut self, buffer: &mut [u8]) -> Result<usize, std::io::Error> {
-        self.socket.read(buffer).await
+        std::future::poll_fn(|cx| self.socket.poll_read_bytes(cx, buffer)).await
     }
 
     pub async fn write(&mut self, buffer: &[u8]) -> Result<usize, std::io::Error> {
-        self.socket.write(buffer).await
+        std::future::poll_fn(|cx| self.socket.poll_write_bytes(cx, buffer)).await
     }
+}
 
-    fn can_address(
-        tx_id: impl Into<CanId>,
-        rx_id: impl Into<CanId>,
-    ) -> libc::__c_anonymous_sockaddr_can_can_addr {
-        let mut address: libc::__c_anonymous_sockaddr_can_can_addr = unsafe { std::mem::zeroed() };
-        address.tp.rx_id = match rx_id.into() {
-            CanId::Standard(id) => id.as_raw() as u32,
-            CanId::Extended(id) => id.as_raw(),
-        };
-        address.tp.tx_id = match tx_id.into() {
-            CanId::Standard(id) => id.as_raw() as u32,
-            CanId::Extended(id) => id.as_raw(),
-        };
-
-        address
-    }
+fn can_address(
+    tx_id: impl Into<CanId>,
+    rx_id: impl Into<CanId>,
+) -> libc::__c_anonymous_sockaddr_can_can_addr {
+    let mut address: libc::__c_anonymous_sockaddr_can_can_addr = unsafe { std::mem::zeroed() };
+    address.tp.rx_id = match rx_id.into() {
+        CanId::Standard(id) => id.as_raw() as u32,
+        CanId::Extended(id) => id.as_raw(),
+    };
+    address.tp.tx_id = match tx_id.into() {
+        CanId::Standard(id) => id.as_raw() as u32,
+        CanId::Extended(id) => id.as_raw(),
+    };
+
+    address
+}
+
+/// Creates an ISO-TP socket and binds it to the TX and RX IDs
+pub(super) fn open_socket(
+    can_if: &CanInterface,
+    tx_id: impl Into<CanId>,
+    rx_id: impl Into<CanId>,
+) -> Result<RawSocket, std::io::Error> {
+    let socket = RawSocket::create(libc::SOCK_DGRAM, libc::CAN_ISOTP)?;
+
+    let mut isotp_options = IsoTpOptions::new();
+    isotp_options.set_flag(IsotpOptionsFlag::WaitTxDone);
+    socket.set_option(CAN_ISOTP_OPTS, &isotp_options)?;
+
+    // The socket must be bound to the specific ISOTP TX and RX IDs
+    let can_addr = can_address(rx_id, tx_id);
+    socket.bind_address(can_if, can_addr)?;
+
+    Ok(socket)
 }
 
-const ISOTP_OPTIONS_SIZE: usize = std::mem::size_of::<IsoTpOptions>();
+// UNSAFE: IsoTpOptions has the layout of `struct can_isotp_options`
+const CAN_ISOTP_OPTS: SocketOption<IsoTpOptions> = unsafe { SocketOption::new(SOL_CAN_ISOTP, 1) };
 
+#[allow(dead_code)]
 #[repr(u16)]
 enum IsotpOptionsFlag {
     /// Listen only (do not send FC)
@@ -130,10 +140,11 @@ impl IsoTpOptions {
     }
 
     pub fn set_flag(&mut self, flag: IsotpOptionsFlag) {
-        self.flags = self.flags | flag as u32;
+        self.flags |= flag as u32;
     }
 
+    #[allow(dead_code)]
     pub fn clear_flag(&mut self, flag: IsotpOptionsFlag) {
-        self.flags = self.flags & !(flag as u32);
+        self.flags &= !(flag as u32);
     }
 }
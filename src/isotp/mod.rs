@@ -1,3 +1,7 @@
 mod connection;
 
+#[cfg(feature = "blocking")]
+pub(crate) mod blocking;
+
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 pub use connection::*;
@@ -0,0 +1,7 @@
+mod name;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+mod socket;
+
+pub use name::*;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use socket::*;
@@ -0,0 +1,169 @@
+/// 64 bit NAME of a J1939 control application
+///
+/// The NAME uniquely identifies an ECU on the network and is used to resolve
+/// address conflicts during the address claiming. The ECU with the lower NAME
+/// wins, which is reflected by the [Ord] implementation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct J1939Name(u64);
+
+impl J1939Name {
+    /// Creates a NAME from its raw representation
+    pub fn from_raw(name: u64) -> Self {
+        Self(name)
+    }
+
+    /// Returns the raw representation of the NAME
+    pub fn as_raw(&self) -> u64 {
+        self.0
+    }
+
+    fn field(&self, offset: u32, bits: u32) -> u64 {
+        (self.0 >> offset) & ((1 << bits) - 1)
+    }
+
+    fn set_field(&mut self, offset: u32, bits: u32, value: u64) {
+        let mask = ((1 << bits) - 1) << offset;
+        self.0 = (self.0 & !mask) | ((value << offset) & mask);
+    }
+
+    /// Unique number assigned by the manufacturer (21 bits)
+    pub fn identity_number(&self) -> u32 {
+        self.field(0, 21) as u32
+    }
+
+    pub fn set_identity_number(&mut self, identity_number: u32) {
+        self.set_field(0, 21, identity_number as u64)
+    }
+
+    /// Code of the manufacturer assigned by the SAE (11 bits)
+    pub fn manufacturer_code(&self) -> u16 {
+        self.field(21, 11) as u16
+    }
+
+    pub fn set_manufacturer_code(&mut self, manufacturer_code: u16) {
+        self.set_field(21, 11, manufacturer_code as u64)
+    }
+
+    /// Instance of the ECU with the same function (3 bits)
+    pub fn ecu_instance(&self) -> u8 {
+        self.field(32, 3) as u8
+    }
+
+    pub fn set_ecu_instance(&mut self, ecu_instance: u8) {
+        self.set_field(32, 3, ecu_instance as u64)
+    }
+
+    /// Instance of the function on the network (5 bits)
+    pub fn function_instance(&self) -> u8 {
+        self.field(35, 5) as u8
+    }
+
+    pub fn set_function_instance(&mut self, function_instance: u8) {
+        self.set_field(35, 5, function_instance as u64)
+    }
+
+    /// Function of the ECU (8 bits)
+    pub fn function(&self) -> u8 {
+        self.field(40, 8) as u8
+    }
+
+    pub fn set_function(&mut self, function: u8) {
+        self.set_field(40, 8, function as u64)
+    }
+
+    /// Vehicle system the ECU belongs to (7 bits)
+    pub fn vehicle_system(&self) -> u8 {
+        self.field(49, 7) as u8
+    }
+
+    pub fn set_vehicle_system(&mut self, vehicle_system: u8) {
+        self.set_field(49, 7, vehicle_system as u64)
+    }
+
+    /// Instance of the vehicle system (4 bits)
+    pub fn vehicle_system_instance(&self) -> u8 {
+        self.field(56, 4) as u8
+    }
+
+    pub fn set_vehicle_system_instance(&mut self, vehicle_system_instance: u8) {
+        self.set_field(56, 4, vehicle_system_instance as u64)
+    }
+
+    /// Industry group of the ECU (3 bits)
+    pub fn industry_group(&self) -> u8 {
+        self.field(60, 3) as u8
+    }
+
+    pub fn set_industry_group(&mut self, industry_group: u8) {
+        self.set_field(60, 3, industry_group as u64)
+    }
+
+    /// Returns `true` if the ECU can claim any address
+    pub fn arbitrary_address_capable(&self) -> bool {
+        self.field(63, 1) != 0
+    }
+
+    pub fn set_arbitrary_address_capable(&mut self, capable: bool) {
+        self.set_field(63, 1, capable as u64)
+    }
+}
+
+impl From<u64> for J1939Name {
+    fn from(name: u64) -> Self {
+        Self(name)
+    }
+}
+
+impl From<J1939Name> for u64 {
+    fn from(name: J1939Name) -> Self {
+        name.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::J1939Name;
+
+    #[test]
+    fn encodes_fields() {
+        let mut name = J1939Name::default();
+        name.set_identity_number(0x12345);
+        name.set_manufacturer_code(0x7FF);
+        name.set_ecu_instance(1);
+        name.set_function_instance(2);
+        name.set_function(0x81);
+        name.set_vehicle_system(0x7F);
+        name.set_vehicle_system_instance(3);
+        name.set_industry_group(2);
+        name.set_arbitrary_address_capable(true);
+
+        assert_eq!(name.as_raw(), 0xA3FE_8111_FFE1_2345);
+    }
+
+    #[test]
+    fn decodes_fields() {
+        let name = J1939Name::from_raw(0xA3FE_8111_FFE1_2345);
+        assert_eq!(name.identity_number(), 0x12345);
+        assert_eq!(name.manufacturer_code(), 0x7FF);
+        assert_eq!(name.ecu_instance(), 1);
+        assert_eq!(name.function_instance(), 2);
+        assert_eq!(name.function(), 0x81);
+        assert_eq!(name.vehicle_system(), 0x7F);
+        assert_eq!(name.vehicle_system_instance(), 3);
+        assert_eq!(name.industry_group(), 2);
+        assert!(name.arbitrary_address_capable());
+    }
+
+    #[test]
+    fn truncates_fields() {
+        let mut name = J1939Name::default();
+        name.set_ecu_instance(0xFF);
+        assert_eq!(name.ecu_instance(), 0x07);
+        assert_eq!(name.function_instance(), 0);
+    }
+
+    #[test]
+    fn lower_name_wins() {
+        assert!(J1939Name::from_raw(0x10) < J1939Name::from_raw(0x20));
+    }
+}
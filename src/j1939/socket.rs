@@ -0,0 +1,311 @@
+use std::task::{Context, Poll};
+
+use crate::{
+    socket::{CanInterface, CanSocket},
+    sockopt,
+};
+
+use super::J1939Name;
+
+/// Address of a J1939 endpoint
+///
+/// Depending on the use, not all parts of the address must be set. Unused
+/// parts are set to `J1939_NO_NAME`, `J1939_NO_PGN` and `J1939_NO_ADDR`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct J1939Address {
+    /// NAME of the ECU, `0` if not used
+    pub name: J1939Name,
+    /// Parameter group number
+    pub pgn: u32,
+    /// Source or destination address
+    pub addr: u8,
+}
+
+impl J1939Address {
+    pub fn new(name: impl Into<J1939Name>, pgn: u32, addr: u8) -> Self {
+        Self {
+            name: name.into(),
+            pgn,
+            addr,
+        }
+    }
+
+    /// Address that doesn't restrict the NAME, PGN or address
+    pub fn any() -> Self {
+        // J1939_NO_NAME is represented by the default NAME of zero
+        Self::new(
+            J1939Name::default(),
+            libc::J1939_NO_PGN,
+            libc::J1939_NO_ADDR,
+        )
+    }
+
+    /// Address of an ECU which is only identified by its source address
+    pub fn with_addr(addr: u8) -> Self {
+        Self {
+            addr,
+            ..Self::any()
+        }
+    }
+
+    /// Address for sending a parameter group to a destination address
+    ///
+    /// Use `J1939_NO_ADDR` as address to broadcast the parameter group.
+    pub fn with_pgn(pgn: u32, addr: u8) -> Self {
+        Self {
+            pgn,
+            addr,
+            ..Self::any()
+        }
+    }
+
+    fn to_can_addr(self) -> libc::__c_anonymous_sockaddr_can_can_addr {
+        let mut can_addr: libc::__c_anonymous_sockaddr_can_can_addr = unsafe { std::mem::zeroed() };
+        can_addr.j1939 = libc::__c_anonymous_sockaddr_can_j1939 {
+            name: self.name.as_raw(),
+            pgn: self.pgn,
+            addr: self.addr,
+        };
+
+        can_addr
+    }
+
+    fn from_can_addr(can_addr: &libc::__c_anonymous_sockaddr_can_can_addr) -> Self {
+        // UNSAFE: J1939 sockets always use the j1939 member of the union
+        let j1939 = unsafe { can_addr.j1939 };
+        Self::new(j1939.name, j1939.pgn, j1939.addr)
+    }
+}
+
+/// Metadata of a received J1939 message
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct J1939RecvInfo {
+    /// Address of the sender including the PGN of the message
+    pub source: J1939Address,
+    /// Destination address of the message (`J1939_NO_ADDR` for broadcasts)
+    pub dest_addr: Option<u8>,
+    /// Destination NAME, if the destination address is claimed
+    pub dest_name: Option<J1939Name>,
+    /// Priority of the message
+    pub priority: Option<u8>,
+}
+
+/// Socket for the SAE J1939 protocol (`CAN_J1939`)
+///
+/// The kernel handles the transport protocols (TP and ETP), so messages of up
+/// to 1785 bytes (TP) or several megabytes (ETP) can be sent and received in a
+/// single call. The receive buffer must be large enough for the expected
+/// messages.
+///
+/// The socket is bound to a local address. If the local address contains a
+/// NAME, the kernel resolves the source address using the address claims seen
+/// on the bus. Use [J1939Socket::send_address_claim()] to claim an address.
+///
+/// After [J1939Socket::connect()], messages can be exchanged with a single
+/// peer using [J1939Socket::send()] and [J1939Socket::recv()].
+pub struct J1939Socket {
+    socket: CanSocket,
+    can_if: CanInterface,
+    local: J1939Address,
+}
+
+impl J1939Socket {
+    /// Opens a J1939 socket bound to the local address
+    ///
+    /// The PGN of the local address is used as receive filter. Use
+    /// `J1939_NO_PGN` to receive all parameter groups.
+    pub fn open(can_if: &CanInterface, local: J1939Address) -> Result<Self, std::io::Error> {
+        let socket = CanSocket::create(libc::SOCK_DGRAM, libc::CAN_J1939)?;
+        socket.bind_address(can_if, local.to_can_addr())?;
+        socket.set_nonblocking()?;
+
+        Ok(Self {
+            socket,
+            can_if: *can_if,
+            local,
+        })
+    }
+
+    /// Returns the address the socket is bound to
+    pub fn local_address(&self) -> J1939Address {
+        self.local
+    }
+
+    /// Returns the underlying socket, e.g. to set further
+    /// [socket options](crate::sockopt)
+    pub fn socket(&self) -> &CanSocket {
+        &self.socket
+    }
+
+    /// Receives all messages on the bus, not only the ones sent to the socket
+    pub fn set_promiscuous(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        let enable = enable as libc::c_int;
+        self.socket.set_option(sockopt::SO_J1939_PROMISC, &enable)
+    }
+
+    /// Allows sending messages to the broadcast address
+    pub fn set_broadcast(&mut self, enable: bool) -> Result<(), std::io::Error> {
+        let enable = enable as libc::c_int;
+        self.socket.set_option(sockopt::SO_BROADCAST, &enable)
+    }
+
+    /// Sets the priority (0 to 7) of sent messages
+    pub fn set_send_priority(&mut self, priority: u8) -> Result<(), std::io::Error> {
+        let priority = priority as libc::c_int;
+        self.socket
+            .set_option(sockopt::SO_J1939_SEND_PRIO, &priority)
+    }
+
+    /// Connects the socket to a peer
+    ///
+    /// The destination is used for [J1939Socket::send()] and only messages
+    /// from the peer are received afterwards.
+    pub fn connect(&mut self, dest: J1939Address) -> Result<(), std::io::Error> {
+        self.socket
+            .connect_address(&self.can_if, dest.to_can_addr())
+    }
+
+    /// Sends a message to the connected peer
+    ///
+    /// See [J1939Socket::connect()].
+    pub async fn send(&mut self, data: &[u8]) -> Result<usize, std::io::Error> {
+        std::future::poll_fn(|cx| self.socket.poll_write_bytes(cx, data)).await
+    }
+
+    /// Receives the next message from the connected peer
+    ///
+    /// See [J1939Socket::connect()].
+    pub async fn recv(&mut self, buf: &mut [u8]) -> Result<usize, std::io::Error> {
+        std::future::poll_fn(|cx| self.socket.poll_read_bytes(cx, buf)).await
+    }
+
+    /// Sends a message to the destination
+    ///
+    /// The PGN of the destination is used for the message. Messages larger
+    /// than 8 bytes are sent using the transport protocols.
+    pub async fn send_to(
+        &mut self,
+        data: &[u8],
+        dest: J1939Address,
+    ) -> Result<usize, std::io::Error> {
+        let mut address: libc::sockaddr_can = unsafe { std::mem::zeroed() };
+        address.can_family = libc::AF_CAN as _;
+        address.can_ifindex = self.can_if.if_index() as _;
+        address.can_addr = dest.to_can_addr();
+
+        std::future::poll_fn(|cx| self.socket.poll_send_to(cx, data, &address)).await
+    }
+
+    /// Polls for the next message and its metadata
+    pub fn poll_recv_from(
+        &mut self,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<Result<(usize, J1939RecvInfo), std::io::Error>> {
+        let mut address: libc::sockaddr_can = unsafe { std::mem::zeroed() };
+
+        let result = self
+            .socket
+            .poll_recvmsg(cx, buf, Some(&mut address), |msg, bytes_read| {
+                let mut dest_addr = None;
+                let mut dest_name = None;
+                let mut priority = None;
+
+                // UNSAFE: The control buffer was filled by recvmsg
+                unsafe {
+                    let mut cmsg = libc::CMSG_FIRSTHDR(msg);
+                    while !cmsg.is_null() {
+                        let data = libc::CMSG_DATA(cmsg);
+                        if (*cmsg).cmsg_level == libc::SOL_CAN_J1939 {
+                            match (*cmsg).cmsg_type {
+                                libc::SCM_J1939_DEST_ADDR => dest_addr = Some(*data),
+                                libc::SCM_J1939_DEST_NAME => {
+                                    let name = std::ptr::read_unaligned(data as *const u64);
+                                    dest_name = Some(J1939Name::from_raw(name));
+                                }
+                                libc::SCM_J1939_PRIO => priority = Some(*data),
+                                _ => {}
+                            }
+                        }
+                        cmsg = libc::CMSG_NXTHDR(msg, cmsg);
+                    }
+                }
+
+                (bytes_read, dest_addr, dest_name, priority)
+            });
+
+        match result {
+            Poll::Ready(Ok((bytes_read, dest_addr, dest_name, priority))) => {
+                let info = J1939RecvInfo {
+                    source: J1939Address::from_can_addr(&address.can_addr),
+                    dest_addr,
+                    dest_name,
+                    priority,
+                };
+                Poll::Ready(Ok((bytes_read, info)))
+            }
+            Poll::Ready(Err(e)) => Poll::Ready(Err(e)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// Receives the next message and its metadata
+    pub async fn recv_from(
+        &mut self,
+        buf: &mut [u8],
+    ) -> Result<(usize, J1939RecvInfo), std::io::Error> {
+        std::future::poll_fn(|cx| self.poll_recv_from(cx, buf)).await
+    }
+
+    /// Binds the socket to the address and broadcasts an address claim
+    ///
+    /// Only the claim is sent, contention is left to the caller: if another
+    /// ECU with a lower NAME claims the same address within 250 ms, it wins
+    /// the address and a different address must be claimed. Such claims can
+    /// be received on a socket bound to `J1939_PGN_ADDRESS_CLAIMED`.
+    ///
+    /// The broadcast flag of the socket is restored after sending.
+    pub async fn send_address_claim(&mut self, addr: u8) -> Result<(), std::io::Error> {
+        if self.local.name == J1939Name::default() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "The socket must be bound to a NAME to claim an address",
+            ));
+        }
+
+        let local = J1939Address { addr, ..self.local };
+        self.socket
+            .bind_address(&self.can_if, local.to_can_addr())?;
+        self.local = local;
+
+        let broadcast = self.socket.option(sockopt::SO_BROADCAST)? != 0;
+        self.set_broadcast(true)?;
+        let claim = self.local.name.as_raw().to_le_bytes();
+        let dest = J1939Address::with_pgn(libc::J1939_PGN_ADDRESS_CLAIMED, libc::J1939_NO_ADDR);
+        let result = self.send_to(&claim, dest).await;
+        self.set_broadcast(broadcast)?;
+
+        result.map(|_| ())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::J1939Address;
+
+    #[test]
+    fn converts_address() {
+        let address = J1939Address::new(0x1122334455667788, 0xFECA, 0x80);
+        let can_addr = address.to_can_addr();
+        assert_eq!(J1939Address::from_can_addr(&can_addr), address);
+    }
+
+    #[test]
+    fn creates_any_address() {
+        let address = J1939Address::any();
+        assert_eq!(address.name.as_raw(), 0);
+        assert_eq!(address.pgn, libc::J1939_NO_PGN);
+        assert_eq!(address.addr, libc::J1939_NO_ADDR);
+        assert_eq!(J1939Address::with_addr(0x20).pgn, libc::J1939_NO_PGN);
+    }
+}
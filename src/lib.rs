@@ -5,21 +5,38 @@ different CAN interfaces litke the PCAN interfaces form Peak Symstes, the PiCan
 shields for a Raspberry Pi or any other socketcan compatible interface.
 For more information about socketcan, visit the [Linux Kernel documentation](
 https://www.kernel.org/doc/html/latest/networking/can.html).
-
+*/
+#![cfg_attr(
+    any(feature = "tokio", feature = "async-io"),
+    doc = r#"
  * [CanBus] allows you to receive and send raw CAN frames.
+ * [ResilientCanBus] reopens itself when the interface is lost (e.g., an USB
+   adapter is unplugged).
  * [IsotpConnection] allows you to send and receive large payloads.
+ * [CanBcm] allows you to transmit cyclic frames and monitor received frames
+   using the broadcast manager of the kernel.
+ * [J1939Socket] allows you to communicate using the SAE J1939 protocol.
  * [UdsClient](crate::uds::UdsClient) allows you to access the diagnostics
    interface on automotive ECUs
+ * [CanLinkMonitor] reports added and removed interfaces and state changes
+   of the CAN controllers."#
+)]
+/*!
+ * [Dbc](crate::dbc::Dbc) decodes and encodes the signals of frames described
+   by a DBC database.
 
-DDose currently is build for the use with the async Tokio Runtime. There is no
-plan to support sync environments but if you need it, feel free to open a pull
-request!
+DDose is build for the use with the async Tokio Runtime by default. To use it
+with smol or other executors based on `async-io`, disable the default features
+and enable the `async-io` feature instead. For synchronous applications, the
+`blocking` feature provides blocking versions of `CanBus`, `IsotpConnection`
+and `UdsClient` in the `blocking` module. Without the `tokio` and `async-io`
+features, only the blocking API is built and no async runtime is required.
 
 # Examples
 In order to access the CAN bus, you first need to define which interface you
 want to access. You can either access the interface by its name or by its index.
 
-```rust
+```rust,no_run
 use ddose::CanInterface;
 
 // Interface by name (e.g., vcan0, can0, socan0, ...)
@@ -29,20 +46,29 @@ let can_if = CanInterface::try_from(4).unwrap();
 ```
 
 The send and receive raw CAN frames, you can use the `CanBus`.
-```rust
+```rust,no_run
+# use ddose::CanInterface;
 use ddose::CanBus;
+# #[tokio::main]
+# async fn main() {
+# let can_if = CanInterface::try_from("can0").unwrap();
 let mut can_bus = CanBus::open(&can_if).unwrap();
 
 // Read from the CAN bus
 let frame = can_bus.read().await.unwrap();
 
 // Write to the CAN bus
-let can_bus.write(&frame).await.unwrap();
+can_bus.write(&frame).await.unwrap();
+# }
 ```
 
 To send large payloads using ISOTP, you can use the `IsotpConnection`.
-```rust
+```rust,no_run
+# use ddose::CanInterface;
 use ddose::IsotpConnection;
+# #[tokio::main]
+# async fn main() {
+# let can_if = CanInterface::try_from("can0").unwrap();
 let rx_id = embedded_hal::can::StandardId::new(0x100).unwrap();
 let tx_id = embedded_hal::can::StandardId::new(0x101).unwrap();
 let mut isotp_conn = IsotpConnection::open(&can_if, tx_id, rx_id).unwrap();
@@ -53,16 +79,31 @@ let bytes_read = isotp_conn.read(&mut buffer).await.unwrap();
 let payload = &buffer[..bytes_read];
 
 // Echo back the received data
-let _bytes_written = isotp_conn.write(&buffer).await.unwrap();
+let _bytes_written = isotp_conn.write(payload).await.unwrap();
+# }
 ```
 */
 
+#[cfg(not(any(feature = "tokio", feature = "async-io", feature = "blocking")))]
+compile_error!("Either the `tokio`, the `async-io` or the `blocking` feature must be enabled");
+
 mod can;
 mod isotp;
+mod j1939;
+mod netlink;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+mod runtime;
 mod socket;
 
+#[cfg(feature = "blocking")]
+pub mod blocking;
+pub mod dbc;
+pub mod sockopt;
 pub mod uds;
 
 pub use can::*;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 pub use isotp::*;
+pub use j1939::*;
+pub use netlink::*;
 pub use socket::*;
@@ -0,0 +1,429 @@
+use crate::socket::CanInterface;
+
+use super::socket::{
+    put_attribute, put_nested, read_struct, struct_bytes, Attributes, NetlinkSocket,
+};
+
+/// Bit timing of a CAN controller
+///
+/// When setting the bit timing, either only the bitrate and optionally the
+/// sample point are set and the kernel calculates the remaining fields, or
+/// all fields except the bitrate and sample point are set.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CanBitTiming {
+    /// Bitrate in bits per second
+    pub bitrate: u32,
+    /// Sample point in tenths of a percent (e.g., `875` for 87.5 %)
+    pub sample_point: u32,
+    /// Time quantum in nanoseconds
+    pub tq: u32,
+    /// Propagation segment in time quanta
+    pub prop_seg: u32,
+    /// Phase buffer segment 1 in time quanta
+    pub phase_seg1: u32,
+    /// Phase buffer segment 2 in time quanta
+    pub phase_seg2: u32,
+    /// Synchronisation jump width in time quanta
+    pub sjw: u32,
+    /// Bitrate prescaler
+    pub brp: u32,
+}
+
+impl CanBitTiming {
+    /// Bit timing with only the bitrate and sample point set
+    ///
+    /// Without a sample point, the kernel uses the CiA recommendation.
+    pub fn from_bitrate(bitrate: u32, sample_point: Option<u32>) -> Self {
+        Self {
+            bitrate,
+            sample_point: sample_point.unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
+    fn from_inner(bittiming: libc::can_bittiming) -> Self {
+        Self {
+            bitrate: bittiming.bitrate,
+            sample_point: bittiming.sample_point,
+            tq: bittiming.tq,
+            prop_seg: bittiming.prop_seg,
+            phase_seg1: bittiming.phase_seg1,
+            phase_seg2: bittiming.phase_seg2,
+            sjw: bittiming.sjw,
+            brp: bittiming.brp,
+        }
+    }
+
+    fn to_inner(self) -> libc::can_bittiming {
+        libc::can_bittiming {
+            bitrate: self.bitrate,
+            sample_point: self.sample_point,
+            tq: self.tq,
+            prop_seg: self.prop_seg,
+            phase_seg1: self.phase_seg1,
+            phase_seg2: self.phase_seg2,
+            sjw: self.sjw,
+            brp: self.brp,
+        }
+    }
+}
+
+/// Bit timing limits of a CAN controller
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanBitTimingConst {
+    /// Name of the controller
+    pub name: String,
+    pub tseg1_min: u32,
+    pub tseg1_max: u32,
+    pub tseg2_min: u32,
+    pub tseg2_max: u32,
+    pub sjw_max: u32,
+    pub brp_min: u32,
+    pub brp_max: u32,
+    pub brp_inc: u32,
+}
+
+impl CanBitTimingConst {
+    fn from_inner(bittiming_const: libc::can_bittiming_const) -> Self {
+        let name: Vec<u8> = bittiming_const
+            .name
+            .iter()
+            .take_while(|c| **c != 0)
+            .map(|c| *c as u8)
+            .collect();
+
+        Self {
+            name: String::from_utf8_lossy(&name).into_owned(),
+            tseg1_min: bittiming_const.tseg1_min,
+            tseg1_max: bittiming_const.tseg1_max,
+            tseg2_min: bittiming_const.tseg2_min,
+            tseg2_max: bittiming_const.tseg2_max,
+            sjw_max: bittiming_const.sjw_max,
+            brp_min: bittiming_const.brp_min,
+            brp_max: bittiming_const.brp_max,
+            brp_inc: bittiming_const.brp_inc,
+        }
+    }
+}
+
+/// Control mode flags of a CAN controller (`CAN_CTRLMODE_*`)
+///
+/// Flags can be combined using `|`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CanCtrlMode(u32);
+
+impl CanCtrlMode {
+    /// Frames sent are received back by the controller
+    pub const LOOPBACK: Self = Self(libc::CAN_CTRLMODE_LOOPBACK);
+    /// The controller only listens and doesn't acknowledge frames
+    pub const LISTEN_ONLY: Self = Self(libc::CAN_CTRLMODE_LISTENONLY);
+    /// The bus is sampled three times per bit
+    pub const TRIPLE_SAMPLING: Self = Self(libc::CAN_CTRLMODE_3_SAMPLES);
+    /// Frames are not retransmitted on errors
+    pub const ONE_SHOT: Self = Self(libc::CAN_CTRLMODE_ONE_SHOT);
+    /// Bus errors are reported as error frames
+    pub const BERR_REPORTING: Self = Self(libc::CAN_CTRLMODE_BERR_REPORTING);
+    /// CAN FD mode
+    pub const FD: Self = Self(libc::CAN_CTRLMODE_FD);
+    /// Missing acknowledgements are ignored
+    pub const PRESUME_ACK: Self = Self(libc::CAN_CTRLMODE_PRESUME_ACK);
+    /// CAN FD in the non-ISO (Bosch) variant
+    pub const FD_NON_ISO: Self = Self(libc::CAN_CTRLMODE_FD_NON_ISO);
+
+    pub fn from_bits(bits: u32) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Returns `true` if all flags of `other` are set
+    pub fn contains(&self, other: Self) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl std::ops::BitOr for CanCtrlMode {
+    type Output = Self;
+
+    fn bitor(self, rhs: Self) -> Self::Output {
+        Self(self.0 | rhs.0)
+    }
+}
+
+/// Configuration of a CAN controller read from the kernel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanConfig {
+    /// Bit timing of the arbitration phase
+    pub bit_timing: Option<CanBitTiming>,
+    /// Bit timing limits of the arbitration phase
+    pub bit_timing_const: Option<CanBitTimingConst>,
+    /// Bit timing of the data phase of CAN FD frames
+    pub data_bit_timing: Option<CanBitTiming>,
+    /// Bit timing limits of the data phase of CAN FD frames
+    pub data_bit_timing_const: Option<CanBitTimingConst>,
+    /// Frequency of the controller clock in Hz
+    pub clock_frequency: Option<u32>,
+    /// Enabled control modes
+    pub ctrl_mode: CanCtrlMode,
+    /// Control modes supported by the controller, if reported by the kernel
+    pub supported_ctrl_mode: Option<CanCtrlMode>,
+    /// Delay before restarting the controller after a bus-off in
+    /// milliseconds, `0` if disabled
+    pub restart_ms: u32,
+}
+
+/// Configuration of CAN interfaces via netlink
+///
+/// Changing the configuration requires the `CAP_NET_ADMIN` capability. Most
+/// settings can only be changed while the interface is down. Virtual
+/// interfaces like `vcan` don't have a bit timing.
+impl CanInterface {
+    /// Reads the configuration of the CAN controller
+    pub fn can_config(&self) -> Result<CanConfig, std::io::Error> {
+        let link = NetlinkSocket::open()?.get_link(self.if_index())?;
+        let data = link.can_data();
+
+        let ctrl_mode = data
+            .get(libc::IFLA_CAN_CTRLMODE as u16)
+            .map(read_struct::<libc::can_ctrlmode>);
+        let supported_ctrl_mode = data
+            .get(libc::IFLA_CAN_CTRLMODE_EXT as u16)
+            .and_then(|ext| Attributes::new(ext).get(libc::IFLA_CAN_CTRLMODE_SUPPORTED as u16))
+            .map(|supported| CanCtrlMode(read_struct::<u32>(supported)));
+
+        Ok(CanConfig {
+            bit_timing: data
+                .get(libc::IFLA_CAN_BITTIMING as u16)
+                .map(|data| CanBitTiming::from_inner(read_struct(data))),
+            bit_timing_const: data
+                .get(libc::IFLA_CAN_BITTIMING_CONST as u16)
+                .map(|data| CanBitTimingConst::from_inner(read_struct(data))),
+            data_bit_timing: data
+                .get(libc::IFLA_CAN_DATA_BITTIMING as u16)
+                .map(|data| CanBitTiming::from_inner(read_struct(data))),
+            data_bit_timing_const: data
+                .get(libc::IFLA_CAN_DATA_BITTIMING_CONST as u16)
+                .map(|data| CanBitTimingConst::from_inner(read_struct(data))),
+            clock_frequency: data
+                .get(libc::IFLA_CAN_CLOCK as u16)
+                .map(|data| read_struct::<libc::can_clock>(data).freq),
+            ctrl_mode: CanCtrlMode(ctrl_mode.map(|mode| mode.flags).unwrap_or_default()),
+            supported_ctrl_mode,
+            restart_ms: data
+                .get(libc::IFLA_CAN_RESTART_MS as u16)
+                .map(read_struct::<u32>)
+                .unwrap_or_default(),
+        })
+    }
+
+    /// Changes the `IFLA_CAN_*` attributes written by the closure
+    fn set_can_data(&self, f: impl FnOnce(&mut Vec<u8>)) -> Result<(), std::io::Error> {
+        let mut attributes = Vec::new();
+        put_nested(&mut attributes, libc::IFLA_LINKINFO, |buf| {
+            put_attribute(buf, libc::IFLA_INFO_KIND, b"can");
+            put_nested(buf, libc::IFLA_INFO_DATA, f);
+        });
+
+        NetlinkSocket::open()?.set_link(self.if_index(), 0, 0, &attributes)
+    }
+
+    /// Sets the bitrate and optionally the sample point
+    ///
+    /// The sample point is given in tenths of a percent (e.g., `875`).
+    pub fn set_bitrate(
+        &self,
+        bitrate: u32,
+        sample_point: Option<u32>,
+    ) -> Result<(), std::io::Error> {
+        self.set_bit_timing(&CanBitTiming::from_bitrate(bitrate, sample_point))
+    }
+
+    /// Sets the bit timing of the arbitration phase
+    pub fn set_bit_timing(&self, bit_timing: &CanBitTiming) -> Result<(), std::io::Error> {
+        let bit_timing = bit_timing.to_inner();
+        self.set_can_data(|buf| {
+            put_attribute(
+                buf,
+                libc::IFLA_CAN_BITTIMING as u16,
+                struct_bytes(&bit_timing),
+            );
+        })
+    }
+
+    /// Sets the data bitrate and optionally the sample point for CAN FD
+    ///
+    /// CAN FD mode is enabled as well.
+    pub fn set_data_bitrate(
+        &self,
+        bitrate: u32,
+        sample_point: Option<u32>,
+    ) -> Result<(), std::io::Error> {
+        self.set_data_bit_timing(&CanBitTiming::from_bitrate(bitrate, sample_point))
+    }
+
+    /// Sets the bit timing of the data phase and enables CAN FD mode
+    pub fn set_data_bit_timing(&self, bit_timing: &CanBitTiming) -> Result<(), std::io::Error> {
+        let bit_timing = bit_timing.to_inner();
+        let ctrl_mode = libc::can_ctrlmode {
+            mask: libc::CAN_CTRLMODE_FD,
+            flags: libc::CAN_CTRLMODE_FD,
+        };
+        self.set_can_data(|buf| {
+            put_attribute(
+                buf,
+                libc::IFLA_CAN_DATA_BITTIMING as u16,
+                struct_bytes(&bit_timing),
+            );
+            put_attribute(
+                buf,
+                libc::IFLA_CAN_CTRLMODE as u16,
+                struct_bytes(&ctrl_mode),
+            );
+        })
+    }
+
+    /// Enables or disables the control modes
+    ///
+    /// Modes which are not part of `mode` are left unchanged.
+    pub fn set_ctrl_mode(&self, mode: CanCtrlMode, enable: bool) -> Result<(), std::io::Error> {
+        let ctrl_mode = libc::can_ctrlmode {
+            mask: mode.0,
+            flags: if enable { mode.0 } else { 0 },
+        };
+        self.set_can_data(|buf| {
+            put_attribute(
+                buf,
+                libc::IFLA_CAN_CTRLMODE as u16,
+                struct_bytes(&ctrl_mode),
+            );
+        })
+    }
+
+    /// Enables or disables the listen-only mode
+    pub fn set_listen_only(&self, enable: bool) -> Result<(), std::io::Error> {
+        self.set_ctrl_mode(CanCtrlMode::LISTEN_ONLY, enable)
+    }
+
+    /// Enables or disables the loopback mode of the controller
+    pub fn set_loopback(&self, enable: bool) -> Result<(), std::io::Error> {
+        self.set_ctrl_mode(CanCtrlMode::LOOPBACK, enable)
+    }
+
+    /// Enables or disables triple sampling
+    pub fn set_triple_sampling(&self, enable: bool) -> Result<(), std::io::Error> {
+        self.set_ctrl_mode(CanCtrlMode::TRIPLE_SAMPLING, enable)
+    }
+
+    /// Sets the delay for automatically restarting the controller after a
+    /// bus-off
+    ///
+    /// A delay of `0` disables the automatic restart.
+    pub fn set_restart_ms(&self, restart_ms: u32) -> Result<(), std::io::Error> {
+        self.set_can_data(|buf| {
+            put_attribute(
+                buf,
+                libc::IFLA_CAN_RESTART_MS as u16,
+                &restart_ms.to_ne_bytes(),
+            );
+        })
+    }
+
+    /// Restarts the controller after a bus-off
+    ///
+    /// The kernel only allows a manual restart while the controller is in
+    /// the bus-off state and the automatic restart is disabled.
+    pub fn restart(&self) -> Result<(), std::io::Error> {
+        self.set_can_data(|buf| {
+            put_attribute(buf, libc::IFLA_CAN_RESTART as u16, &1u32.to_ne_bytes());
+        })
+    }
+
+    /// Returns `true` if the interface is administratively up
+    pub fn is_up(&self) -> Result<bool, std::io::Error> {
+        let link = NetlinkSocket::open()?.get_link(self.if_index())?;
+        Ok(link.header.ifi_flags & libc::IFF_UP as libc::c_uint != 0)
+    }
+
+    /// Brings the interface up or down
+    pub fn set_up(&self, up: bool) -> Result<(), std::io::Error> {
+        let flags = if up { libc::IFF_UP as u32 } else { 0 };
+        NetlinkSocket::open()?.set_link(self.if_index(), flags, libc::IFF_UP as u32, &[])
+    }
+
+    /// Returns the length of the transmit queue in frames (`txqueuelen`)
+    pub fn tx_queue_len(&self) -> Result<u32, std::io::Error> {
+        let link = NetlinkSocket::open()?.get_link(self.if_index())?;
+        link.attributes()
+            .get(libc::IFLA_TXQLEN)
+            .map(read_struct)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Received no transmit queue length",
+                )
+            })
+    }
+
+    /// Sets the length of the transmit queue in frames (`txqueuelen`)
+    ///
+    /// Writes fail with `ENOBUFS` when the queue is full, so a longer queue
+    /// absorbs longer bursts of frames. See [TxQueuePolicy](crate::TxQueuePolicy).
+    pub fn set_tx_queue_len(&self, len: u32) -> Result<(), std::io::Error> {
+        let mut attributes = Vec::new();
+        put_attribute(&mut attributes, libc::IFLA_TXQLEN, &len.to_ne_bytes());
+        NetlinkSocket::open()?.set_link(self.if_index(), 0, 0, &attributes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CanBitTiming, CanBitTimingConst, CanCtrlMode};
+
+    #[test]
+    fn converts_bit_timing() {
+        let bit_timing = CanBitTiming {
+            bitrate: 500_000,
+            sample_point: 875,
+            tq: 125,
+            prop_seg: 6,
+            phase_seg1: 7,
+            phase_seg2: 2,
+            sjw: 1,
+            brp: 10,
+        };
+        assert_eq!(CanBitTiming::from_inner(bit_timing.to_inner()), bit_timing);
+        assert_eq!(
+            CanBitTiming::from_bitrate(250_000, None),
+            CanBitTiming {
+                bitrate: 250_000,
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn converts_bit_timing_const() {
+        let mut bittiming_const: libc::can_bittiming_const = unsafe { std::mem::zeroed() };
+        for (c, name) in bittiming_const.name.iter_mut().zip(b"mcp251x") {
+            *c = *name as libc::c_char;
+        }
+        bittiming_const.brp_max = 64;
+
+        let bittiming_const = CanBitTimingConst::from_inner(bittiming_const);
+        assert_eq!(bittiming_const.name, "mcp251x");
+        assert_eq!(bittiming_const.brp_max, 64);
+    }
+
+    #[test]
+    fn combines_ctrl_modes() {
+        let mode = CanCtrlMode::LISTEN_ONLY | CanCtrlMode::FD;
+        assert!(mode.contains(CanCtrlMode::FD));
+        assert!(!mode.contains(CanCtrlMode::LOOPBACK));
+        assert_eq!(
+            mode.bits(),
+            libc::CAN_CTRLMODE_LISTENONLY | libc::CAN_CTRLMODE_FD
+        );
+    }
+}
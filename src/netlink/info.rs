@@ -0,0 +1,234 @@
+use crate::socket::CanInterface;
+
+use super::socket::{read_struct, LinkMessage, NetlinkSocket};
+
+/// Kind of a CAN interface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CanLinkKind {
+    /// CAN controller driven by the kernel (`can`)
+    Can,
+    /// Virtual CAN interface (`vcan`)
+    Vcan,
+    /// Virtual CAN tunnel between network namespaces (`vxcan`)
+    Vxcan,
+    /// Serial line CAN interface (`slcan`)
+    Slcan,
+    /// Other kind of CAN interface
+    Other(String),
+}
+
+impl CanLinkKind {
+    fn from_link(link: &LinkMessage) -> Self {
+        // Serial line interfaces either have no kind or the kind of CAN
+        // controllers, so they are recognized by their name
+        if link.name().is_some_and(|name| name.starts_with("slcan")) {
+            return Self::Slcan;
+        }
+
+        match link.kind() {
+            None | Some("can") => Self::Can,
+            Some("vcan") => Self::Vcan,
+            Some("vxcan") => Self::Vxcan,
+            Some(kind) => Self::Other(kind.to_string()),
+        }
+    }
+}
+
+/// Operational state of an interface as defined in RFC 2863
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperState {
+    Unknown,
+    NotPresent,
+    Down,
+    LowerLayerDown,
+    Testing,
+    Dormant,
+    Up,
+}
+
+impl From<u8> for OperState {
+    fn from(state: u8) -> Self {
+        match state as libc::c_int {
+            libc::IF_OPER_NOTPRESENT => Self::NotPresent,
+            libc::IF_OPER_DOWN => Self::Down,
+            libc::IF_OPER_LOWERLAYERDOWN => Self::LowerLayerDown,
+            libc::IF_OPER_TESTING => Self::Testing,
+            libc::IF_OPER_DORMANT => Self::Dormant,
+            libc::IF_OPER_UP => Self::Up,
+            _ => Self::Unknown,
+        }
+    }
+}
+
+/// Description of a CAN interface on the host
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CanInterfaceInfo {
+    pub interface: CanInterface,
+    /// Name of the interface (e.g., `can0`)
+    pub name: String,
+    pub kind: CanLinkKind,
+    /// Maximum transmission unit, which tells the supported frame formats
+    pub mtu: u32,
+    /// `true` if the interface is administratively up
+    pub is_up: bool,
+    pub oper_state: OperState,
+    /// Name of the driver (e.g., `gs_usb` or `vcan`)
+    pub driver: Option<String>,
+}
+
+impl CanInterfaceInfo {
+    /// Returns `None` if the link is not a CAN interface
+    pub(super) fn from_link(link: &LinkMessage) -> Option<Self> {
+        if !link.is_can() {
+            return None;
+        }
+
+        let attributes = link.attributes();
+        let name = link.name().unwrap_or_default().to_string();
+        Some(Self {
+            interface: CanInterface::from_index_unchecked(link.header.ifi_index as _),
+            kind: CanLinkKind::from_link(link),
+            mtu: attributes
+                .get(libc::IFLA_MTU)
+                .map(read_struct::<u32>)
+                .unwrap_or_default(),
+            is_up: link.header.ifi_flags & libc::IFF_UP as libc::c_uint != 0,
+            oper_state: attributes
+                .get(libc::IFLA_OPERSTATE)
+                .map(|state| OperState::from(read_struct::<u8>(state)))
+                .unwrap_or(OperState::Unknown),
+            driver: driver(&name).or_else(|| link.kind().map(str::to_string)),
+            name,
+        })
+    }
+
+    /// Returns `true` if the interface can transmit CAN FD frames
+    pub fn supports_fd(&self) -> bool {
+        self.mtu as usize >= libc::CANFD_MTU
+    }
+
+    /// Returns `true` if the interface can transmit CAN XL frames
+    pub fn supports_xl(&self) -> bool {
+        self.mtu as usize >= libc::CANXL_MIN_MTU
+    }
+}
+
+/// Returns the driver of the device behind the interface from sysfs
+///
+/// Virtual interfaces don't have a device.
+fn driver(name: &str) -> Option<String> {
+    if name.is_empty() {
+        return None;
+    }
+
+    let driver = std::fs::read_link(format!("/sys/class/net/{}/device/driver", name)).ok()?;
+    driver
+        .file_name()
+        .map(|driver| driver.to_string_lossy().into_owned())
+}
+
+/// Returns an error if the link is not a CAN interface
+pub(crate) fn check_can_link(if_index: libc::c_uint) -> Result<(), std::io::Error> {
+    let link = NetlinkSocket::open()?.get_link(if_index)?;
+    if !link.is_can() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "Interface '{}' is not a CAN interface",
+                link.name().unwrap_or_default()
+            ),
+        ));
+    }
+
+    Ok(())
+}
+
+impl CanInterface {
+    /// Lists all CAN interfaces of the host
+    pub fn list() -> Result<Vec<CanInterfaceInfo>, std::io::Error> {
+        let links = NetlinkSocket::open()?.get_links()?;
+        Ok(links
+            .iter()
+            .filter_map(CanInterfaceInfo::from_link)
+            .collect())
+    }
+
+    /// Returns the description of the interface
+    pub fn info(&self) -> Result<CanInterfaceInfo, std::io::Error> {
+        let link = NetlinkSocket::open()?.get_link(self.if_index())?;
+        CanInterfaceInfo::from_link(&link).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Interface is not a CAN interface",
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CanInterfaceInfo, CanLinkKind, OperState};
+    use crate::netlink::socket::{link_request, put_attribute, put_nested, LinkMessage};
+
+    fn link(if_type: u16, name: &str, kind: Option<&str>, mtu: u32) -> LinkMessage {
+        let mut payload = link_request(7, libc::IFF_UP as u32, 0);
+        payload[2..4].copy_from_slice(&if_type.to_ne_bytes());
+
+        put_attribute(
+            &mut payload,
+            libc::IFLA_IFNAME,
+            format!("{}\0", name).as_bytes(),
+        );
+        put_attribute(&mut payload, libc::IFLA_MTU, &mtu.to_ne_bytes());
+        put_attribute(
+            &mut payload,
+            libc::IFLA_OPERSTATE,
+            &[libc::IF_OPER_UP as u8],
+        );
+        if let Some(kind) = kind {
+            put_nested(&mut payload, libc::IFLA_LINKINFO, |buf| {
+                put_attribute(buf, libc::IFLA_INFO_KIND, kind.as_bytes());
+            });
+        }
+
+        LinkMessage::parse(payload).unwrap()
+    }
+
+    #[test]
+    fn describes_can_links() {
+        let info = CanInterfaceInfo::from_link(&link(
+            libc::ARPHRD_CAN,
+            "vcan0",
+            Some("vcan"),
+            libc::CANFD_MTU as u32,
+        ))
+        .unwrap();
+
+        assert_eq!(info.interface.if_index(), 7);
+        assert_eq!(info.name, "vcan0");
+        assert_eq!(info.kind, CanLinkKind::Vcan);
+        assert!(info.is_up);
+        assert_eq!(info.oper_state, OperState::Up);
+        assert_eq!(info.driver.as_deref(), Some("vcan"));
+        assert!(info.supports_fd());
+        assert!(!info.supports_xl());
+    }
+
+    #[test]
+    fn detects_link_kinds() {
+        let info = |name, kind| {
+            CanInterfaceInfo::from_link(&link(libc::ARPHRD_CAN, name, kind, 16))
+                .unwrap()
+                .kind
+        };
+        assert_eq!(info("can0", Some("can")), CanLinkKind::Can);
+        assert_eq!(info("slcan0", None), CanLinkKind::Slcan);
+        assert_eq!(info("vxcan1", Some("vxcan")), CanLinkKind::Vxcan);
+    }
+
+    #[test]
+    fn skips_other_links() {
+        let link = link(libc::ARPHRD_ETHER, "eth0", None, 1500);
+        assert!(CanInterfaceInfo::from_link(&link).is_none());
+    }
+}
@@ -0,0 +1,12 @@
+mod config;
+mod info;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+mod monitor;
+mod socket;
+mod stats;
+
+pub use config::*;
+pub use info::*;
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub use monitor::*;
+pub use stats::*;
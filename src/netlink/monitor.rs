@@ -0,0 +1,434 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::runtime::AsyncFd;
+
+use crate::socket::CanInterface;
+
+use super::{
+    info::CanInterfaceInfo,
+    socket::{split_messages, LinkMessage, NetlinkSocket, RECV_BUFFER_SIZE},
+    stats::{CanState, CanStats},
+};
+
+/// Change of a CAN interface reported by the kernel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CanLinkEvent {
+    /// The interface was created (e.g., a USB adapter was plugged in)
+    Added(CanInterfaceInfo),
+    /// The interface was removed (e.g., a USB adapter was unplugged)
+    Removed(CanInterface),
+    /// The interface was brought up
+    Up(CanInterface),
+    /// The interface was brought down
+    Down(CanInterface),
+    /// The state of the CAN controller changed
+    StateChanged {
+        interface: CanInterface,
+        state: CanState,
+    },
+}
+
+impl CanLinkEvent {
+    /// Returns the interface the event belongs to
+    pub fn interface(&self) -> CanInterface {
+        match self {
+            Self::Added(info) => info.interface,
+            Self::Removed(interface) | Self::Up(interface) | Self::Down(interface) => *interface,
+            Self::StateChanged { interface, .. } => *interface,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct LinkStatus {
+    is_up: bool,
+    state: Option<CanState>,
+}
+
+/// Remembers the status of the links to turn link messages into events
+#[derive(Debug, Default)]
+struct LinkTracker {
+    /// Only the link with this name is tracked if set
+    filter: Option<String>,
+    links: HashMap<libc::c_uint, LinkStatus>,
+}
+
+impl LinkTracker {
+    fn new(filter: Option<String>) -> Self {
+        Self {
+            filter,
+            links: HashMap::new(),
+        }
+    }
+
+    /// Queues the events for a `RTM_NEWLINK` or `RTM_DELLINK` message
+    fn update(&mut self, msg_type: u16, link: &LinkMessage, events: &mut VecDeque<CanLinkEvent>) {
+        let if_index = link.header.ifi_index as libc::c_uint;
+        let interface = CanInterface::from_index_unchecked(if_index);
+        let filtered = self
+            .filter
+            .as_deref()
+            .is_some_and(|filter| link.name() != Some(filter));
+        if !link.is_can() || filtered {
+            // A tracked link which was renamed no longer matches the filter
+            if self.links.remove(&if_index).is_some() {
+                events.push_back(CanLinkEvent::Removed(interface));
+            }
+            return;
+        }
+
+        if msg_type == libc::RTM_DELLINK {
+            if self.links.remove(&if_index).is_some() {
+                events.push_back(CanLinkEvent::Removed(interface));
+            }
+            return;
+        }
+
+        let status = LinkStatus {
+            is_up: link.header.ifi_flags & libc::IFF_UP as libc::c_uint != 0,
+            state: CanStats::from_link(link).state,
+        };
+        let Some(previous) = self.links.insert(if_index, status) else {
+            if let Some(info) = CanInterfaceInfo::from_link(link) {
+                events.push_back(CanLinkEvent::Added(info));
+            }
+            return;
+        };
+
+        if previous.is_up != status.is_up {
+            events.push_back(match status.is_up {
+                true => CanLinkEvent::Up(interface),
+                false => CanLinkEvent::Down(interface),
+            });
+        }
+        if let Some(state) = status.state.filter(|_| previous.state != status.state) {
+            events.push_back(CanLinkEvent::StateChanged { interface, state });
+        }
+    }
+
+    /// Queues the events for the differences to a dump of all links
+    fn sync(&mut self, links: &[LinkMessage], events: &mut VecDeque<CanLinkEvent>) {
+        let mut removed: Vec<_> = self.links.keys().copied().collect();
+        for link in links {
+            removed.retain(|if_index| *if_index != link.header.ifi_index as libc::c_uint);
+            self.update(libc::RTM_NEWLINK, link, events);
+        }
+
+        for if_index in removed {
+            self.links.remove(&if_index);
+            let interface = CanInterface::from_index_unchecked(if_index);
+            events.push_back(CanLinkEvent::Removed(interface));
+        }
+    }
+}
+
+/// Monitors CAN interfaces for changes reported by the kernel.
+///
+/// The monitor subscribes to the link notifications of the kernel and reports
+/// added and removed interfaces, interfaces brought up or down and state
+/// changes of the CAN controllers. The events are received with
+/// [CanLinkMonitor::read_event()] or by using the [CanLinkMonitor] as
+/// [Stream](futures_core::Stream).
+///
+/// The kernel notifies about state changes together with other changes of the
+/// link (e.g., the loss of the carrier when going bus-off). Transitions
+/// between the error states may therefore be reported late or not at all,
+/// use [CanInterface::state()] to poll the current state.
+///
+/// # Example:
+/// ```no_run
+/// # use ddose::{CanInterface, CanLinkEvent, CanLinkMonitor};
+/// # #[tokio::main] async fn main() -> Result<(), std::io::Error> {
+/// let can_if = CanInterface::try_from("can0")?;
+/// let mut monitor = CanLinkMonitor::open_interface(&can_if)?;
+/// monitor.set_restart_on_bus_off(true);
+///
+/// loop {
+///     match monitor.read_event().await? {
+///         CanLinkEvent::Removed(_) => break,
+///         event => println!("{:?}", event),
+///     }
+/// }
+/// # Ok(())
+/// # }
+/// ```
+pub struct CanLinkMonitor {
+    socket: AsyncFd<NetlinkSocket>,
+    tracker: LinkTracker,
+    events: VecDeque<CanLinkEvent>,
+    restart_on_bus_off: bool,
+    buffer: Vec<u8>,
+}
+
+impl CanLinkMonitor {
+    /// Monitors all CAN interfaces of the host
+    pub fn open() -> Result<Self, std::io::Error> {
+        Self::open_with(None)
+    }
+
+    /// Monitors a single CAN interface
+    ///
+    /// The interface is followed by its name, so it is reported as added
+    /// again when e.g. a USB adapter is plugged in again, although it gets a
+    /// new index.
+    pub fn open_interface(can_if: &CanInterface) -> Result<Self, std::io::Error> {
+        Self::open_with(Some(can_if.name()?))
+    }
+
+    /// Monitors the CAN interface with the name (e.g., `can0`)
+    ///
+    /// In contrast to [CanLinkMonitor::open_interface()], the interface
+    /// doesn't have to exist yet.
+    pub fn open_name(name: &str) -> Result<Self, std::io::Error> {
+        Self::open_with(Some(name.to_owned()))
+    }
+
+    fn open_with(filter: Option<String>) -> Result<Self, std::io::Error> {
+        let socket = NetlinkSocket::open_groups(libc::RTMGRP_LINK as u32)?;
+        socket.set_nonblocking()?;
+
+        // The current links are read after subscribing, so no change between
+        // both steps is missed. Existing links are not reported as added.
+        let mut tracker = LinkTracker::new(filter);
+        let links = NetlinkSocket::open()?.get_links()?;
+        tracker.sync(&links, &mut VecDeque::new());
+
+        Ok(Self {
+            socket: AsyncFd::new(socket)?,
+            tracker,
+            events: VecDeque::new(),
+            restart_on_bus_off: false,
+            buffer: vec![0; RECV_BUFFER_SIZE],
+        })
+    }
+
+    /// Restarts controllers through the kernel when they go bus-off
+    ///
+    /// The automatic restart of the kernel must be disabled (see
+    /// [CanInterface::set_restart_ms()]), otherwise the restart fails. A
+    /// failed restart is returned as error before the bus-off event.
+    pub fn set_restart_on_bus_off(&mut self, enable: bool) {
+        self.restart_on_bus_off = enable;
+    }
+
+    /// Polls for the next event
+    pub fn poll_read_event(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<CanLinkEvent, std::io::Error>> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Poll::Ready(Ok(event));
+            }
+
+            let buffer = &mut self.buffer;
+            let len = match self.socket.poll_read_with(cx, |socket| socket.recv(buffer)) {
+                Poll::Ready(Ok(len)) => len,
+                // Notifications were lost because the receive queue was full
+                Poll::Ready(Err(e)) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                    self.resync()?;
+                    continue;
+                }
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            };
+
+            let queued = self.events.len();
+            for (header, payload) in split_messages(&self.buffer[..len])? {
+                if let Some(link) = LinkMessage::parse(payload.to_vec()) {
+                    self.tracker
+                        .update(header.nlmsg_type, &link, &mut self.events);
+                }
+            }
+            self.restart_bus_off(queued)?;
+        }
+    }
+
+    /// Reads the next event
+    pub async fn read_event(&mut self) -> Result<CanLinkEvent, std::io::Error> {
+        std::future::poll_fn(|cx| self.poll_read_event(cx)).await
+    }
+
+    /// Reads all links to find the changes of lost notifications
+    fn resync(&mut self) -> Result<(), std::io::Error> {
+        let queued = self.events.len();
+        let links = NetlinkSocket::open()?.get_links()?;
+        self.tracker.sync(&links, &mut self.events);
+        self.restart_bus_off(queued)
+    }
+
+    /// Restarts the controllers which went bus-off in the newly queued events
+    fn restart_bus_off(&self, queued: usize) -> Result<(), std::io::Error> {
+        if !self.restart_on_bus_off {
+            return Ok(());
+        }
+
+        for event in self.events.iter().skip(queued) {
+            if let CanLinkEvent::StateChanged {
+                interface,
+                state: CanState::BusOff,
+            } = event
+            {
+                interface.restart()?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl futures_core::Stream for CanLinkMonitor {
+    type Item = Result<CanLinkEvent, std::io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll_read_event(cx).map(Some)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::VecDeque;
+
+    use super::{CanLinkEvent, LinkTracker};
+    use crate::{
+        netlink::{
+            socket::{link_request, put_attribute, put_nested, LinkMessage},
+            stats::CanState,
+        },
+        socket::CanInterface,
+    };
+
+    fn link(if_index: u32, if_type: u16, up: bool, state: u32) -> LinkMessage {
+        named_link(if_index, "can0", if_type, up, state)
+    }
+
+    fn named_link(if_index: u32, name: &str, if_type: u16, up: bool, state: u32) -> LinkMessage {
+        let flags = if up { libc::IFF_UP as u32 } else { 0 };
+        let mut payload = link_request(if_index, flags, 0);
+        payload[2..4].copy_from_slice(&if_type.to_ne_bytes());
+
+        put_attribute(
+            &mut payload,
+            libc::IFLA_IFNAME,
+            format!("{}\0", name).as_bytes(),
+        );
+        put_nested(&mut payload, libc::IFLA_LINKINFO, |buf| {
+            put_attribute(buf, libc::IFLA_INFO_KIND, b"can");
+            put_nested(buf, libc::IFLA_INFO_DATA, |buf| {
+                put_attribute(buf, libc::IFLA_CAN_STATE as u16, &state.to_ne_bytes());
+            });
+        });
+
+        LinkMessage::parse(payload).unwrap()
+    }
+
+    #[test]
+    fn reports_link_changes() {
+        let mut tracker = LinkTracker::new(None);
+        let mut events = VecDeque::new();
+        let can0 = CanInterface::from_index_unchecked(4);
+
+        let down = link(4, libc::ARPHRD_CAN, false, libc::CAN_STATE_STOPPED);
+        tracker.update(libc::RTM_NEWLINK, &down, &mut events);
+        assert!(
+            matches!(events.pop_front(), Some(CanLinkEvent::Added(info)) if info.interface == can0)
+        );
+
+        let up = link(4, libc::ARPHRD_CAN, true, libc::CAN_STATE_ERROR_ACTIVE);
+        tracker.update(libc::RTM_NEWLINK, &up, &mut events);
+        assert_eq!(events.pop_front(), Some(CanLinkEvent::Up(can0)));
+        assert_eq!(
+            events.pop_front(),
+            Some(CanLinkEvent::StateChanged {
+                interface: can0,
+                state: CanState::ErrorActive
+            })
+        );
+
+        // Messages without a change don't create events
+        tracker.update(libc::RTM_NEWLINK, &up, &mut events);
+        assert!(events.is_empty());
+
+        let bus_off = link(4, libc::ARPHRD_CAN, true, libc::CAN_STATE_BUS_OFF);
+        tracker.update(libc::RTM_NEWLINK, &bus_off, &mut events);
+        assert_eq!(
+            events.pop_front(),
+            Some(CanLinkEvent::StateChanged {
+                interface: can0,
+                state: CanState::BusOff
+            })
+        );
+
+        tracker.update(libc::RTM_DELLINK, &bus_off, &mut events);
+        assert_eq!(events.pop_front(), Some(CanLinkEvent::Removed(can0)));
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn filters_links() {
+        let mut tracker = LinkTracker::new(Some("can0".into()));
+        let mut events = VecDeque::new();
+
+        let other = named_link(5, "can1", libc::ARPHRD_CAN, true, 0);
+        tracker.update(libc::RTM_NEWLINK, &other, &mut events);
+        let ethernet = link(4, libc::ARPHRD_ETHER, true, libc::CAN_STATE_ERROR_ACTIVE);
+        tracker.update(libc::RTM_NEWLINK, &ethernet, &mut events);
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn follows_link_name() {
+        let mut tracker = LinkTracker::new(Some("can0".into()));
+        let mut events = VecDeque::new();
+
+        let plugged = link(4, libc::ARPHRD_CAN, true, libc::CAN_STATE_ERROR_ACTIVE);
+        tracker.update(libc::RTM_NEWLINK, &plugged, &mut events);
+        tracker.update(libc::RTM_DELLINK, &plugged, &mut events);
+        events.clear();
+
+        // The adapter gets a new index when it is plugged in again
+        let replugged = link(7, libc::ARPHRD_CAN, false, libc::CAN_STATE_STOPPED);
+        tracker.update(libc::RTM_NEWLINK, &replugged, &mut events);
+        let can0 = CanInterface::from_index_unchecked(7);
+        assert!(
+            matches!(events.pop_front(), Some(CanLinkEvent::Added(info)) if info.interface == can0)
+        );
+
+        let renamed = named_link(7, "can9", libc::ARPHRD_CAN, false, 0);
+        tracker.update(libc::RTM_NEWLINK, &renamed, &mut events);
+        assert_eq!(events.pop_front(), Some(CanLinkEvent::Removed(can0)));
+        assert!(events.is_empty());
+    }
+
+    #[test]
+    fn syncs_with_dump() {
+        let mut tracker = LinkTracker::new(None);
+        let mut events = VecDeque::new();
+        let links = [
+            link(4, libc::ARPHRD_CAN, true, libc::CAN_STATE_ERROR_ACTIVE),
+            link(5, libc::ARPHRD_CAN, true, libc::CAN_STATE_ERROR_ACTIVE),
+        ];
+        tracker.sync(&links, &mut events);
+        events.clear();
+
+        let links = [link(4, libc::ARPHRD_CAN, false, libc::CAN_STATE_STOPPED)];
+        tracker.sync(&links, &mut events);
+        let can0 = CanInterface::from_index_unchecked(4);
+        let can1 = CanInterface::from_index_unchecked(5);
+        assert_eq!(
+            Vec::from(events),
+            vec![
+                CanLinkEvent::Down(can0),
+                CanLinkEvent::StateChanged {
+                    interface: can0,
+                    state: CanState::Stopped
+                },
+                CanLinkEvent::Removed(can1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,420 @@
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+
+/// Alignment of netlink messages and attributes
+const ALIGNMENT: usize = 4;
+
+/// Flags in the type of an attribute, which are not part of the type
+const NLA_TYPE_MASK: u16 = 0x3FFF;
+
+pub(crate) const RECV_BUFFER_SIZE: usize = 64 * 1024;
+
+const HEADER_LEN: usize = std::mem::size_of::<libc::nlmsghdr>();
+
+fn align(len: usize) -> usize {
+    (len + ALIGNMENT - 1) & !(ALIGNMENT - 1)
+}
+
+/// Returns the bytes of a C struct
+pub(crate) fn struct_bytes<T: Copy>(value: &T) -> &[u8] {
+    // UNSAFE: The struct is plain old data and the slice covers its size
+    unsafe { std::slice::from_raw_parts(value as *const T as *const u8, std::mem::size_of::<T>()) }
+}
+
+/// Reads a C struct from the payload of an attribute
+///
+/// Older kernels may send shorter structs, the missing fields are zeroed.
+pub(crate) fn read_struct<T: Copy>(data: &[u8]) -> T {
+    // UNSAFE: The structs read from netlink are plain old data and can be zeroed
+    let mut value: T = unsafe { std::mem::zeroed() };
+    let len = data.len().min(std::mem::size_of::<T>());
+    unsafe {
+        std::ptr::copy_nonoverlapping(data.as_ptr(), &mut value as *mut T as *mut u8, len);
+    }
+    value
+}
+
+/// Appends an attribute to a netlink message
+pub(crate) fn put_attribute(buf: &mut Vec<u8>, kind: u16, data: &[u8]) {
+    let len = std::mem::size_of::<libc::rtattr>() + data.len();
+    buf.extend_from_slice(&(len as u16).to_ne_bytes());
+    buf.extend_from_slice(&kind.to_ne_bytes());
+    buf.extend_from_slice(data);
+    buf.resize(align(buf.len()), 0);
+}
+
+/// Appends an attribute containing the attributes written by the closure
+pub(crate) fn put_nested(buf: &mut Vec<u8>, kind: u16, f: impl FnOnce(&mut Vec<u8>)) {
+    let start = buf.len();
+    put_attribute(buf, kind, &[]);
+    f(buf);
+
+    let len = (buf.len() - start) as u16;
+    buf[start..start + 2].copy_from_slice(&len.to_ne_bytes());
+}
+
+/// Iterator over the attributes of a netlink message
+#[derive(Clone)]
+pub(crate) struct Attributes<'a>(&'a [u8]);
+
+impl<'a> Attributes<'a> {
+    pub(crate) fn new(data: &'a [u8]) -> Self {
+        Self(data)
+    }
+
+    /// Returns the payload of the first attribute of the type
+    pub(crate) fn get(&self, kind: u16) -> Option<&'a [u8]> {
+        self.clone()
+            .find(|(attr_kind, _)| *attr_kind == kind)
+            .map(|(_, data)| data)
+    }
+}
+
+impl<'a> Iterator for Attributes<'a> {
+    type Item = (u16, &'a [u8]);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        const HEADER_LEN: usize = std::mem::size_of::<libc::rtattr>();
+
+        if self.0.len() < HEADER_LEN {
+            return None;
+        }
+
+        let len = u16::from_ne_bytes([self.0[0], self.0[1]]) as usize;
+        let kind = u16::from_ne_bytes([self.0[2], self.0[3]]) & NLA_TYPE_MASK;
+        if len < HEADER_LEN || len > self.0.len() {
+            return None;
+        }
+
+        let data = &self.0[HEADER_LEN..len];
+        self.0 = &self.0[align(len).min(self.0.len())..];
+        Some((kind, data))
+    }
+}
+
+/// Link message (`RTM_NEWLINK`) received from the kernel
+pub(crate) struct LinkMessage {
+    pub(crate) header: libc::ifinfomsg,
+    payload: Vec<u8>,
+}
+
+impl LinkMessage {
+    pub(super) fn parse(payload: Vec<u8>) -> Option<Self> {
+        if payload.len() < std::mem::size_of::<libc::ifinfomsg>() {
+            return None;
+        }
+
+        Some(Self {
+            header: read_struct(&payload),
+            payload,
+        })
+    }
+
+    /// Returns the `IFLA_*` attributes of the link
+    pub(crate) fn attributes(&self) -> Attributes<'_> {
+        Attributes::new(&self.payload[align(std::mem::size_of::<libc::ifinfomsg>())..])
+    }
+
+    /// Returns the `IFLA_INFO_*` attributes of the link
+    pub(crate) fn link_info(&self) -> Attributes<'_> {
+        Attributes::new(
+            self.attributes()
+                .get(libc::IFLA_LINKINFO)
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Returns the name of the link
+    pub(crate) fn name(&self) -> Option<&str> {
+        let name = self.attributes().get(libc::IFLA_IFNAME)?;
+        std::str::from_utf8(name)
+            .ok()
+            .map(|name| name.trim_end_matches('\0'))
+    }
+
+    /// Returns the kind of the link (e.g., `can` or `vcan`)
+    pub(crate) fn kind(&self) -> Option<&str> {
+        let kind = self.link_info().get(libc::IFLA_INFO_KIND)?;
+        std::str::from_utf8(kind)
+            .ok()
+            .map(|kind| kind.trim_end_matches('\0'))
+    }
+
+    /// Returns `true` if the link is a CAN device
+    pub(crate) fn is_can(&self) -> bool {
+        self.header.ifi_type == libc::ARPHRD_CAN
+    }
+
+    /// Returns the `IFLA_CAN_*` attributes of a CAN link
+    pub(crate) fn can_data(&self) -> Attributes<'_> {
+        Attributes::new(
+            self.link_info()
+                .get(libc::IFLA_INFO_DATA)
+                .unwrap_or_default(),
+        )
+    }
+}
+
+/// Builds the payload of a link message for the interface
+pub(crate) fn link_request(if_index: libc::c_uint, flags: u32, change: u32) -> Vec<u8> {
+    let mut header: libc::ifinfomsg = unsafe { std::mem::zeroed() };
+    header.ifi_family = libc::AF_UNSPEC as _;
+    header.ifi_index = if_index as _;
+    header.ifi_flags = flags as _;
+    header.ifi_change = change as _;
+
+    struct_bytes(&header).to_vec()
+}
+
+/// Splits a received datagram into the headers and payloads of its messages
+pub(crate) fn split_messages(
+    mut data: &[u8],
+) -> Result<Vec<(libc::nlmsghdr, &[u8])>, std::io::Error> {
+    let mut messages = Vec::new();
+    while data.len() >= HEADER_LEN {
+        let header: libc::nlmsghdr = read_struct(data);
+        let len = header.nlmsg_len as usize;
+        if len < HEADER_LEN || len > data.len() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Received truncated netlink message",
+            ));
+        }
+        messages.push((header, &data[HEADER_LEN..len]));
+        data = &data[align(len).min(data.len())..];
+    }
+
+    Ok(messages)
+}
+
+/// Routing netlink socket for requests to the kernel
+///
+/// The requests are answered immediately by the kernel, so the socket is
+/// used in blocking mode. Sockets subscribed to multicast groups receive
+/// notifications and are used in non-blocking mode instead.
+pub(crate) struct NetlinkSocket {
+    fd: OwnedFd,
+    sequence: u32,
+}
+
+impl NetlinkSocket {
+    pub(crate) fn open() -> Result<Self, std::io::Error> {
+        Self::open_groups(0)
+    }
+
+    /// Opens a socket subscribed to the multicast groups (`RTMGRP_*`)
+    pub(crate) fn open_groups(groups: u32) -> Result<Self, std::io::Error> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                libc::NETLINK_ROUTE,
+            )
+        };
+        if fd.is_negative() {
+            return Err(std::io::Error::last_os_error());
+        }
+        // UNSAFE: The file descriptor was just created and is owned by us
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as _;
+        address.nl_groups = groups;
+        let ptr = &address as *const libc::sockaddr_nl;
+        let size = std::mem::size_of::<libc::sockaddr_nl>();
+        let ret = unsafe { libc::bind(fd.as_raw_fd(), ptr as _, size as _) };
+        if ret == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(Self { fd, sequence: 0 })
+    }
+
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub(crate) fn set_nonblocking(&self) -> Result<(), std::io::Error> {
+        let flags = unsafe { libc::fcntl(self.fd.as_raw_fd(), libc::F_GETFL) };
+        if flags == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let ret =
+            unsafe { libc::fcntl(self.fd.as_raw_fd(), libc::F_SETFL, flags | libc::O_NONBLOCK) };
+        if ret == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Receives a datagram of messages from the kernel
+    pub(crate) fn recv(&self, buffer: &mut [u8]) -> Result<usize, std::io::Error> {
+        let ret = unsafe {
+            libc::recv(
+                self.fd.as_raw_fd(),
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+                0,
+            )
+        };
+        if ret == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(ret as usize)
+    }
+
+    /// Sends a request and returns the payloads of the responses
+    ///
+    /// Requests are acknowledged by the kernel, errors in the
+    /// acknowledgement are returned as error.
+    pub(crate) fn request(
+        &mut self,
+        msg_type: u16,
+        flags: libc::c_int,
+        payload: &[u8],
+    ) -> Result<Vec<Vec<u8>>, std::io::Error> {
+        self.sequence = self.sequence.wrapping_add(1);
+        let header = libc::nlmsghdr {
+            nlmsg_len: (HEADER_LEN + payload.len()) as u32,
+            nlmsg_type: msg_type,
+            nlmsg_flags: (flags | libc::NLM_F_REQUEST | libc::NLM_F_ACK) as u16,
+            nlmsg_seq: self.sequence,
+            nlmsg_pid: 0,
+        };
+
+        let mut request = struct_bytes(&header).to_vec();
+        request.extend_from_slice(payload);
+        let ret = unsafe {
+            libc::send(
+                self.fd.as_raw_fd(),
+                request.as_ptr() as *const libc::c_void,
+                request.len(),
+                0,
+            )
+        };
+        if ret == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let mut responses = Vec::new();
+        let mut buffer = vec![0u8; RECV_BUFFER_SIZE];
+        loop {
+            let len = self.recv(&mut buffer)?;
+            for (header, payload) in split_messages(&buffer[..len])? {
+                // Skip responses to earlier requests
+                if header.nlmsg_seq != self.sequence {
+                    continue;
+                }
+
+                match header.nlmsg_type as libc::c_int {
+                    libc::NLMSG_DONE => return Ok(responses),
+                    libc::NLMSG_ERROR => {
+                        let error: libc::nlmsgerr = read_struct(payload);
+                        return match error.error {
+                            0 => Ok(responses),
+                            error => Err(std::io::Error::from_raw_os_error(-error)),
+                        };
+                    }
+                    _ => responses.push(payload.to_vec()),
+                }
+            }
+        }
+    }
+
+    /// Requests the link with the index
+    pub(crate) fn get_link(
+        &mut self,
+        if_index: libc::c_uint,
+    ) -> Result<LinkMessage, std::io::Error> {
+        let payload = link_request(if_index, 0, 0);
+        self.request(libc::RTM_GETLINK, 0, &payload)?
+            .into_iter()
+            .find_map(LinkMessage::parse)
+            .ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "Received no link information",
+                )
+            })
+    }
+
+    /// Requests all links of the system
+    pub(crate) fn get_links(&mut self) -> Result<Vec<LinkMessage>, std::io::Error> {
+        let payload = link_request(0, 0, 0);
+        Ok(self
+            .request(libc::RTM_GETLINK, libc::NLM_F_DUMP, &payload)?
+            .into_iter()
+            .filter_map(LinkMessage::parse)
+            .collect())
+    }
+
+    /// Changes the link with the index
+    ///
+    /// The attributes are appended to the link message.
+    pub(crate) fn set_link(
+        &mut self,
+        if_index: libc::c_uint,
+        flags: u32,
+        change: u32,
+        attributes: &[u8],
+    ) -> Result<(), std::io::Error> {
+        let mut payload = link_request(if_index, flags, change);
+        payload.extend_from_slice(attributes);
+        self.request(libc::RTM_NEWLINK, 0, &payload)?;
+        Ok(())
+    }
+}
+
+impl AsRawFd for NetlinkSocket {
+    fn as_raw_fd(&self) -> std::os::fd::RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+impl AsFd for NetlinkSocket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{put_attribute, put_nested, read_struct, struct_bytes, Attributes};
+
+    #[test]
+    fn serializes_attributes() {
+        let mut buf = Vec::new();
+        put_attribute(&mut buf, 3, b"can");
+        put_nested(&mut buf, 18, |buf| {
+            put_attribute(buf, 1, &7u32.to_ne_bytes());
+        });
+
+        assert_eq!(buf.len(), 8 + 4 + 8);
+        let attributes: Vec<_> = Attributes::new(&buf).collect();
+        assert_eq!(attributes.len(), 2);
+        assert_eq!(attributes[0], (3, &b"can"[..]));
+
+        let nested = Attributes::new(attributes[1].1);
+        assert_eq!(nested.get(1), Some(&7u32.to_ne_bytes()[..]));
+        assert_eq!(nested.get(2), None);
+    }
+
+    #[test]
+    fn stops_at_truncated_attributes() {
+        let mut buf = Vec::new();
+        put_attribute(&mut buf, 1, &[1, 2, 3, 4]);
+        buf.truncate(6);
+        assert_eq!(Attributes::new(&buf).count(), 0);
+    }
+
+    #[test]
+    fn reads_short_structs() {
+        let counter = libc::can_berr_counter {
+            txerr: 0x1234,
+            rxerr: 0x5678,
+        };
+        let bytes = struct_bytes(&counter);
+        let read: libc::can_berr_counter = read_struct(&bytes[..2]);
+        assert_eq!(read.txerr, 0x1234);
+        assert_eq!(read.rxerr, 0);
+    }
+}
@@ -0,0 +1,251 @@
+use crate::socket::CanInterface;
+
+use super::socket::{read_struct, LinkMessage, NetlinkSocket};
+
+/// State of a CAN controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CanState {
+    /// Both error counters are below 96
+    ErrorActive,
+    /// One of the error counters reached 96
+    ErrorWarning,
+    /// One of the error counters reached 128
+    ErrorPassive,
+    /// The transmit error counter reached 256, the controller is off the bus
+    BusOff,
+    /// The controller is stopped (e.g., the interface is down)
+    Stopped,
+    Sleeping,
+}
+
+impl CanState {
+    pub(crate) fn from_inner(state: u32) -> Option<Self> {
+        match state {
+            libc::CAN_STATE_ERROR_ACTIVE => Some(Self::ErrorActive),
+            libc::CAN_STATE_ERROR_WARNING => Some(Self::ErrorWarning),
+            libc::CAN_STATE_ERROR_PASSIVE => Some(Self::ErrorPassive),
+            libc::CAN_STATE_BUS_OFF => Some(Self::BusOff),
+            libc::CAN_STATE_STOPPED => Some(Self::Stopped),
+            libc::CAN_STATE_SLEEPING => Some(Self::Sleeping),
+            _ => None,
+        }
+    }
+}
+
+/// Error counters of a CAN controller
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CanErrorCounter {
+    pub tx_errors: u16,
+    pub rx_errors: u16,
+}
+
+/// Events counted by the CAN driver since the interface was created
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CanDeviceStats {
+    pub bus_errors: u32,
+    /// Number of changes to the error warning state
+    pub error_warning: u32,
+    /// Number of changes to the error passive state
+    pub error_passive: u32,
+    /// Number of changes to the bus-off state
+    pub bus_off: u32,
+    pub arbitration_lost: u32,
+    /// Number of controller restarts after a bus-off
+    pub restarts: u32,
+}
+
+impl CanDeviceStats {
+    fn from_inner(stats: libc::can_device_stats) -> Self {
+        Self {
+            bus_errors: stats.bus_error,
+            error_warning: stats.error_warning,
+            error_passive: stats.error_passive,
+            bus_off: stats.bus_off,
+            arbitration_lost: stats.arbitration_lost,
+            restarts: stats.restarts,
+        }
+    }
+}
+
+/// Generic statistics of a network interface
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct LinkStats {
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    /// Received frames dropped by the kernel (e.g., because of full queues)
+    pub rx_dropped: u64,
+    /// Frames dropped before they were sent
+    pub tx_dropped: u64,
+    /// Received frames lost because of controller overruns
+    pub rx_over_errors: u64,
+    /// Received frames lost because of receive FIFO overflows
+    pub rx_fifo_errors: u64,
+    /// Received frames missed by the controller
+    pub rx_missed_errors: u64,
+    pub tx_aborted_errors: u64,
+    pub tx_fifo_errors: u64,
+}
+
+impl LinkStats {
+    /// Reads the fields of `struct rtnl_link_stats64` which is missing in libc
+    fn from_inner(stats: [u64; 19]) -> Self {
+        Self {
+            rx_packets: stats[0],
+            tx_packets: stats[1],
+            rx_bytes: stats[2],
+            tx_bytes: stats[3],
+            rx_errors: stats[4],
+            tx_errors: stats[5],
+            rx_dropped: stats[6],
+            tx_dropped: stats[7],
+            rx_over_errors: stats[11],
+            rx_fifo_errors: stats[14],
+            rx_missed_errors: stats[15],
+            tx_aborted_errors: stats[16],
+            tx_fifo_errors: stats[18],
+        }
+    }
+}
+
+/// Snapshot of the state and statistics of a CAN interface
+///
+/// The CAN specific values are only reported by real CAN controllers.
+/// Virtual interfaces like `vcan` only have link statistics.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct CanStats {
+    pub state: Option<CanState>,
+    /// Error counters, if supported by the driver
+    pub error_counter: Option<CanErrorCounter>,
+    pub device: Option<CanDeviceStats>,
+    pub link: LinkStats,
+}
+
+impl CanStats {
+    pub(crate) fn from_link(link: &LinkMessage) -> Self {
+        let data = link.can_data();
+        Self {
+            state: data
+                .get(libc::IFLA_CAN_STATE as u16)
+                .and_then(|state| CanState::from_inner(read_struct(state))),
+            error_counter: data.get(libc::IFLA_CAN_BERR_COUNTER as u16).map(|counter| {
+                let counter = read_struct::<libc::can_berr_counter>(counter);
+                CanErrorCounter {
+                    tx_errors: counter.txerr,
+                    rx_errors: counter.rxerr,
+                }
+            }),
+            device: link
+                .link_info()
+                .get(libc::IFLA_INFO_XSTATS)
+                .map(|stats| CanDeviceStats::from_inner(read_struct(stats))),
+            link: link
+                .attributes()
+                .get(libc::IFLA_STATS64)
+                .map(|stats| LinkStats::from_inner(read_struct(stats)))
+                .unwrap_or_default(),
+        }
+    }
+}
+
+impl CanInterface {
+    /// Reads the current state and statistics of the interface
+    pub fn stats(&self) -> Result<CanStats, std::io::Error> {
+        let link = NetlinkSocket::open()?.get_link(self.if_index())?;
+        Ok(CanStats::from_link(&link))
+    }
+
+    /// Reads the current state of the CAN controller
+    ///
+    /// Returns `None` if the driver doesn't report a state.
+    pub fn state(&self) -> Result<Option<CanState>, std::io::Error> {
+        Ok(self.stats()?.state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CanDeviceStats, CanErrorCounter, CanState, CanStats};
+    use crate::netlink::socket::{
+        link_request, put_attribute, put_nested, struct_bytes, LinkMessage,
+    };
+
+    #[test]
+    fn reads_can_stats() {
+        let mut payload = link_request(3, 0, 0);
+
+        let mut link_stats = [0u64; 24];
+        link_stats[0] = 10;
+        link_stats[1] = 20;
+        link_stats[6] = 2;
+        link_stats[11] = 1;
+        let link_stats: Vec<u8> = link_stats.iter().flat_map(|v| v.to_ne_bytes()).collect();
+        put_attribute(&mut payload, libc::IFLA_STATS64, &link_stats);
+
+        put_nested(&mut payload, libc::IFLA_LINKINFO, |buf| {
+            put_attribute(buf, libc::IFLA_INFO_KIND, b"can");
+            put_nested(buf, libc::IFLA_INFO_DATA, |buf| {
+                put_attribute(
+                    buf,
+                    libc::IFLA_CAN_STATE as u16,
+                    &libc::CAN_STATE_ERROR_PASSIVE.to_ne_bytes(),
+                );
+                put_attribute(
+                    buf,
+                    libc::IFLA_CAN_BERR_COUNTER as u16,
+                    struct_bytes(&libc::can_berr_counter {
+                        txerr: 128,
+                        rxerr: 5,
+                    }),
+                );
+            });
+            put_attribute(
+                buf,
+                libc::IFLA_INFO_XSTATS,
+                struct_bytes(&libc::can_device_stats {
+                    bus_error: 7,
+                    error_warning: 2,
+                    error_passive: 1,
+                    bus_off: 0,
+                    arbitration_lost: 4,
+                    restarts: 0,
+                }),
+            );
+        });
+
+        let stats = CanStats::from_link(&LinkMessage::parse(payload).unwrap());
+        assert_eq!(stats.state, Some(CanState::ErrorPassive));
+        assert_eq!(
+            stats.error_counter,
+            Some(CanErrorCounter {
+                tx_errors: 128,
+                rx_errors: 5
+            })
+        );
+        assert_eq!(
+            stats.device,
+            Some(CanDeviceStats {
+                bus_errors: 7,
+                error_warning: 2,
+                error_passive: 1,
+                bus_off: 0,
+                arbitration_lost: 4,
+                restarts: 0,
+            })
+        );
+        assert_eq!(stats.link.rx_packets, 10);
+        assert_eq!(stats.link.tx_packets, 20);
+        assert_eq!(stats.link.rx_dropped, 2);
+        assert_eq!(stats.link.rx_over_errors, 1);
+    }
+
+    #[test]
+    fn handles_virtual_links() {
+        let payload = link_request(3, 0, 0);
+        let stats = CanStats::from_link(&LinkMessage::parse(payload).unwrap());
+        assert_eq!(stats, CanStats::default());
+    }
+}
@@ -0,0 +1,193 @@
+//! Reactor and timer of the async runtime selected by the features.
+//!
+//! Tokio is used if the `tokio` feature is enabled (default), otherwise the
+//! reactor of `async-io`, which is used by smol.
+
+use std::{
+    future::Future,
+    os::fd::{AsFd, AsRawFd},
+    pin::Pin,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+#[cfg(feature = "tokio")]
+pub(crate) use tokio::time::Instant;
+
+#[cfg(not(feature = "tokio"))]
+pub(crate) use std::time::Instant;
+
+/// File descriptor registered with the reactor of the runtime
+///
+/// The file descriptor must be set to non-blocking before it is used.
+pub(crate) struct AsyncFd<T: AsRawFd + AsFd> {
+    #[cfg(feature = "tokio")]
+    inner: tokio::io::unix::AsyncFd<T>,
+    #[cfg(not(feature = "tokio"))]
+    inner: async_io::Async<T>,
+}
+
+impl<T: AsRawFd + AsFd> AsyncFd<T> {
+    pub(crate) fn new(io: T) -> Result<Self, std::io::Error> {
+        #[cfg(feature = "tokio")]
+        let inner = tokio::io::unix::AsyncFd::new(io)?;
+        #[cfg(not(feature = "tokio"))]
+        let inner = async_io::Async::new_nonblocking(io)?;
+
+        Ok(Self { inner })
+    }
+
+    pub(crate) fn get_ref(&self) -> &T {
+        self.inner.get_ref()
+    }
+
+    /// Polls the operation until it doesn't fail with `WouldBlock`
+    pub(crate) fn poll_read_with<R>(
+        &self,
+        cx: &mut Context<'_>,
+        mut op: impl FnMut(&T) -> std::io::Result<R>,
+    ) -> Poll<std::io::Result<R>> {
+        #[cfg(feature = "tokio")]
+        loop {
+            let mut ready = match self.inner.poll_read_ready(cx) {
+                Poll::Ready(ready) => ready?,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            match op(self.inner.get_ref()) {
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => ready.clear_ready(),
+                result => return Poll::Ready(result),
+            }
+        }
+
+        #[cfg(not(feature = "tokio"))]
+        loop {
+            match op(self.inner.get_ref()) {
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                result => return Poll::Ready(result),
+            }
+
+            match self.inner.poll_readable(cx) {
+                Poll::Ready(ready) => ready?,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    /// See [AsyncFd::poll_read_with()]
+    pub(crate) fn poll_write_with<R>(
+        &self,
+        cx: &mut Context<'_>,
+        mut op: impl FnMut(&T) -> std::io::Result<R>,
+    ) -> Poll<std::io::Result<R>> {
+        #[cfg(feature = "tokio")]
+        loop {
+            let mut ready = match self.inner.poll_write_ready(cx) {
+                Poll::Ready(ready) => ready?,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            match op(self.inner.get_ref()) {
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => ready.clear_ready(),
+                result => return Poll::Ready(result),
+            }
+        }
+
+        #[cfg(not(feature = "tokio"))]
+        loop {
+            match op(self.inner.get_ref()) {
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {}
+                result => return Poll::Ready(result),
+            }
+
+            match self.inner.poll_writable(cx) {
+                Poll::Ready(ready) => ready?,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+/// Timer of the runtime which can be reset to a new deadline
+pub(crate) struct Sleep {
+    #[cfg(feature = "tokio")]
+    inner: Pin<Box<tokio::time::Sleep>>,
+    #[cfg(not(feature = "tokio"))]
+    inner: async_io::Timer,
+}
+
+impl Sleep {
+    pub(crate) fn until(deadline: Instant) -> Self {
+        #[cfg(feature = "tokio")]
+        let inner = Box::pin(tokio::time::sleep_until(deadline));
+        #[cfg(not(feature = "tokio"))]
+        let inner = async_io::Timer::at(deadline);
+
+        Self { inner }
+    }
+
+    pub(crate) fn reset(&mut self, deadline: Instant) {
+        #[cfg(feature = "tokio")]
+        self.inner.as_mut().reset(deadline);
+        #[cfg(not(feature = "tokio"))]
+        self.inner.set_at(deadline);
+    }
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        #[cfg(feature = "tokio")]
+        return self.inner.as_mut().poll(cx);
+        #[cfg(not(feature = "tokio"))]
+        return Pin::new(&mut self.inner).poll(cx).map(|_| ());
+    }
+}
+
+/// Waits for the duration
+pub(crate) fn sleep(duration: Duration) -> Sleep {
+    Sleep::until(Instant::now() + duration)
+}
+
+/// Awaits the future, returns `None` if it didn't complete within the duration
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    let mut future = std::pin::pin!(future);
+    let mut sleep = sleep(duration);
+
+    std::future::poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        Pin::new(&mut sleep).poll(cx).map(|_| None)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::timeout;
+
+    async fn check_timeout() {
+        let pending = std::future::pending::<()>();
+        assert_eq!(timeout(Duration::from_millis(10), pending).await, None);
+        assert_eq!(
+            timeout(Duration::from_millis(10), async { 42 }).await,
+            Some(42)
+        );
+    }
+
+    #[cfg(feature = "tokio")]
+    #[tokio::test(start_paused = true)]
+    async fn times_out() {
+        check_timeout().await;
+    }
+
+    #[cfg(not(feature = "tokio"))]
+    #[test]
+    fn times_out() {
+        async_io::block_on(check_timeout());
+    }
+}
@@ -1,34 +1,58 @@
 use std::{
     ffi::CString,
-    os::unix::{io::RawFd, prelude::AsRawFd},
+    os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd, RawFd},
 };
 
-use tokio::io::{unix::AsyncFd, AsyncRead, AsyncWrite};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use crate::runtime::AsyncFd;
+use crate::sockopt::SocketOption;
 
 /// Represents a specific CAN interface on the system.
 ///
 /// The CAN interace can either be addressed by an name (e.g., `vcan0`)
 /// or by its index.
-/// During the creation, it is checked if the interface actually exists.
-/// If the interface doesn't exist, an error is returned.
+/// During the creation, it is checked if the interface actually exists
+/// and if it is a CAN interface. Otherwise, an error is returned.
 ///
 /// # Example:
-/// ```
+/// ```no_run
+/// # use ddose::CanInterface;
 /// let interface = match CanInterface::try_from("vcan0") {
-///     Ok(if) => if,
+///     Ok(interface) => interface,
 ///     Err(e) => {
 ///         println!("Couldn't find the CAN interface: {}", e);
-///         exit();
+///         std::process::exit(1);
 ///     }
 /// };
 /// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct CanInterface(libc::c_uint);
 
 impl CanInterface {
+    /// Creates the interface without checking that it exists
+    pub(crate) fn from_index_unchecked(if_index: libc::c_uint) -> Self {
+        Self(if_index)
+    }
+
     /// Returns the interfaces system index
     pub fn if_index(&self) -> libc::c_uint {
         self.0
     }
+
+    /// Returns the interfaces name (e.g., `can0`)
+    pub fn name(&self) -> Result<String, std::io::Error> {
+        let mut if_name: [libc::c_char; libc::IF_NAMESIZE] = [0; libc::IF_NAMESIZE];
+        let ptr = if_name.as_mut_ptr();
+        let ret = unsafe { libc::if_indextoname(self.0, ptr) };
+
+        if !std::ptr::eq(ret, ptr) {
+            return Err(std::io::Error::last_os_error());
+        };
+
+        // UNSAFE: if_indextoname wrote a null terminated string to the buffer
+        let if_name = unsafe { std::ffi::CStr::from_ptr(ptr) };
+        Ok(if_name.to_string_lossy().into_owned())
+    }
 }
 
 impl TryFrom<&str> for CanInterface {
@@ -42,6 +66,7 @@ impl TryFrom<&str> for CanInterface {
             return Err(std::io::Error::last_os_error());
         }
 
+        crate::netlink::check_can_link(if_index)?;
         Ok(Self(if_index))
     }
 }
@@ -54,21 +79,478 @@ impl TryFrom<libc::c_uint> for CanInterface {
         let ptr = if_name.as_mut_ptr();
         let ret = unsafe { libc::if_indextoname(if_index, ptr) };
 
-        // We know it is an existing interface but we don't know
-        // if it's actaully a socketcan interface
-        if ret != ptr as _ {
+        if !std::ptr::eq(ret, ptr) {
             return Err(std::io::Error::last_os_error());
         };
 
+        // We know it is an existing interface, so check that it is
+        // actually a socketcan interface
+        crate::netlink::check_can_link(if_index)?;
         Ok(Self(if_index))
     }
 }
 
+/// Socketcan socket without an async runtime
+///
+/// The operations block unless the socket is set to non-blocking. It is used
+/// by [CanSocket] and the blocking API. The socket is closed when it is
+/// dropped.
+pub(crate) struct RawSocket(OwnedFd);
+
+impl RawSocket {
+    /// Creates a new Linux socket
+    ///
+    /// See [CanSocket::create()].
+    pub(crate) fn create(
+        socket_type: libc::c_int,
+        socket_proto: libc::c_int,
+    ) -> Result<Self, std::io::Error> {
+        let socket = unsafe { libc::socket(libc::PF_CAN, socket_type, socket_proto) };
+        if socket.is_negative() {
+            return Err(std::io::Error::last_os_error());
+        }
+        // UNSAFE: The file descriptor was just created and is owned by us
+        let socket = unsafe { OwnedFd::from_raw_fd(socket) };
+
+        Ok(Self(socket))
+    }
+
+    /// Binds the socket to an interface
+    pub(crate) fn bind(&self, can_if: &CanInterface) -> Result<(), std::io::Error> {
+        let can_address: libc::__c_anonymous_sockaddr_can_can_addr = unsafe { std::mem::zeroed() };
+        self.bind_address(can_if, can_address)
+    }
+
+    /// Binds the socket to an interface with custom address
+    pub(crate) fn bind_address(
+        &self,
+        can_if: &CanInterface,
+        can_address: libc::__c_anonymous_sockaddr_can_can_addr,
+    ) -> Result<(), std::io::Error> {
+        const ADDRESS_SIZE: usize = std::mem::size_of::<libc::sockaddr_can>();
+
+        let mut address: libc::sockaddr_can = unsafe { std::mem::zeroed() };
+        address.can_family = libc::AF_CAN as _;
+        address.can_ifindex = can_if.if_index() as _;
+        address.can_addr = can_address;
+
+        let ptr = &address as *const libc::sockaddr_can;
+        let ret = unsafe { libc::bind(self.as_raw_fd(), ptr as _, ADDRESS_SIZE as _) };
+        if ret == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Connects the socket to an interface
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub(crate) fn connect(&self, can_if: &CanInterface) -> Result<(), std::io::Error> {
+        let can_address: libc::__c_anonymous_sockaddr_can_can_addr = unsafe { std::mem::zeroed() };
+        self.connect_address(can_if, can_address)
+    }
+
+    /// Connects the socket to a peer with custom address
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub(crate) fn connect_address(
+        &self,
+        can_if: &CanInterface,
+        can_address: libc::__c_anonymous_sockaddr_can_can_addr,
+    ) -> Result<(), std::io::Error> {
+        const ADDRESS_SIZE: usize = std::mem::size_of::<libc::sockaddr_can>();
+
+        let mut address: libc::sockaddr_can = unsafe { std::mem::zeroed() };
+        address.can_family = libc::AF_CAN as _;
+        address.can_ifindex = can_if.if_index() as _;
+        address.can_addr = can_address;
+
+        let ptr = &address as *const libc::sockaddr_can;
+        let ret = unsafe { libc::connect(self.as_raw_fd(), ptr as _, ADDRESS_SIZE as _) };
+        if ret == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Sets an option on the socket
+    pub(crate) fn set_option<T: ?Sized>(
+        &self,
+        option: SocketOption<T>,
+        value: &T,
+    ) -> Result<(), std::io::Error> {
+        let ret = unsafe {
+            libc::setsockopt(
+                self.as_raw_fd(),
+                option.level(),
+                option.name(),
+                value as *const T as *const libc::c_void,
+                std::mem::size_of_val(value) as _,
+            )
+        };
+        if ret == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Reads an option of the socket
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub(crate) fn option<T: Copy>(&self, option: SocketOption<T>) -> Result<T, std::io::Error> {
+        let mut value = std::mem::MaybeUninit::<T>::zeroed();
+        let mut len = std::mem::size_of::<T>() as libc::socklen_t;
+        let ret = unsafe {
+            libc::getsockopt(
+                self.as_raw_fd(),
+                option.level(),
+                option.name(),
+                value.as_mut_ptr() as *mut libc::c_void,
+                &mut len,
+            )
+        };
+        if ret == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        if len as usize != std::mem::size_of::<T>() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!(
+                    "Socket option has {} bytes instead of {}",
+                    len,
+                    std::mem::size_of::<T>()
+                ),
+            ));
+        }
+
+        // UNSAFE: The kernel filled the value and the option guarantees that
+        // every value is valid
+        Ok(unsafe { value.assume_init() })
+    }
+
+    /// Sets the O_NOBLOCK flag on the socket
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub(crate) fn set_nonblocking(&self) -> Result<(), std::io::Error> {
+        // Get current flags so we can only change the O_NOBLOCK flag
+        let mut flags = unsafe { libc::fcntl(self.as_raw_fd(), libc::F_GETFL) };
+        if flags == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // Write back the flags with O_NOBLOCK set
+        flags |= libc::O_NONBLOCK;
+        let ret = unsafe { libc::fcntl(self.as_raw_fd(), libc::F_SETFL, flags) };
+        if ret == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(())
+    }
+
+    /// Sets the time after which blocking reads fail with `TimedOut`
+    ///
+    /// `None` blocks until a message is received. Like the timeouts of the
+    /// standard library, a zero duration is rejected.
+    #[cfg(feature = "blocking")]
+    pub(crate) fn set_read_timeout(
+        &self,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<(), std::io::Error> {
+        self.set_option(crate::sockopt::SO_RCVTIMEO, &timeval(timeout)?)
+    }
+
+    /// Sets the time after which blocking writes fail with `TimedOut`
+    ///
+    /// See [RawSocket::set_read_timeout()].
+    #[cfg(feature = "blocking")]
+    pub(crate) fn set_write_timeout(
+        &self,
+        timeout: Option<std::time::Duration>,
+    ) -> Result<(), std::io::Error> {
+        self.set_option(crate::sockopt::SO_SNDTIMEO, &timeval(timeout)?)
+    }
+
+    /// Reads a message using `read`
+    pub(crate) fn read(&self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let ret = unsafe { libc::read(self.as_raw_fd(), buf.as_mut_ptr() as _, buf.len()) };
+        if ret.is_negative() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(ret as usize)
+    }
+
+    /// Writes a message using `write`
+    pub(crate) fn write(&self, buf: &[u8]) -> std::io::Result<usize> {
+        let ret = unsafe { libc::write(self.as_raw_fd(), buf.as_ptr() as _, buf.len()) };
+        if ret.is_negative() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(ret as usize)
+    }
+
+    /// Receives a message together with its ancillary data
+    pub(crate) fn recv(&self, buf: &mut [u8]) -> std::io::Result<(usize, CanRxMeta)> {
+        let mut address: libc::sockaddr_can = unsafe { std::mem::zeroed() };
+        self.recvmsg(buf, Some(&mut address), |msg, bytes_read| {
+            let meta = unsafe { CanRxMeta::from_msghdr(msg) };
+            (bytes_read, meta)
+        })
+    }
+
+    /// Receives a message using `recvmsg`
+    ///
+    /// If `address` is given, it is filled with the address of the sender. The
+    /// message header is passed to `parse` while the control buffer is still
+    /// valid, so the control messages can be evaluated there.
+    pub(crate) fn recvmsg<R>(
+        &self,
+        buf: &mut [u8],
+        address: Option<&mut libc::sockaddr_can>,
+        parse: impl FnOnce(&libc::msghdr, usize) -> R,
+    ) -> std::io::Result<R> {
+        // Large enough for the timestamps, the drop counter and the J1939
+        // metadata. Using u64 makes sure the buffer is aligned for the control
+        // message headers.
+        let mut control = [0u64; 32];
+
+        let mut iov = libc::iovec {
+            iov_base: buf.as_mut_ptr() as _,
+            iov_len: buf.len(),
+        };
+        let mut msg: libc::msghdr = unsafe { std::mem::zeroed() };
+        msg.msg_iov = &mut iov;
+        msg.msg_iovlen = 1;
+        msg.msg_control = control.as_mut_ptr() as _;
+        msg.msg_controllen = std::mem::size_of_val(&control) as _;
+        if let Some(address) = address {
+            msg.msg_name = address as *mut libc::sockaddr_can as _;
+            msg.msg_namelen = std::mem::size_of::<libc::sockaddr_can>() as _;
+        }
+
+        let ret = unsafe { libc::recvmsg(self.as_raw_fd(), &mut msg, 0) };
+        if ret.is_negative() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(parse(&msg, ret as usize))
+    }
+
+    /// Receives multiple frames with a single `recvmmsg` call
+    ///
+    /// Up to `count` frames are received into `buffers`, each of up to
+    /// `buffer_size` bytes. `parse` is called for each frame with its bytes
+    /// and ancillary data. Returns the number of frames.
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub(crate) fn recv_many(
+        &self,
+        buffers: &mut RecvBuffers,
+        count: usize,
+        buffer_size: usize,
+        mut parse: impl FnMut(&[u8], CanRxMeta),
+    ) -> std::io::Result<usize> {
+        buffers.prepare(count, buffer_size);
+
+        let ret = unsafe {
+            libc::recvmmsg(
+                self.as_raw_fd(),
+                buffers.msgs.as_mut_ptr(),
+                count as _,
+                0,
+                std::ptr::null_mut(),
+            )
+        };
+        if ret.is_negative() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        let received = ret as usize;
+        for (msg, buffer) in buffers
+            .msgs
+            .iter()
+            .zip(buffers.data.chunks(buffer_size))
+            .take(received)
+        {
+            let meta = unsafe { CanRxMeta::from_msghdr(&msg.msg_hdr) };
+            parse(&buffer[..msg.msg_len as usize], meta);
+        }
+
+        Ok(received)
+    }
+
+    /// Sends multiple messages with a single `sendmmsg` call
+    ///
+    /// Returns the number of sent messages, which is less than the number of
+    /// messages if the transmit queue is full.
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub(crate) fn send_many(&self, messages: &[&[u8]]) -> std::io::Result<usize> {
+        if messages.is_empty() {
+            return Ok(0);
+        }
+
+        let mut iovs: Vec<libc::iovec> = messages
+            .iter()
+            .map(|message| libc::iovec {
+                iov_base: message.as_ptr() as _,
+                iov_len: message.len(),
+            })
+            .collect();
+        let mut msgs: Vec<libc::mmsghdr> = iovs
+            .iter_mut()
+            .map(|iov| {
+                let mut msg: libc::mmsghdr = unsafe { std::mem::zeroed() };
+                msg.msg_hdr.msg_iov = iov;
+                msg.msg_hdr.msg_iovlen = 1;
+                msg
+            })
+            .collect();
+
+        let ret =
+            unsafe { libc::sendmmsg(self.as_raw_fd(), msgs.as_mut_ptr(), msgs.len() as _, 0) };
+        if ret.is_negative() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(ret as usize)
+    }
+
+    /// Sends a message to the given address using `sendto`
+    #[cfg(any(feature = "tokio", feature = "async-io"))]
+    pub(crate) fn send_to(
+        &self,
+        buf: &[u8],
+        address: &libc::sockaddr_can,
+    ) -> std::io::Result<usize> {
+        const ADDRESS_SIZE: usize = std::mem::size_of::<libc::sockaddr_can>();
+
+        let ptr = address as *const libc::sockaddr_can;
+        let ret = unsafe {
+            libc::sendto(
+                self.as_raw_fd(),
+                buf.as_ptr() as _,
+                buf.len(),
+                0,
+                ptr as _,
+                ADDRESS_SIZE as _,
+            )
+        };
+        if ret.is_negative() {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(ret as usize)
+    }
+}
+
+/// Storage of [RawSocket::recv_many()] which is reused between the calls
+///
+/// The buffers only grow, so receiving batches of the same size doesn't
+/// allocate after the first call.
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+#[derive(Default)]
+pub(crate) struct RecvBuffers {
+    data: Vec<u8>,
+    // See RawSocket::recvmsg() for the size of the control buffers
+    controls: Vec<[u64; 32]>,
+    addresses: Vec<libc::sockaddr_can>,
+    iovs: Vec<libc::iovec>,
+    msgs: Vec<libc::mmsghdr>,
+}
+
+// UNSAFE: The pointers of the message headers only refer to the buffers of the
+// struct and are set again before each use
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+unsafe impl Send for RecvBuffers {}
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+unsafe impl Sync for RecvBuffers {}
+
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+impl RecvBuffers {
+    /// Sets up the message headers for `count` buffers of `buffer_size` bytes
+    fn prepare(&mut self, count: usize, buffer_size: usize) {
+        // UNSAFE: The structs are plain C structs which can be zeroed
+        let (address, iov, msg) = unsafe { std::mem::zeroed() };
+        self.data.resize(count * buffer_size, 0);
+        self.controls.resize(count, [0; 32]);
+        self.addresses.resize(count, address);
+        self.iovs.resize(count, iov);
+        self.msgs.resize(count, msg);
+
+        let buffers = self.data.chunks_mut(buffer_size);
+        for ((((buffer, control), address), iov), msg) in buffers
+            .zip(&mut self.controls)
+            .zip(&mut self.addresses)
+            .zip(&mut self.iovs)
+            .zip(&mut self.msgs)
+        {
+            iov.iov_base = buffer.as_mut_ptr() as _;
+            iov.iov_len = buffer.len();
+            msg.msg_hdr.msg_iov = iov;
+            msg.msg_hdr.msg_iovlen = 1;
+            // The kernel overwrites the lengths with the received sizes
+            msg.msg_hdr.msg_control = control.as_mut_ptr() as _;
+            msg.msg_hdr.msg_controllen = std::mem::size_of_val(control) as _;
+            msg.msg_hdr.msg_name = address as *mut libc::sockaddr_can as _;
+            msg.msg_hdr.msg_namelen = std::mem::size_of::<libc::sockaddr_can>() as _;
+            msg.msg_hdr.msg_flags = 0;
+            msg.msg_len = 0;
+        }
+    }
+}
+
+/// Converts a socket timeout into the representation of the kernel
+#[cfg(feature = "blocking")]
+fn timeval(timeout: Option<std::time::Duration>) -> Result<libc::timeval, std::io::Error> {
+    let timeout = match timeout {
+        Some(timeout) if timeout.is_zero() => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Cannot set a zero duration timeout",
+            ));
+        }
+        // Round up, as timeouts below a microsecond would block forever
+        Some(timeout) => timeout.max(std::time::Duration::from_micros(1)),
+        None => std::time::Duration::ZERO,
+    };
+
+    Ok(libc::timeval {
+        tv_sec: timeout.as_secs().min(libc::time_t::MAX as u64) as libc::time_t,
+        tv_usec: timeout.subsec_micros() as libc::suseconds_t,
+    })
+}
+
+/// Reports expired socket timeouts as `TimedOut` instead of `WouldBlock`
+#[cfg(feature = "blocking")]
+pub(crate) fn timed_out(e: std::io::Error) -> std::io::Error {
+    match e.kind() {
+        std::io::ErrorKind::WouldBlock => std::io::Error::from(std::io::ErrorKind::TimedOut),
+        _ => e,
+    }
+}
+
+impl AsRawFd for RawSocket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl AsFd for RawSocket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.as_fd()
+    }
+}
+
 /// Wrapper for socketcan sockets
 ///
 /// Creates socketcan sockets and allows to read and write to them.
-pub struct CanSocket(AsyncFd<RawFd>);
+///
+/// The socket is closed when it is dropped.
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+pub struct CanSocket(AsyncFd<RawSocket>);
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 impl CanSocket {
     /// Creates a new Linux socket
     ///
@@ -83,11 +565,11 @@ impl CanSocket {
         socket_type: libc::c_int,
         socket_proto: libc::c_int,
     ) -> Result<Self, std::io::Error> {
-        let socket = unsafe { libc::socket(libc::PF_CAN, socket_type, socket_proto) };
-        if socket.is_negative() {
-            return Err(std::io::Error::last_os_error());
-        }
+        Self::from_raw(RawSocket::create(socket_type, socket_proto)?)
+    }
 
+    /// Registers a socket with the runtime
+    pub(crate) fn from_raw(socket: RawSocket) -> Result<Self, std::io::Error> {
         Ok(Self(AsyncFd::new(socket)?))
     }
 
@@ -98,8 +580,7 @@ impl CanSocket {
     /// If you need to specifiy the addresses CAN adresses, you can use the
     /// [Self::bind_address()] function.
     pub fn bind(&self, can_if: &CanInterface) -> Result<(), std::io::Error> {
-        let can_address: libc::__c_anonymous_sockaddr_can_can_addr = unsafe { std::mem::zeroed() };
-        self.bind_address(can_if, can_address)
+        self.0.get_ref().bind(can_if)
     }
 
     /// Binds the CAN socket to an CAN interface with custom address
@@ -111,25 +592,51 @@ impl CanSocket {
         can_if: &CanInterface,
         can_address: libc::__c_anonymous_sockaddr_can_can_addr,
     ) -> Result<(), std::io::Error> {
-        const ADDRESS_SIZE: usize = std::mem::size_of::<libc::sockaddr_can>();
+        self.0.get_ref().bind_address(can_if, can_address)
+    }
 
-        let mut address: libc::sockaddr_can = unsafe { std::mem::zeroed() };
-        address.can_family = libc::AF_CAN as _;
-        address.can_ifindex = can_if.if_index() as _;
-        address.can_addr = can_address;
+    /// Connects the CAN socket to an CAN interface
+    ///
+    /// Some CAN protocols (e.g., `CAN_BCM`) require the socket to be connected
+    /// instead of being bound to the interface.
+    pub fn connect(&self, can_if: &CanInterface) -> Result<(), std::io::Error> {
+        self.0.get_ref().connect(can_if)
+    }
 
-        let ptr = &address as *const libc::sockaddr_can;
-        let ret = unsafe { libc::bind(self.as_raw_fd(), ptr as _, ADDRESS_SIZE as _) };
-        if ret == -1 {
-            // Store error because closing could produce another error
-            let error = std::io::Error::last_os_error();
-            unsafe {
-                libc::close(self.as_raw_fd());
-            }
-            return Err(error);
-        }
+    /// Connects the CAN socket to a peer with custom address
+    ///
+    /// Datagram protocols (e.g., `CAN_J1939`) use the address as default
+    /// destination for `write` and only receive messages from that peer.
+    pub fn connect_address(
+        &self,
+        can_if: &CanInterface,
+        can_address: libc::__c_anonymous_sockaddr_can_can_addr,
+    ) -> Result<(), std::io::Error> {
+        self.0.get_ref().connect_address(can_if, can_address)
+    }
 
-        Ok(())
+    /// Sets an option on the socket
+    ///
+    /// See [sockopt](crate::sockopt) for the available options.
+    pub fn set_option<T: ?Sized>(
+        &self,
+        option: SocketOption<T>,
+        value: &T,
+    ) -> Result<(), std::io::Error> {
+        self.0.get_ref().set_option(option, value)
+    }
+
+    /// Reads an option of the socket
+    ///
+    /// Returns an error if the size of the value returned by the kernel
+    /// doesn't match the type of the option.
+    pub fn option<T: Copy>(&self, option: SocketOption<T>) -> Result<T, std::io::Error> {
+        self.0.get_ref().option(option)
+    }
+
+    /// Returns the socket without the runtime, e.g. for the shared options
+    pub(crate) fn raw(&self) -> &RawSocket {
+        self.0.get_ref()
     }
 
     /// Sets the O_NOBLOCK flag on the socket
@@ -137,89 +644,236 @@ impl CanSocket {
     /// This function sets the O_NOBLOCK flag on the underlying socket. This is
     /// required for async to work.
     pub fn set_nonblocking(&self) -> Result<(), std::io::Error> {
-        // Get current flags so we can only change the O_NOBLOCK flag
-        let mut flags = unsafe { libc::fcntl(self.as_raw_fd(), libc::F_GETFL) };
-        if flags == -1 {
-            return Err(std::io::Error::last_os_error());
+        self.0.get_ref().set_nonblocking()
+    }
+}
+
+/// Ancillary data which is received together with a frame
+///
+/// The fields are only set if the corresponding socket options are enabled,
+/// e.g. using `set_timestamping()` of the bus.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CanRxMeta {
+    /// Time at which the kernel received the frame (`SO_TIMESTAMPNS`)
+    pub software_timestamp: Option<std::time::SystemTime>,
+    /// Raw timestamp of the CAN controller (`SO_TIMESTAMPING`)
+    ///
+    /// The reference point of the timestamp depends on the hardware.
+    pub hardware_timestamp: Option<std::time::Duration>,
+    /// Total number of frames the socket dropped so far (`SO_RXQ_OVFL`)
+    pub dropped: Option<u32>,
+    /// Interface the frame was received on
+    pub interface: Option<CanInterface>,
+    /// The frame was written by the receiving socket (`MSG_CONFIRM`)
+    pub is_own: bool,
+    /// The frame was written by a socket on this host (`MSG_DONTROUTE`)
+    pub is_local: bool,
+}
+
+impl CanRxMeta {
+    /// Parses the control messages of a received message
+    ///
+    /// UNSAFE: The control buffer and the address of the message header must
+    /// be valid and filled by `recvmsg`.
+    unsafe fn from_msghdr(msg: &libc::msghdr) -> Self {
+        let mut meta = Self {
+            is_own: msg.msg_flags & libc::MSG_CONFIRM != 0,
+            is_local: msg.msg_flags & libc::MSG_DONTROUTE != 0,
+            ..Self::default()
+        };
+
+        if !msg.msg_name.is_null()
+            && msg.msg_namelen as usize >= std::mem::size_of::<libc::sockaddr_can>()
+        {
+            let address = std::ptr::read_unaligned(msg.msg_name as *const libc::sockaddr_can);
+            if address.can_ifindex > 0 {
+                meta.interface = Some(CanInterface(address.can_ifindex as _));
+            }
         }
 
-        // Write back the flags with O_NOBLOCK set
-        flags |= libc::O_NONBLOCK;
-        let ret = unsafe { libc::fcntl(self.as_raw_fd(), libc::F_SETFL, flags) };
-        if ret == -1 {
-            return Err(std::io::Error::last_os_error());
+        let mut cmsg = libc::CMSG_FIRSTHDR(msg);
+        while !cmsg.is_null() {
+            let level = (*cmsg).cmsg_level;
+            let kind = (*cmsg).cmsg_type;
+            let data = libc::CMSG_DATA(cmsg);
+
+            match (level, kind) {
+                (libc::SOL_SOCKET, libc::SO_TIMESTAMPNS) => {
+                    let ts = std::ptr::read_unaligned(data as *const libc::timespec);
+                    meta.software_timestamp =
+                        Some(std::time::UNIX_EPOCH + timespec_to_duration(&ts));
+                }
+                (libc::SOL_SOCKET, libc::SO_TIMESTAMPING) => {
+                    // struct scm_timestamping contains three timestamps: software,
+                    // deprecated and raw hardware timestamp. Unset ones are zero.
+                    let ts = std::ptr::read_unaligned(data as *const [libc::timespec; 3]);
+                    let software = timespec_to_duration(&ts[0]);
+                    let hardware = timespec_to_duration(&ts[2]);
+                    if !software.is_zero() {
+                        meta.software_timestamp = Some(std::time::UNIX_EPOCH + software);
+                    }
+                    if !hardware.is_zero() {
+                        meta.hardware_timestamp = Some(hardware);
+                    }
+                }
+                (libc::SOL_SOCKET, libc::SO_RXQ_OVFL) => {
+                    meta.dropped = Some(std::ptr::read_unaligned(data as *const u32));
+                }
+                _ => {}
+            }
+
+            cmsg = libc::CMSG_NXTHDR(msg, cmsg);
         }
 
-        Ok(())
+        meta
     }
 }
 
-impl std::os::unix::io::AsRawFd for CanSocket {
+fn timespec_to_duration(ts: &libc::timespec) -> std::time::Duration {
+    std::time::Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
+
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+impl CanSocket {
+    /// Receives a message together with its ancillary data
+    ///
+    /// In contrast to `AsyncRead`, `recvmsg` is used so the timestamps and the
+    /// drop counter of the frame are available.
+    pub fn poll_recv(
+        &self,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<(usize, CanRxMeta)>> {
+        self.0.poll_read_with(cx, |socket| socket.recv(buf))
+    }
+
+    /// Receives a message together with its ancillary data
+    ///
+    /// See [CanSocket::poll_recv()].
+    pub async fn recv(&self, buf: &mut [u8]) -> std::io::Result<(usize, CanRxMeta)> {
+        std::future::poll_fn(|cx| self.poll_recv(cx, buf)).await
+    }
+
+    /// Receives a message using `recvmsg`
+    ///
+    /// See [RawSocket::recvmsg()].
+    pub(crate) fn poll_recvmsg<R>(
+        &self,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+        mut address: Option<&mut libc::sockaddr_can>,
+        mut parse: impl FnMut(&libc::msghdr, usize) -> R,
+    ) -> std::task::Poll<std::io::Result<R>> {
+        self.0.poll_read_with(cx, |socket| {
+            socket.recvmsg(buf, address.as_deref_mut(), &mut parse)
+        })
+    }
+
+    /// Receives multiple frames with a single `recvmmsg` call
+    ///
+    /// See [RawSocket::recv_many()].
+    pub(crate) fn poll_recv_many(
+        &self,
+        cx: &mut std::task::Context<'_>,
+        buffers: &mut RecvBuffers,
+        count: usize,
+        buffer_size: usize,
+        mut parse: impl FnMut(&[u8], CanRxMeta),
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.0.poll_read_with(cx, |socket| {
+            socket.recv_many(buffers, count, buffer_size, &mut parse)
+        })
+    }
+
+    /// Sends multiple messages with a single `sendmmsg` call
+    ///
+    /// See [RawSocket::send_many()].
+    pub(crate) fn poll_send_many(
+        &self,
+        cx: &mut std::task::Context<'_>,
+        messages: &[&[u8]],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.0
+            .poll_write_with(cx, |socket| socket.send_many(messages))
+    }
+
+    /// Sends a message to the given address using `sendto`
+    pub(crate) fn poll_send_to(
+        &self,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+        address: &libc::sockaddr_can,
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.0
+            .poll_write_with(cx, |socket| socket.send_to(buf, address))
+    }
+
+    /// Reads a message using `read`
+    ///
+    /// In contrast to `AsyncRead`, only a shared reference is needed, so the
+    /// socket can be read and written at the same time.
+    pub(crate) fn poll_read_bytes(
+        &self,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.0.poll_read_with(cx, |socket| socket.read(buf))
+    }
+
+    /// Writes a message using `write`
+    ///
+    /// See [CanSocket::poll_read_bytes()].
+    pub(crate) fn poll_write_bytes(
+        &self,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.0.poll_write_with(cx, |socket| socket.write(buf))
+    }
+}
+
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+impl AsRawFd for CanSocket {
     fn as_raw_fd(&self) -> RawFd {
-        *self.0.get_ref()
+        self.0.get_ref().as_raw_fd()
     }
 }
 
-impl AsyncRead for CanSocket {
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+impl AsFd for CanSocket {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.0.get_ref().as_fd()
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for CanSocket {
     fn poll_read(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
         buf: &mut tokio::io::ReadBuf<'_>,
     ) -> std::task::Poll<std::io::Result<()>> {
-        loop {
-            let mut ready = match self.0.poll_read_ready(cx) {
-                std::task::Poll::Ready(t) => t,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
-            }?;
-
-            let ret = unsafe {
-                libc::read(
-                    *self.0.get_ref(),
-                    buf.unfilled_mut() as *mut _ as _,
-                    buf.remaining(),
-                )
-            };
-
-            if ret.is_negative() {
-                let error = std::io::Error::last_os_error();
-                match error.kind() {
-                    std::io::ErrorKind::WouldBlock => ready.clear_ready(),
-                    _ => return std::task::Poll::Ready(Err(error)),
-                }
-            } else {
-                let n_bytes = ret as usize;
-                unsafe { buf.assume_init(n_bytes) };
-                buf.advance(n_bytes);
-                return std::task::Poll::Ready(Ok(()));
-            }
-        }
+        // UNSAFE: read only writes initialized bytes to the buffer
+        let unfilled = unsafe { &mut *(buf.unfilled_mut() as *mut _ as *mut [u8]) };
+        let n_bytes = match self.poll_read_bytes(cx, unfilled) {
+            std::task::Poll::Ready(result) => result?,
+            std::task::Poll::Pending => return std::task::Poll::Pending,
+        };
+
+        unsafe { buf.assume_init(n_bytes) };
+        buf.advance(n_bytes);
+        std::task::Poll::Ready(Ok(()))
     }
 }
 
-impl AsyncWrite for CanSocket {
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncWrite for CanSocket {
     fn poll_write(
         self: std::pin::Pin<&mut Self>,
         cx: &mut std::task::Context<'_>,
         buf: &[u8],
     ) -> std::task::Poll<Result<usize, std::io::Error>> {
-        loop {
-            let mut ready = match self.0.poll_write_ready(cx) {
-                std::task::Poll::Ready(t) => t,
-                std::task::Poll::Pending => return std::task::Poll::Pending,
-            }?;
-
-            let ret = unsafe { libc::write(*self.0.get_ref(), buf.as_ptr() as _, buf.len()) };
-            if ret.is_negative() {
-                let error = std::io::Error::last_os_error();
-                match error.kind() {
-                    std::io::ErrorKind::WouldBlock => ready.clear_ready(),
-                    _ => return std::task::Poll::Ready(Err(error)),
-                }
-            } else {
-                let n_bytes = ret as usize;
-                return std::task::Poll::Ready(Ok(n_bytes));
-            }
-        }
+        self.poll_write_bytes(cx, buf)
     }
 
     fn poll_flush(
@@ -236,3 +890,74 @@ impl AsyncWrite for CanSocket {
         std::task::Poll::Ready(Ok(()))
     }
 }
+
+#[cfg(feature = "async-io")]
+impl futures_io::AsyncRead for CanSocket {
+    fn poll_read(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &mut [u8],
+    ) -> std::task::Poll<std::io::Result<usize>> {
+        self.poll_read_bytes(cx, buf)
+    }
+}
+
+#[cfg(feature = "async-io")]
+impl futures_io::AsyncWrite for CanSocket {
+    fn poll_write(
+        self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<Result<usize, std::io::Error>> {
+        self.poll_write_bytes(cx, buf)
+    }
+
+    fn poll_flush(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), std::io::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(
+        self: std::pin::Pin<&mut Self>,
+        _cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), std::io::Error>> {
+        std::task::Poll::Ready(Ok(()))
+    }
+}
+
+// The batched receive is only used by the async CanBus
+#[cfg(all(test, any(feature = "tokio", feature = "async-io")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reuses_receive_buffers() {
+        let mut fds = [0; 2];
+        let ret = unsafe { libc::socketpair(libc::AF_UNIX, libc::SOCK_DGRAM, 0, fds.as_mut_ptr()) };
+        assert_eq!(ret, 0);
+        let (rx, tx) = unsafe {
+            (
+                RawSocket(OwnedFd::from_raw_fd(fds[0])),
+                RawSocket(OwnedFd::from_raw_fd(fds[1])),
+            )
+        };
+        // recvmmsg() waits for all messages on a blocking socket
+        rx.set_nonblocking().unwrap();
+
+        let mut buffers = RecvBuffers::default();
+        for messages in [&[&b"one"[..], b"two"][..], &[b"three"]] {
+            assert_eq!(tx.send_many(messages).unwrap(), messages.len());
+
+            let mut received = Vec::new();
+            let count = rx
+                .recv_many(&mut buffers, 4, 16, |buffer, _| {
+                    received.push(buffer.to_vec())
+                })
+                .unwrap();
+            assert_eq!(count, messages.len());
+            assert_eq!(received, messages);
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Typed socket options for CAN sockets.
+//!
+//! Each option knows its level, its name and the type of its value, so the
+//! value passed to `CanSocket::set_option()` or returned by
+//! `CanSocket::option()` always has the layout the kernel expects.
+//!
+//! # Example:
+//! ```no_run
+//! # use ddose::{sockopt, CanBus, CanInterface};
+//! # #[tokio::main] async fn main() -> Result<(), std::io::Error> {
+//! let can_bus = CanBus::open(&CanInterface::try_from("can0")?)?;
+//! can_bus.socket().set_option(sockopt::SO_RCVBUF, &(1024 * 1024))?;
+//! let size = can_bus.socket().option(sockopt::SO_RCVBUF)?;
+//! # Ok(())
+//! # }
+//! ```
+
+use std::marker::PhantomData;
+
+use crate::can::CanRawVcidOptions;
+
+/// Level of the socket options of ISO-TP sockets
+pub const SOL_CAN_ISOTP: libc::c_int = libc::SOL_CAN_BASE + libc::CAN_ISOTP;
+
+/// Option of a socket with the type `T` of its value
+pub struct SocketOption<T: ?Sized> {
+    level: libc::c_int,
+    name: libc::c_int,
+    value: PhantomData<T>,
+}
+
+impl<T: ?Sized> SocketOption<T> {
+    /// Creates an option which isn't predefined in this module
+    ///
+    /// # Safety
+    ///
+    /// `T` must have the layout of the value the kernel expects for the
+    /// option and every bit pattern written by the kernel must be a valid `T`.
+    pub const unsafe fn new(level: libc::c_int, name: libc::c_int) -> Self {
+        Self {
+            level,
+            name,
+            value: PhantomData,
+        }
+    }
+
+    /// Returns the level of the option, e.g. `SOL_SOCKET`
+    pub fn level(&self) -> libc::c_int {
+        self.level
+    }
+
+    /// Returns the name of the option within its level
+    pub fn name(&self) -> libc::c_int {
+        self.name
+    }
+}
+
+impl<T: ?Sized> Clone for SocketOption<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized> Copy for SocketOption<T> {}
+
+impl<T: ?Sized> std::fmt::Debug for SocketOption<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SocketOption")
+            .field("level", &self.level)
+            .field("name", &self.name)
+            .finish()
+    }
+}
+
+// UNSAFE: The types of the predefined options match the kernel headers
+
+/// Size of the receive buffer in bytes
+pub const SO_RCVBUF: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_SOCKET, libc::SO_RCVBUF) };
+/// Size of the send buffer in bytes
+pub const SO_SNDBUF: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_SOCKET, libc::SO_SNDBUF) };
+/// Time after which a blocking read fails, zero blocks forever
+pub const SO_RCVTIMEO: SocketOption<libc::timeval> =
+    unsafe { SocketOption::new(libc::SOL_SOCKET, libc::SO_RCVTIMEO) };
+/// Time after which a blocking write fails, zero blocks forever
+pub const SO_SNDTIMEO: SocketOption<libc::timeval> =
+    unsafe { SocketOption::new(libc::SOL_SOCKET, libc::SO_SNDTIMEO) };
+/// Mark of the sent packets used for routing and filtering
+pub const SO_MARK: SocketOption<u32> =
+    unsafe { SocketOption::new(libc::SOL_SOCKET, libc::SO_MARK) };
+/// Priority of the sent packets used by the queueing disciplines
+pub const SO_PRIORITY: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_SOCKET, libc::SO_PRIORITY) };
+/// Allows sending to the broadcast address of J1939
+pub const SO_BROADCAST: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_SOCKET, libc::SO_BROADCAST) };
+/// Records the time of reception with nanosecond resolution
+pub const SO_TIMESTAMPNS: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_SOCKET, libc::SO_TIMESTAMPNS) };
+/// Flags of the recorded software and hardware timestamps
+pub const SO_TIMESTAMPING: SocketOption<libc::c_uint> =
+    unsafe { SocketOption::new(libc::SOL_SOCKET, libc::SO_TIMESTAMPING) };
+/// Reports the number of frames dropped by the receive queue
+pub const SO_RXQ_OVFL: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_SOCKET, libc::SO_RXQ_OVFL) };
+
+/// Acceptance filters of a raw socket
+pub const CAN_RAW_FILTER: SocketOption<[libc::can_filter]> =
+    unsafe { SocketOption::new(libc::SOL_CAN_RAW, libc::CAN_RAW_FILTER) };
+/// Classes of the received error frames
+pub const CAN_RAW_ERR_FILTER: SocketOption<libc::can_err_mask_t> =
+    unsafe { SocketOption::new(libc::SOL_CAN_RAW, libc::CAN_RAW_ERR_FILTER) };
+/// Sends the written frames to the other sockets on the host
+pub const CAN_RAW_LOOPBACK: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_CAN_RAW, libc::CAN_RAW_LOOPBACK) };
+/// Receives the frames written by the socket itself
+pub const CAN_RAW_RECV_OWN_MSGS: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_CAN_RAW, libc::CAN_RAW_RECV_OWN_MSGS) };
+/// Enables reading and writing CAN FD frames
+pub const CAN_RAW_FD_FRAMES: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_CAN_RAW, libc::CAN_RAW_FD_FRAMES) };
+/// Requires frames to match all filters instead of any filter
+pub const CAN_RAW_JOIN_FILTERS: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_CAN_RAW, libc::CAN_RAW_JOIN_FILTERS) };
+
+/// Enables reading and writing CAN XL frames
+pub const CAN_RAW_XL_FRAMES: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_CAN_RAW, libc::CAN_RAW_XL_FRAMES) };
+/// Sets how the VCID of CAN XL frames is transmitted and filtered
+pub(crate) const CAN_RAW_XL_VCID_OPTS: SocketOption<CanRawVcidOptions> =
+    unsafe { SocketOption::new(libc::SOL_CAN_RAW, 8) };
+
+/// Minimum separation time between sent consecutive frames in nanoseconds
+pub const CAN_ISOTP_TX_STMIN: SocketOption<u32> = unsafe { SocketOption::new(SOL_CAN_ISOTP, 3) };
+/// Minimum separation time between received consecutive frames in
+/// nanoseconds, faster frames are ignored
+pub const CAN_ISOTP_RX_STMIN: SocketOption<u32> = unsafe { SocketOption::new(SOL_CAN_ISOTP, 4) };
+
+/// Receives all J1939 messages, not only the ones sent to the socket
+pub const SO_J1939_PROMISC: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_CAN_J1939, libc::SO_J1939_PROMISC) };
+/// Priority of the sent J1939 messages
+pub const SO_J1939_SEND_PRIO: SocketOption<libc::c_int> =
+    unsafe { SocketOption::new(libc::SOL_CAN_J1939, libc::SO_J1939_SEND_PRIO) };
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn predefined_options() {
+        assert_eq!(SO_RCVBUF.level(), libc::SOL_SOCKET);
+        assert_eq!(SO_RCVBUF.name(), libc::SO_RCVBUF);
+        assert_eq!(CAN_RAW_FILTER.level(), libc::SOL_CAN_RAW);
+        assert_eq!(CAN_ISOTP_TX_STMIN.level(), 106);
+        assert_eq!(CAN_ISOTP_RX_STMIN.name(), 4);
+    }
+}
@@ -0,0 +1,58 @@
+use crate::isotp::blocking::IsotpConnection;
+
+use super::{
+    parse_response,
+    pdus::{RxPdu, TxPdu},
+    UdsError, DEFAULT_P2_TIMEOUT,
+};
+
+/// UDS client which blocks the calling thread
+///
+/// The client uses the read timeout of the connection to wait for the p2 and
+/// p2* timings of the server, so it must not be changed while the client
+/// exists.
+pub struct UdsClient {
+    pub(super) p2_timing: std::time::Duration,
+    pub(super) p2_extended_timing: std::time::Duration,
+    isotp_conn: IsotpConnection,
+}
+
+impl UdsClient {
+    pub fn new(isotp_conn: IsotpConnection) -> Self {
+        Self {
+            isotp_conn,
+            p2_timing: DEFAULT_P2_TIMEOUT,
+            p2_extended_timing: DEFAULT_P2_TIMEOUT,
+        }
+    }
+
+    pub fn query<Req, Res>(&mut self, req: Req) -> Result<Res, UdsError>
+    where
+        Req: TxPdu,
+        Res: RxPdu,
+    {
+        let data = req.serialize();
+        self.isotp_conn.write(&data)?;
+
+        // Continue receiving while the server needs more time to answer
+        let mut timeout = self.p2_timing;
+        loop {
+            // A zero timeout is rejected by the socket, the server announced
+            // a p2 timing of 0 ms in this case
+            let read_timeout = timeout.max(std::time::Duration::from_millis(1));
+            self.isotp_conn.set_read_timeout(Some(read_timeout))?;
+
+            let mut buffer = [0; 4096];
+            let bytes_read = match self.isotp_conn.read(&mut buffer) {
+                Ok(bytes_read) => Ok(bytes_read),
+                Err(e) if e.kind() == std::io::ErrorKind::TimedOut => Err(UdsError::Timeout),
+                Err(e) => Err(UdsError::TransportError(e)),
+            }?;
+
+            match parse_response::<Req, Res>(&buffer[..bytes_read])? {
+                Some(response) => return Ok(response),
+                None => timeout = self.p2_extended_timing,
+            }
+        }
+    }
+}
@@ -1,7 +1,8 @@
-use nrc::Nrc;
 use pdus::{RxPdu, TxPdu};
 use thiserror::Error;
 
+#[cfg(feature = "blocking")]
+pub(crate) mod blocking;
 mod nrc;
 mod pdus;
 mod services;
@@ -9,6 +10,7 @@ mod services;
 pub use nrc::*;
 pub use services::*;
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 use crate::isotp::IsotpConnection;
 
 const DEFAULT_P2_TIMEOUT: std::time::Duration = std::time::Duration::from_millis(10_000);
@@ -34,12 +36,14 @@ pub enum UdsError {
     Timeout,
 }
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 pub struct UdsClient {
     p2_timing: std::time::Duration,
     p2_extended_timing: std::time::Duration,
     isotp_conn: IsotpConnection,
 }
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 impl UdsClient {
     pub fn new(isotp_conn: IsotpConnection) -> Self {
         Self {
@@ -54,8 +58,6 @@ impl UdsClient {
         Req: TxPdu,
         Res: RxPdu,
     {
-        const RESPONSE_SID_NEGATIVE: u8 = 0x7F;
-
         let data = req.serialize();
         self.isotp_conn.write(&data).await?;
 
@@ -65,76 +67,122 @@ impl UdsClient {
         loop {
             let mut buffer = [0; 4096];
             let bytes_read =
-                match tokio::time::timeout(timeout, self.isotp_conn.read(&mut buffer)).await {
-                    Ok(Ok(bytes_read)) => Ok(bytes_read),
-                    Ok(Err(e)) => Err(UdsError::TransportError(e)),
-                    Err(_) => Err(UdsError::Timeout),
+                match crate::runtime::timeout(timeout, self.isotp_conn.read(&mut buffer)).await {
+                    Some(Ok(bytes_read)) => Ok(bytes_read),
+                    Some(Err(e)) => Err(UdsError::TransportError(e)),
+                    None => Err(UdsError::Timeout),
                 }?;
 
-            let data = &buffer[..bytes_read];
-
-            // Handle all the negative responses
-            if data[0] == RESPONSE_SID_NEGATIVE {
-                // Negative Response: NR SID NRC
-                if data.len() != 3 {
-                    return Err(UdsError::InvalidResponse(format!(
-                        "Response is negative and has invalid length '{}'. Expected length of '3'",
-                        data.len()
-                    )));
-                }
-
-                // For negative responses, the original NRC is echoed back, not the response SID
-                if data[1] != Req::sid() {
-                    return Err(UdsError::InvalidResponse(
-                        "Received response for another SID".to_string(),
-                    ));
-                }
-
-                // UDS allows the server to send out the NRC 0x78 which signals that more time is
-                // needed for the requested operation. Therefor we just wait for the next response.
-                if data[2] == Nrc::RequestCorrectlyReceivedResponsePending.into() {
-                    timeout = self.p2_extended_timing;
-                    continue;
-                }
-
-                // At this point we have some negative response that we should return the the consumer
-                // of the UDS client
-                return Err(UdsError::NegativeResponse(data[2].into()));
+            match parse_response::<Req, Res>(&buffer[..bytes_read])? {
+                Some(response) => return Ok(response),
+                None => timeout = self.p2_extended_timing,
             }
+        }
+    }
+}
 
-            // At this point we only have positive responses
-            // We can do the following basics checks to ensure we have a parsable PDU
-            //  - Check that the PDU is actually the one we expect by checking the SID
-            //  - Ensure that the PDU has an acceptable length
-            if data[0] != Res::sid() {
-                return Err(UdsError::InvalidResponse(format!(
-                    "Expected the SID {:02X} but received SID {:02X}",
-                    Res::sid(),
-                    data[0]
-                )));
-            }
+/// Checks and deserializes the response to a request
+///
+/// Returns `None` if the server needs more time to answer, in this case the
+/// next response must be awaited with the p2* timeout.
+fn parse_response<Req, Res>(data: &[u8]) -> Result<Option<Res>, UdsError>
+where
+    Req: TxPdu,
+    Res: RxPdu,
+{
+    const RESPONSE_SID_NEGATIVE: u8 = 0x7F;
+
+    // Handle all the negative responses
+    if data[0] == RESPONSE_SID_NEGATIVE {
+        // Negative Response: NR SID NRC
+        if data.len() != 3 {
+            return Err(UdsError::InvalidResponse(format!(
+                "Response is negative and has invalid length '{}'. Expected length of '3'",
+                data.len()
+            )));
+        }
 
-            if data.len() < Res::len_min() || data.len() > Res::len_max() {
-                return Err(UdsError::InvalidResponse(format!(
-                    "Expected the length of the response to be between {} and {} but got length of {}",
-                    Res::len_min(),
-                    Res::len_max(),
-                    data.len()
-                )));
-            }
+        // For negative responses, the original NRC is echoed back, not the response SID
+        if data[1] != Req::sid() {
+            return Err(UdsError::InvalidResponse(
+                "Received response for another SID".to_string(),
+            ));
+        }
 
-            // We checked all we can, so just deserialize it
-            let response = Res::deserialize(data);
-            return Ok(response);
+        // UDS allows the server to send out the NRC 0x78 which signals that more time is
+        // needed for the requested operation. Therefor we just wait for the next response.
+        if data[2] == Nrc::RequestCorrectlyReceivedResponsePending.into() {
+            return Ok(None);
         }
+
+        // At this point we have some negative response that we should return the the consumer
+        // of the UDS client
+        return Err(UdsError::NegativeResponse(data[2].into()));
     }
+
+    // At this point we only have positive responses
+    // We can do the following basics checks to ensure we have a parsable PDU
+    //  - Check that the PDU is actually the one we expect by checking the SID
+    //  - Ensure that the PDU has an acceptable length
+    if data[0] != Res::sid() {
+        return Err(UdsError::InvalidResponse(format!(
+            "Expected the SID {:02X} but received SID {:02X}",
+            Res::sid(),
+            data[0]
+        )));
+    }
+
+    if data.len() < Res::len_min() || data.len() > Res::len_max() {
+        return Err(UdsError::InvalidResponse(format!(
+            "Expected the length of the response to be between {} and {} but got length of {}",
+            Res::len_min(),
+            Res::len_max(),
+            data.len()
+        )));
+    }
+
+    // We checked all we can, so just deserialize it
+    Ok(Some(Res::deserialize(data)))
 }
 
 #[cfg(test)]
 mod tests {
+    use super::{
+        parse_response,
+        pdus::tester::{TesterRequest, TesterResponse},
+        Nrc, UdsError,
+    };
+
     #[test]
     fn it_works() {
         let result = 2 + 2;
         assert_eq!(result, 4);
     }
+
+    #[test]
+    fn parses_positive_response() {
+        let response = parse_response::<TesterRequest, TesterResponse>(&[0x7E, 0x00]);
+        assert!(matches!(response, Ok(Some(_))));
+
+        let response = parse_response::<TesterRequest, TesterResponse>(&[0x7E, 0x00, 0x00]);
+        assert!(matches!(response, Err(UdsError::InvalidResponse(_))));
+
+        let response = parse_response::<TesterRequest, TesterResponse>(&[0x50, 0x00]);
+        assert!(matches!(response, Err(UdsError::InvalidResponse(_))));
+    }
+
+    #[test]
+    fn parses_negative_response() {
+        let response = parse_response::<TesterRequest, TesterResponse>(&[0x7F, 0x3E, 0x78]);
+        assert!(matches!(response, Ok(None)));
+
+        let response = parse_response::<TesterRequest, TesterResponse>(&[0x7F, 0x3E, 0x11]);
+        assert!(matches!(
+            response,
+            Err(UdsError::NegativeResponse(Nrc::ServiceNotSupported))
+        ));
+
+        let response = parse_response::<TesterRequest, TesterResponse>(&[0x7F, 0x10, 0x11]);
+        assert!(matches!(response, Err(UdsError::InvalidResponse(_))));
+    }
 }
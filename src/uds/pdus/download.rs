@@ -35,6 +35,7 @@ impl TxPdu for DownloadRequest {
     }
 }
 
+#[allow(dead_code)]
 pub struct DownloadResponse {
     pub length_format_id: u8,
     pub block_len: u16,
@@ -31,6 +31,7 @@ impl<'a> TxPdu for TransferRequest<'a> {
     }
 }
 
+#[allow(dead_code)]
 pub struct TransferResponse {
     pub block_seq_counter: u8,
     pub payload: Vec<u8>,
@@ -83,6 +84,7 @@ impl<'a> TxPdu for TransferExitRequest<'a> {
     }
 }
 
+#[allow(dead_code)]
 pub struct TransferExitResponse {
     pub payload: Vec<u8>,
 }
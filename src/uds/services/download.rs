@@ -1,7 +1,8 @@
-use std::num::Wrapping;
-
-use crate::uds::{pdus, UdsClient, UdsError};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use crate::uds::UdsClient;
+use crate::uds::{pdus, UdsError};
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 impl UdsClient {
     pub async fn download(&mut self, start_addr: u32, data: &[u8]) -> Result<(), UdsError> {
         // Currently only 32bits are supported
@@ -14,15 +15,15 @@ impl UdsClient {
             .await?;
 
         // Step 2: Transfer the data
-        let data_block_len = dl_res.block_len - 15;
-        let mut block_seq_counter = Wrapping(1u8);
+        let data_block_len = data_block_len(&dl_res);
+        // The block sequence counter starts at 1 and wraps around to 0 after 0xFF
+        let block_seq_counters = std::iter::successors(Some(1u8), |c| Some(c.wrapping_add(1)));
         let blocks = data.chunks(data_block_len as usize);
-        for block in blocks {
-            let tr_req = pdus::transfer::TransferRequest::new(block_seq_counter.0, block);
+        for (block_seq_counter, block) in block_seq_counters.zip(blocks) {
+            let tr_req = pdus::transfer::TransferRequest::new(block_seq_counter, block);
             let _tr_res = self
                 .query::<_, pdus::transfer::TransferResponse>(tr_req)
                 .await?;
-            block_seq_counter += 1;
         }
 
         // Step 3: Exit the transfer/download
@@ -34,3 +35,36 @@ impl UdsClient {
         Ok(())
     }
 }
+
+#[cfg(feature = "blocking")]
+impl crate::uds::blocking::UdsClient {
+    /// Downloads the data to the server, starting at the address
+    pub fn download(&mut self, start_addr: u32, data: &[u8]) -> Result<(), UdsError> {
+        // Currently only 32bits are supported
+        assert!(data.len() < u32::MAX as usize);
+
+        // Step 1: Start the download
+        let dl_req = pdus::download::DownloadRequest::new(0x00, start_addr, data.len() as u32);
+        let dl_res = self.query::<_, pdus::download::DownloadResponse>(dl_req)?;
+
+        // Step 2: Transfer the data
+        let data_block_len = data_block_len(&dl_res);
+        let block_seq_counters = std::iter::successors(Some(1u8), |c| Some(c.wrapping_add(1)));
+        let blocks = data.chunks(data_block_len as usize);
+        for (block_seq_counter, block) in block_seq_counters.zip(blocks) {
+            let tr_req = pdus::transfer::TransferRequest::new(block_seq_counter, block);
+            let _tr_res = self.query::<_, pdus::transfer::TransferResponse>(tr_req)?;
+        }
+
+        // Step 3: Exit the transfer/download
+        let ex_req = pdus::transfer::TransferExitRequest::new(&[]);
+        let _eq_res = self.query::<_, pdus::transfer::TransferExitResponse>(ex_req)?;
+
+        Ok(())
+    }
+}
+
+/// Returns the number of data bytes which fit into a transfer request
+fn data_block_len(dl_res: &pdus::download::DownloadResponse) -> u16 {
+    dl_res.block_len - 15
+}
@@ -1,7 +1,9 @@
-use crate::uds::{pdus, UdsClient, UdsError};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use crate::uds::UdsClient;
+use crate::uds::{pdus, UdsError};
 
 /// Represents the different sessions defined in the UDS specification.
-/// Non standard sessions can be represented using [`Session::Other(nrc)`]
+/// Non standard sessions can be represented using [`SessionType::Other`]
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum SessionType {
     Default,
@@ -35,6 +37,7 @@ impl From<SessionType> for u8 {
     }
 }
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 impl UdsClient {
     pub async fn start_session(
         &mut self,
@@ -43,18 +46,26 @@ impl UdsClient {
         let session_id = session_id.into();
         let req_pdu = pdus::dsc::SessionRequest::new(session_id);
         let res_pdu = self.query::<_, pdus::dsc::SessionResponse>(req_pdu).await?;
+        let (p2, p2_extended) = session_timing(session_id, &res_pdu)?;
 
-        // Ensure we actually entered the session, we requested
-        if res_pdu.session_type != session_id {
-            return Err(UdsError::Other(format!(
-                "Requested session id 0x{:02X} but received response for session id 0x{:02X}",
-                session_id, res_pdu.session_type
-            )));
-        }
+        self.p2_timing = p2;
+        self.p2_extended_timing = p2_extended;
 
-        // The p2 timing is represendet in millis, the p2* als 10ms per integer step
-        let p2 = std::time::Duration::from_millis(res_pdu.p2 as u64);
-        let p2_extended = std::time::Duration::from_millis(res_pdu.p2_extended as u64 * 10);
+        Ok((p2, p2_extended))
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl crate::uds::blocking::UdsClient {
+    /// Starts the session and returns its p2 and p2* timings
+    pub fn start_session(
+        &mut self,
+        session_id: impl Into<u8>,
+    ) -> Result<(std::time::Duration, std::time::Duration), UdsError> {
+        let session_id = session_id.into();
+        let req_pdu = pdus::dsc::SessionRequest::new(session_id);
+        let res_pdu = self.query::<_, pdus::dsc::SessionResponse>(req_pdu)?;
+        let (p2, p2_extended) = session_timing(session_id, &res_pdu)?;
 
         self.p2_timing = p2;
         self.p2_extended_timing = p2_extended;
@@ -62,3 +73,23 @@ impl UdsClient {
         Ok((p2, p2_extended))
     }
 }
+
+/// Checks the response and returns the p2 and p2* timings of the session
+fn session_timing(
+    session_id: u8,
+    res_pdu: &pdus::dsc::SessionResponse,
+) -> Result<(std::time::Duration, std::time::Duration), UdsError> {
+    // Ensure we actually entered the session, we requested
+    if res_pdu.session_type != session_id {
+        return Err(UdsError::Other(format!(
+            "Requested session id 0x{:02X} but received response for session id 0x{:02X}",
+            session_id, res_pdu.session_type
+        )));
+    }
+
+    // The p2 timing is represendet in millis, the p2* als 10ms per integer step
+    let p2 = std::time::Duration::from_millis(res_pdu.p2 as u64);
+    let p2_extended = std::time::Duration::from_millis(res_pdu.p2_extended as u64 * 10);
+
+    Ok((p2, p2_extended))
+}
@@ -1,4 +1,6 @@
-use crate::uds::{pdus, UdsClient, UdsError};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use crate::uds::UdsClient;
+use crate::uds::{pdus, UdsError};
 
 /// The different types of reset which can be done by the UDS server
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -34,20 +36,40 @@ impl From<ResetType> for u8 {
     }
 }
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 impl UdsClient {
     pub async fn reset(&mut self, reset_type: impl Into<u8>) -> Result<(), UdsError> {
         let reset_type = reset_type.into();
         let req = pdus::ecu_reset::ResetRequest::new(reset_type);
         let res = self.query::<_, pdus::ecu_reset::ResetResponse>(req).await?;
 
-        // Ensure we have the response for the reset type we actualy requested
-        if res.reset_type != reset_type {
-            return Err(UdsError::InvalidResponse(format!(
-                "Expected response for reset type {} but got for {}",
-                reset_type, res.reset_type
-            )));
-        }
+        check_reset_type(reset_type, &res)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl crate::uds::blocking::UdsClient {
+    /// Resets the ECU
+    pub fn reset(&mut self, reset_type: impl Into<u8>) -> Result<(), UdsError> {
+        let reset_type = reset_type.into();
+        let req = pdus::ecu_reset::ResetRequest::new(reset_type);
+        let res = self.query::<_, pdus::ecu_reset::ResetResponse>(req)?;
+        check_reset_type(reset_type, &res)?;
 
         Ok(())
     }
 }
+
+fn check_reset_type(reset_type: u8, res: &pdus::ecu_reset::ResetResponse) -> Result<(), UdsError> {
+    // Ensure we have the response for the reset type we actualy requested
+    if res.reset_type != reset_type {
+        return Err(UdsError::InvalidResponse(format!(
+            "Expected response for reset type {} but got for {}",
+            reset_type, res.reset_type
+        )));
+    }
+
+    Ok(())
+}
@@ -1,4 +1,6 @@
-use crate::uds::{pdus, UdsClient, UdsError};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use crate::uds::UdsClient;
+use crate::uds::{pdus, UdsError};
 
 #[derive(Debug, Clone, Copy)]
 pub enum RoutineAction {
@@ -29,6 +31,7 @@ impl From<RoutineAction> for u8 {
     }
 }
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 impl UdsClient {
     pub async fn control_routine(
         &mut self,
@@ -42,20 +45,51 @@ impl UdsClient {
         let req = pdus::routine::RoutineRequest::new(action, routine_id, params);
         let res = self.query::<_, pdus::routine::RoutineResponse>(req).await?;
 
-        if res.control != action {
-            return Err(UdsError::InvalidResponse(format!(
-                "Expected response for control action 0x{:02X} but got {:02X}",
-                action, res.control
-            )));
-        }
+        check_routine(action, routine_id, &res)?;
 
-        if res.routine_id != routine_id {
-            return Err(UdsError::InvalidResponse(format!(
-                "Expected response for routine 0x{:04X} but got {:04X}",
-                routine_id, res.routine_id
-            )));
-        }
+        Ok(res.params)
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl crate::uds::blocking::UdsClient {
+    /// Controls the routine and returns the parameters of the response
+    pub fn control_routine(
+        &mut self,
+        action: RoutineAction,
+        routine_id: impl Into<u16>,
+        params: &[u8],
+    ) -> Result<Vec<u8>, UdsError> {
+        let routine_id = routine_id.into();
+        let action: u8 = action.into();
+
+        let req = pdus::routine::RoutineRequest::new(action, routine_id, params);
+        let res = self.query::<_, pdus::routine::RoutineResponse>(req)?;
+        check_routine(action, routine_id, &res)?;
 
         Ok(res.params)
     }
 }
+
+/// Ensures the response belongs to the requested action and routine
+fn check_routine(
+    action: u8,
+    routine_id: u16,
+    res: &pdus::routine::RoutineResponse,
+) -> Result<(), UdsError> {
+    if res.control != action {
+        return Err(UdsError::InvalidResponse(format!(
+            "Expected response for control action 0x{:02X} but got {:02X}",
+            action, res.control
+        )));
+    }
+
+    if res.routine_id != routine_id {
+        return Err(UdsError::InvalidResponse(format!(
+            "Expected response for routine 0x{:04X} but got {:04X}",
+            routine_id, res.routine_id
+        )));
+    }
+
+    Ok(())
+}
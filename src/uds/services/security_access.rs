@@ -1,5 +1,8 @@
-use crate::uds::{pdus, UdsClient, UdsError};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use crate::uds::UdsClient;
+use crate::uds::{pdus, UdsError};
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 impl UdsClient {
     pub async fn unlock(
         &mut self,
@@ -7,28 +10,14 @@ impl UdsClient {
         seed_data: &[u8],
         key_algo: impl FnOnce(&[u8]) -> Result<Vec<u8>, String>,
     ) -> Result<(), UdsError> {
-        // The unlock security level must always be an odd number as
-        // the key request is the next even number (defined in standard)
         let sec_level = sec_level.into();
-        if sec_level % 2 != 1 {
-            return Err(UdsError::InvalidRequest(format!(
-                "The security level must be an even number but is {}",
-                sec_level
-            )));
-        }
+        check_unlock_level(sec_level)?;
 
         let seed_req = pdus::security_access::SeedRequest::new(sec_level, seed_data);
         let seed_res = self
             .query::<_, pdus::security_access::SeedResponse>(seed_req)
             .await?;
-
-        // Ensure we have the response for the security level we actualy requested
-        if seed_res.sec_level != sec_level {
-            return Err(UdsError::InvalidResponse(format!(
-                "Expected response for security level {} but got for {}",
-                sec_level, seed_res.sec_level
-            )));
-        }
+        check_sec_level(sec_level, seed_res.sec_level)?;
 
         let key = key_algo(&seed_res.seed)
             .map_err(|e| UdsError::Other(format!("Failed to generate key: {}", e)))?;
@@ -38,15 +27,61 @@ impl UdsClient {
         let key_res = self
             .query::<_, pdus::security_access::KeyResponse>(key_req)
             .await?;
+        check_sec_level(key_sec_level, key_res.sec_level)?;
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "blocking")]
+impl crate::uds::blocking::UdsClient {
+    /// Unlocks the security level using the key calculated from the seed
+    pub fn unlock(
+        &mut self,
+        sec_level: impl Into<u8>,
+        seed_data: &[u8],
+        key_algo: impl FnOnce(&[u8]) -> Result<Vec<u8>, String>,
+    ) -> Result<(), UdsError> {
+        let sec_level = sec_level.into();
+        check_unlock_level(sec_level)?;
 
-        // Ensure we have the response for the security level we actualy requested
-        if key_res.sec_level != key_sec_level {
-            return Err(UdsError::InvalidResponse(format!(
-                "Expected response for security level {} but got for {}",
-                key_sec_level, key_res.sec_level
-            )));
-        }
+        let seed_req = pdus::security_access::SeedRequest::new(sec_level, seed_data);
+        let seed_res = self.query::<_, pdus::security_access::SeedResponse>(seed_req)?;
+        check_sec_level(sec_level, seed_res.sec_level)?;
+
+        let key = key_algo(&seed_res.seed)
+            .map_err(|e| UdsError::Other(format!("Failed to generate key: {}", e)))?;
+
+        let key_sec_level = sec_level + 1;
+        let key_req = pdus::security_access::KeyRequest::new(key_sec_level, &key);
+        let key_res = self.query::<_, pdus::security_access::KeyResponse>(key_req)?;
+        check_sec_level(key_sec_level, key_res.sec_level)?;
 
         Ok(())
     }
 }
+
+fn check_unlock_level(sec_level: u8) -> Result<(), UdsError> {
+    // The unlock security level must always be an odd number as
+    // the key request is the next even number (defined in standard)
+    if sec_level % 2 != 1 {
+        return Err(UdsError::InvalidRequest(format!(
+            "The security level must be an even number but is {}",
+            sec_level
+        )));
+    }
+
+    Ok(())
+}
+
+fn check_sec_level(sec_level: u8, res_sec_level: u8) -> Result<(), UdsError> {
+    // Ensure we have the response for the security level we actualy requested
+    if res_sec_level != sec_level {
+        return Err(UdsError::InvalidResponse(format!(
+            "Expected response for security level {} but got for {}",
+            sec_level, res_sec_level
+        )));
+    }
+
+    Ok(())
+}
@@ -1,5 +1,8 @@
-use crate::uds::{pdus, UdsClient, UdsError};
+#[cfg(any(feature = "tokio", feature = "async-io"))]
+use crate::uds::UdsClient;
+use crate::uds::{pdus, UdsError};
 
+#[cfg(any(feature = "tokio", feature = "async-io"))]
 impl UdsClient {
     pub async fn tester_present(&mut self) -> Result<(), UdsError> {
         let req = pdus::tester::TesterRequest::new();
@@ -7,3 +10,13 @@ impl UdsClient {
         Ok(())
     }
 }
+
+#[cfg(feature = "blocking")]
+impl crate::uds::blocking::UdsClient {
+    /// Keeps the current session of the server active
+    pub fn tester_present(&mut self) -> Result<(), UdsError> {
+        let req = pdus::tester::TesterRequest::new();
+        let _ = self.query::<_, pdus::tester::TesterResponse>(req)?;
+        Ok(())
+    }
+}